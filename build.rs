@@ -9,6 +9,18 @@ use path_absolutize::*;
 #[path = "src/cli.rs"]
 mod cli;
 
+#[cfg(feature = "cli")]
+#[path = "src/format.rs"]
+pub mod format;
+
+#[cfg(feature = "cli")]
+#[path = "src/lang.rs"]
+pub mod lang;
+
+#[cfg(feature = "cli")]
+#[path = "src/metadata.rs"]
+pub mod metadata;
+
 #[cfg(feature = "cli")]
 #[path = "src/page.rs"]
 pub mod page;
@@ -46,8 +58,9 @@ fn main() -> std::io::Result<()> {
             )?;
         }
 
-        // Re-run if the cli or page files change
+        // Re-run if the cli, format, or page files change
         println!("cargo:rerun-if-changed=src/cli.rs");
+        println!("cargo:rerun-if-changed=src/format.rs");
         println!("cargo:rerun-if-changed=src/page.rs");
     }
 
@@ -0,0 +1,366 @@
+//! Sheets listed in a TOML manifest for the `batch` subcommand
+//!
+//! Each `[[sheet]]` table has the input file and the long names of the command
+//! line options for the sheet, like the config file. The options at the top
+//! level apply to every sheet, e.g.:
+//!
+//! ```toml
+//! page-size = "letter"
+//! metadata = ["date", "sheet-id"]
+//!
+//! [[sheet]]
+//! input = "secrets/database.txt"
+//! output = "sheets/database.pdf"
+//! title = "Database root password"
+//! recipients-file = "keyholders.txt"
+//!
+//! [[sheet]]
+//! input = "secrets/api.txt"
+//! output = "sheets/api.pdf"
+//! title = "API signing key"
+//! passphrase-env = "API_PASSPHRASE"
+//! ```
+//!
+//! Sheets without recipients are encrypted with the passphrase in the
+//! environment variable given with `passphrase-env`, the first line of the file
+//! given with `passphrase-file`, or a passphrase shared by all the sheets.
+//!
+//! Relative paths in the manifest are relative to the directory of the
+//! manifest, not the current directory.
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use clap::Parser;
+
+use crate::cli::Args;
+use crate::config::{option_args, OptionError};
+
+/// Options that apply to the whole batch and can't differ between the sheets
+const BATCH_OPTIONS: [&str; 3] = ["title-font", "code-font", "fallback-font"];
+
+/// Where the passphrase of a sheet comes from
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum PassphraseSource {
+    /// The passphrase shared by the sheets, from the PAPERAGE_PASSPHRASE
+    /// environment variable or a prompt
+    Shared,
+    /// The environment variable with the given name
+    Env(String),
+    /// The first line of the file
+    File(PathBuf),
+}
+
+/// A sheet of the manifest
+#[derive(Debug)]
+pub struct Sheet {
+    /// The options of the sheet, including the input file
+    pub args: Args,
+
+    /// Where the passphrase comes from, if the sheet has no recipients
+    pub passphrase: PassphraseSource,
+}
+
+/// The manifest could not be read or has invalid sheets
+#[derive(Debug)]
+pub enum BatchError {
+    /// The manifest could not be read
+    Read(PathBuf, std::io::Error),
+    /// The manifest isn't valid TOML
+    Parse(PathBuf, String),
+    /// The manifest has no `[[sheet]]` tables
+    NoSheets(PathBuf),
+    /// The options of the sheet with the given number are invalid
+    InvalidSheet(usize, String),
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BatchError::Read(path, error) => {
+                write!(f, "Failed to read the manifest {}: {error}", path.display())
+            }
+            BatchError::Parse(path, msg) => {
+                write!(f, "Failed to parse the manifest {}: {msg}", path.display())
+            }
+            BatchError::NoSheets(path) => {
+                write!(f, "There are no [[sheet]] tables in {}", path.display())
+            }
+            BatchError::InvalidSheet(index, msg) => write!(f, "Sheet {index}: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/// Read the sheets from the manifest file
+pub fn read_manifest(path: &Path) -> Result<Vec<Sheet>, BatchError> {
+    let text =
+        std::fs::read_to_string(path).map_err(|e| BatchError::Read(path.to_path_buf(), e))?;
+
+    parse_manifest(path, &text)
+}
+
+/// Parse the sheets from the text of the manifest
+pub fn parse_manifest(path: &Path, text: &str) -> Result<Vec<Sheet>, BatchError> {
+    let mut defaults = toml::from_str::<toml::value::Table>(text)
+        .map_err(|e| BatchError::Parse(path.to_path_buf(), e.to_string()))?;
+    let sheets = match defaults.remove("sheet") {
+        Some(toml::Value::Array(sheets)) if !sheets.is_empty() => sheets,
+        Some(_) => {
+            return Err(BatchError::Parse(
+                path.to_path_buf(),
+                "sheet must be an array of tables".to_string(),
+            ))
+        }
+        None => return Err(BatchError::NoSheets(path.to_path_buf())),
+    };
+
+    let dir = path.parent().unwrap_or(Path::new(""));
+    sheets
+        .into_iter()
+        .enumerate()
+        .map(|(index, sheet)| match sheet {
+            toml::Value::Table(sheet) => parse_sheet(&defaults, sheet, dir)
+                .map_err(|msg| BatchError::InvalidSheet(index + 1, msg)),
+            _ => Err(BatchError::InvalidSheet(
+                index + 1,
+                "not a table".to_string(),
+            )),
+        })
+        .collect()
+}
+
+/// Parse the options of a sheet on top of the options for every sheet,
+/// resolving the relative paths against `dir`
+fn parse_sheet(
+    defaults: &toml::value::Table,
+    sheet: toml::value::Table,
+    dir: &Path,
+) -> Result<Sheet, String> {
+    if let Some(key) = BATCH_OPTIONS.iter().find(|key| sheet.contains_key(**key)) {
+        return Err(format!("{key} can only be set for the whole batch"));
+    }
+
+    let mut options = defaults.clone();
+    let sheet_passphrase =
+        sheet.contains_key("passphrase-env") || sheet.contains_key("passphrase-file");
+    if sheet_passphrase {
+        options.remove("passphrase-env");
+        options.remove("passphrase-file");
+    }
+    options.extend(sheet);
+
+    let input = match options.remove("input") {
+        Some(toml::Value::String(input)) => input,
+        Some(_) => return Err("Invalid value for input".to_string()),
+        None => return Err("The sheet has no input file".to_string()),
+    };
+    let passphrase = match (
+        options.remove("passphrase-env"),
+        options.remove("passphrase-file"),
+    ) {
+        (None, None) => PassphraseSource::Shared,
+        (Some(toml::Value::String(name)), None) => PassphraseSource::Env(name),
+        (None, Some(toml::Value::String(path))) => PassphraseSource::File(dir.join(path)),
+        (Some(_), Some(_)) => {
+            return Err("passphrase-env and passphrase-file can't both be set".to_string())
+        }
+        (Some(_), None) => return Err("Invalid value for passphrase-env".to_string()),
+        (None, Some(_)) => return Err("Invalid value for passphrase-file".to_string()),
+    };
+
+    let args = option_args(options, None).map_err(|error| match error {
+        OptionError::Unknown(key) => format!("Unknown option: {key}"),
+        OptionError::InvalidValue(key) => format!("Invalid value for {key}"),
+    })?;
+    let mut args = Args::try_parse_from(
+        ["paper-age".into()]
+            .into_iter()
+            .chain(args)
+            .chain(["--".into(), input.into()]),
+    )
+    .map_err(|error| {
+        // Only the first line of the error, without the usage
+        let msg = error.to_string();
+        let line = msg.lines().next().unwrap_or_default();
+        line.trim_start_matches("error: ").to_string()
+    })?;
+
+    resolve_paths(&mut args, dir);
+
+    let has_recipients = !args.recipient.is_empty()
+        || !args.ssh_recipient.is_empty()
+        || !args.recipients_file.is_empty();
+    if has_recipients && sheet_passphrase {
+        return Err("A passphrase can't be combined with recipients".to_string());
+    }
+
+    // The passphrase for every sheet doesn't apply to the sheets with recipients
+    let passphrase = if has_recipients {
+        PassphraseSource::Shared
+    } else {
+        passphrase
+    };
+
+    Ok(Sheet { args, passphrase })
+}
+
+/// Resolve the relative paths of the sheet against `dir`, leaving `-` for
+/// standard output as is
+fn resolve_paths(args: &mut Args, dir: &Path) {
+    let resolve = |path: &mut PathBuf| {
+        if path.as_os_str() != "-" {
+            *path = dir.join(&*path);
+        }
+    };
+
+    args.input.iter_mut().for_each(resolve);
+    args.output.iter_mut().for_each(resolve);
+    args.recipients_file.iter_mut().for_each(resolve);
+    args.title_font.iter_mut().for_each(resolve);
+    args.code_font.iter_mut().for_each(resolve);
+    args.fallback_font.iter_mut().for_each(resolve);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MANIFEST: &str = r#"
+page-size = "letter"
+passphrase-env = "TEAM_PASSPHRASE"
+
+[[sheet]]
+input = "database.txt"
+output = "database.pdf"
+title = "Database"
+
+[[sheet]]
+input = "api.txt"
+output = "api.pdf"
+title = "API key"
+passphrase-file = "api-passphrase.txt"
+page-size = "a4"
+
+[[sheet]]
+input = "root.txt"
+output = "root.pdf"
+passphrase-env = "ROOT_PASSPHRASE"
+split = true
+
+[[sheet]]
+input = "-r.txt"
+recipient = ["age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p"]
+passphrase-env = "UNUSED"
+"#;
+
+    fn parse(text: &str) -> Result<Vec<Sheet>, BatchError> {
+        parse_manifest(Path::new("manifest.toml"), text)
+    }
+
+    #[test]
+    fn test_parse_manifest() {
+        let error = parse(MANIFEST).unwrap_err();
+        assert!(matches!(error, BatchError::InvalidSheet(4, _)));
+        assert_eq!(
+            error.to_string(),
+            "Sheet 4: A passphrase can't be combined with recipients"
+        );
+
+        let sheets = parse(&MANIFEST.replace("passphrase-env = \"UNUSED\"\n", "")).unwrap();
+        assert_eq!(sheets.len(), 4);
+        assert_eq!(sheets[0].args.title, "Database");
+        assert_eq!(sheets[0].args.page_size, crate::page::PageSize::Letter);
+        assert_eq!(sheets[0].args.input, Some(PathBuf::from("database.txt")));
+        assert_eq!(sheets[0].args.output, Some(PathBuf::from("database.pdf")));
+        assert_eq!(
+            sheets[0].passphrase,
+            PassphraseSource::Env("TEAM_PASSPHRASE".to_string())
+        );
+        assert_eq!(sheets[1].args.page_size, crate::page::PageSize::A4);
+        assert_eq!(
+            sheets[1].passphrase,
+            PassphraseSource::File(PathBuf::from("api-passphrase.txt"))
+        );
+        assert_eq!(sheets[2].args.title, "PaperAge");
+        assert!(sheets[2].args.split);
+        assert_eq!(
+            sheets[2].passphrase,
+            PassphraseSource::Env("ROOT_PASSPHRASE".to_string())
+        );
+        // An input file that looks like an option
+        assert_eq!(sheets[3].args.input, Some(PathBuf::from("-r.txt")));
+        assert_eq!(sheets[3].args.recipient.len(), 1);
+        assert_eq!(sheets[3].passphrase, PassphraseSource::Shared);
+
+        let sheets = parse("[[sheet]]\ninput = \"a.txt\"").unwrap();
+        assert_eq!(sheets[0].passphrase, PassphraseSource::Shared);
+    }
+
+    #[test]
+    fn test_relative_paths() {
+        let text = r#"
+title-font = "fonts/Inter.ttf"
+
+[[sheet]]
+input = "secrets/db.txt"
+output = "/tmp/db.pdf"
+recipients-file = ["keyholders.txt"]
+
+[[sheet]]
+input = "api.txt"
+output = "-"
+passphrase-file = "api-passphrase.txt"
+"#;
+        let sheets = parse_manifest(Path::new("ops/q3.toml"), text).unwrap();
+        assert_eq!(
+            sheets[0].args.input,
+            Some(PathBuf::from("ops/secrets/db.txt"))
+        );
+        assert_eq!(sheets[0].args.output, Some(PathBuf::from("/tmp/db.pdf")));
+        assert_eq!(
+            sheets[0].args.recipients_file,
+            vec![PathBuf::from("ops/keyholders.txt")]
+        );
+        assert_eq!(
+            sheets[0].args.title_font,
+            Some(PathBuf::from("ops/fonts/Inter.ttf"))
+        );
+        assert_eq!(sheets[1].args.output, Some(PathBuf::from("-")));
+        assert_eq!(
+            sheets[1].passphrase,
+            PassphraseSource::File(PathBuf::from("ops/api-passphrase.txt"))
+        );
+    }
+
+    #[test]
+    fn test_invalid_manifest() {
+        assert!(matches!(parse("title = "), Err(BatchError::Parse(..))));
+        assert!(matches!(
+            parse("title = \"No sheets\""),
+            Err(BatchError::NoSheets(_))
+        ));
+        assert!(matches!(parse("sheet = 1"), Err(BatchError::Parse(..))));
+
+        let error = parse("[[sheet]]\ntitle = \"No input\"").unwrap_err();
+        assert_eq!(error.to_string(), "Sheet 1: The sheet has no input file");
+
+        let error = parse("[[sheet]]\ninput = \"a.txt\"\n[[sheet]]\ninput = \"b.txt\"\ncolour = 1")
+            .unwrap_err();
+        assert_eq!(error.to_string(), "Sheet 2: Unknown option: colour");
+
+        let error = parse("[[sheet]]\ninput = \"a.txt\"\ncode-font = \"ocr-b.otf\"").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Sheet 1: code-font can only be set for the whole batch"
+        );
+
+        let error = parse("[[sheet]]\ninput = \"a.txt\"\npage-size = \"a0\"").unwrap_err();
+        assert!(matches!(error, BatchError::InvalidSheet(1, msg) if msg.contains("a0")));
+
+        let error =
+            parse("[[sheet]]\ninput = \"a.txt\"\npassphrase-env = \"A\"\npassphrase-file = \"a\"")
+                .unwrap_err();
+        assert!(matches!(error, BatchError::InvalidSheet(1, _)));
+    }
+}
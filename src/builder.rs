@@ -2,201 +2,368 @@
 
 use std::io::Write;
 
-use log::{debug, trace};
-use printpdf::{
-    Color, DateTime, Line, LineDashPattern, LinePoint, Mm, Op, PaintMode, ParsedFont, PdfDocument,
-    PdfFontHandle, PdfPage, PdfSaveOptions, Point, Pt, Rect, Rgb, TextItem, WindingOrder,
-};
-
+use log::{debug, info, trace, warn};
+use printpdf::{DateTime, LineDashPattern, Mm, Point, Pt};
+use rand::Rng;
+
+use crate::checksums;
+use crate::encryption::{self, EncryptionMode, Recipient};
+use crate::format::Format;
+use crate::lang::{self, Language};
+use crate::metadata::{Metadata, MetadataField};
 use crate::page::*;
-
+use crate::split::{self, Part};
+
+use canvas::Canvas;
+use layout::{Element, Font};
+use metrics::Fonts;
+use pdf::PdfCanvas;
+use raster::PngCanvas;
+use svg::SvgCanvas;
+
+pub mod canvas;
+pub mod layout;
+pub mod metrics;
+pub mod pdf;
 pub mod qrcode_ops;
+pub mod raster;
+pub mod svg;
 
 /// PaperAge version
 pub const VERSION: Option<&str> = option_env!("CARGO_PKG_VERSION");
 
-/// Font width / height = 3 / 5
-const FONT_RATIO: f32 = 3.0 / 5.0;
+/// Line height bounds for the list of recipients below the QR code
+const MAX_RECIPIENT_LINE_HEIGHT: Mm = Mm(3.6);
+const MIN_RECIPIENT_LINE_HEIGHT: Mm = Mm(2.8);
+
+/// Maximum number of columns in the list of recipients
+const MAX_RECIPIENT_COLUMNS: usize = 3;
+
+/// Number of QR codes per page when the ciphertext is split into parts
+const PARTS_PER_PAGE: usize = 2;
+
+/// Horizontal gap between the QR codes of split ciphertexts
+const PART_GAP: Mm = Mm(10.0);
+
+/// Font sizes of the title and the footer on pages at least as large as A4
+const TITLE_FONT_SIZE: f32 = 14.0;
+const FOOTER_FONT_SIZE: f32 = 13.0;
+
+/// Largest font size of the PEM text and its line height relative to the
+/// font size
+const PEM_FONT_SIZE: f32 = 13.0;
+const PEM_LINE_SPACING: f32 = 15.0 / 13.0;
+
+/// Shortest placeholder line that is drawn after the notes field label
+const MIN_NOTES_LINE_LENGTH: Mm = Mm(20.0);
+
+/// Size of an ID-1 wallet card, like a credit card
+const CARD_WIDTH: Mm = Mm(85.6);
+const CARD_HEIGHT: Mm = Mm(54.0);
+
+/// Space between the edges of a wallet card and its contents
+const CARD_PADDING: Mm = Mm(4.0);
+
+/// Smallest QR code module size that scans reliably on a wallet card
+const MIN_CARD_MODULE_SIZE: Mm = Mm(0.5);
+
+/// Length of the crop marks around wallet cards and their gap to the cards
+const CROP_MARK_LENGTH: Mm = Mm(4.0);
+const CROP_MARK_GAP: Mm = Mm(1.0);
+
+/// Font size of the instructions page on pages at least as large as A4, and
+/// the line height relative to the font size
+const INSTRUCTIONS_FONT_SIZE: f32 = 11.0;
+const INSTRUCTIONS_LINE_SPACING: f32 = 1.4;
+
+/// Characters of the random sheet identifiers, without the easily confused
+/// I, L, O, and U (Crockford's base32)
+const SHEET_ID_ALPHABET: &[u8] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+
+/// Default resolution of PNG output
+pub const DEFAULT_DPI: u32 = 300;
 
 const CODE_FONT_BYTES: &[u8] = include_bytes!("assets/fonts/IBMPlexMono-Regular.ttf");
 const TITLE_FONT_BYTES: &[u8] = include_bytes!("assets/fonts/IBMPlexMono-Medium.ttf");
 
-/// Container for all the data required to insert elements into the PDF
+/// Container for all the data required to lay out the pages
 pub struct Document {
-    /// The printpdf PDF document
-    pub doc: PdfDocument,
-
-    /// Operations to perform on the page
-    ops: Vec<Op>,
+    /// Elements on the current page
+    elements: Vec<Element>,
 
-    /// The medium weight font handle
-    pub title_font: PdfFontHandle,
-
-    /// The regular weight font handle
-    pub code_font: PdfFontHandle,
+    /// Elements of the finished pages before the current page
+    pages: Vec<Vec<Element>>,
 
     /// Page size
     pub page_size: PageSize,
 
+    /// Page orientation
+    pub orientation: Orientation,
+
     /// Document title
     pub title: String,
+
+    /// Split the ciphertext across several QR codes and pages if it doesn't
+    /// fit in a single QR code
+    pub split: bool,
+
+    /// Line of text below the title, e.g. the share index and set identifier
+    pub subtitle: Option<String>,
+
+    /// Print line numbers and checksums on the lines of the PEM text
+    pub line_checksums: bool,
+
+    /// Tile wallet cards with only the QR code and a few details on the page
+    /// instead of the full page layout
+    pub wallet_card: bool,
+
+    /// Maximum number of wallet cards on the page. Defaults to as many as fit.
+    pub card_count: Option<usize>,
+
+    /// Number of identical panels on the page, separated by cut lines
+    pub copies: usize,
+
+    /// Output format
+    pub format: Format,
+
+    /// Resolution of PNG output in dots per inch
+    pub dpi: u32,
+
+    /// Fonts for measuring and setting the text, including the optional
+    /// fallback font
+    pub fonts: Fonts,
+
+    /// Add a page with step by step instructions for restoring the backup
+    pub instructions: bool,
+
+    /// Creation date of the document, printed on the instructions page and
+    /// the metadata line and stored in the PDF metadata. Defaults to
+    /// [`creation_date`].
+    pub created: DateTime,
+
+    /// Language of the fixed text on the page
+    pub language: Language,
+
+    /// Fields of the metadata line below the title
+    pub metadata: Metadata,
+
+    /// Identifier of the sheet on the metadata line. Defaults to a random
+    /// identifier.
+    pub sheet_id: String,
+
+    /// Texts of the metadata fields, set from the ciphertext when the pages
+    /// are laid out
+    metadata_texts: Vec<String>,
 }
 
 impl Document {
-    /// Initialize the PDF with default dimensions and the required fonts. Also
-    /// sets the title and the producer in the PDF metadata.
+    /// Initialize an empty document with the given title and page size, and
+    /// the embedded IBM Plex Mono fonts
     pub fn new(title: String, page_size: PageSize) -> Result<Document, Box<dyn std::error::Error>> {
-        debug!("Initializing PDF");
-
-        let dimensions = page_size.dimensions();
-
-        let mut doc = PdfDocument::new(&title);
-
-        let producer = format!("PaperAge v{}", VERSION.unwrap_or("0.0.0"));
-        let now = DateTime::now();
-        doc.metadata.info.producer = producer;
-        doc.metadata.info.creation_date = now;
-        doc.metadata.info.modification_date = now;
-
-        let mut warnings = Vec::new();
-
-        let code_parsed = ParsedFont::from_bytes(CODE_FONT_BYTES, 0, &mut warnings)
-            .ok_or("Failed to parse code font")?;
-        let code_font_id = doc.add_font(&code_parsed);
-        let code_font = PdfFontHandle::External(code_font_id);
-
-        let title_parsed = ParsedFont::from_bytes(TITLE_FONT_BYTES, 0, &mut warnings)
-            .ok_or("Failed to parse title font")?;
-        let title_font_id = doc.add_font(&title_parsed);
-        let title_font = PdfFontHandle::External(title_font_id);
-
-        let ops = vec![
-            // White background
-            Op::SetFillColor {
-                col: Color::Rgb(Rgb::new(1.0, 1.0, 1.0, None)),
-            },
-            Op::DrawPolygon {
-                polygon: Rect {
-                    x: Pt(0.0),
-                    y: Pt(0.0),
-                    width: dimensions.width.into_pt(),
-                    height: dimensions.height.into_pt(),
-                    mode: Some(PaintMode::Fill),
-                    winding_order: Some(WindingOrder::NonZero),
-                }
-                .to_polygon(),
-            },
-            // Reset fill color to black for text and QR code
-            Op::SetFillColor {
-                col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
-            },
-        ];
+        Document::with_fonts(title, page_size, Fonts::default())
+    }
+
+    /// Initialize an empty document with the given title, page size, and
+    /// fonts
+    pub fn with_fonts(
+        title: String,
+        page_size: PageSize,
+        fonts: Fonts,
+    ) -> Result<Document, Box<dyn std::error::Error>> {
+        debug!("Initializing document");
 
         Ok(Document {
-            doc,
-            ops,
-            title_font,
-            code_font,
+            elements: vec![],
+            pages: vec![],
             page_size,
+            orientation: Orientation::Portrait,
             title: title.clone(),
+            split: false,
+            subtitle: None,
+            line_checksums: false,
+            wallet_card: false,
+            card_count: None,
+            copies: 1,
+            format: Format::Pdf,
+            dpi: DEFAULT_DPI,
+            fonts,
+            instructions: false,
+            created: creation_date(),
+            language: Language::default(),
+            metadata: Metadata::default(),
+            sheet_id: random_sheet_id(&mut rand::thread_rng()),
+            metadata_texts: vec![],
         })
     }
 
+    /// Finish the current page and start a new, blank page
+    pub fn new_page(&mut self) {
+        debug!("Starting a new page");
+
+        let elements = std::mem::take(&mut self.elements);
+        self.pages.push(elements);
+    }
+
     /// Insert the given title at the top of the PDF
     pub fn insert_title_text(&mut self, title: String) {
         debug!("Inserting title: {}", title.as_str());
 
-        let font_size = 14.0;
-
         // Align the title with the QR code if the title is narrower than the QR code
+        let width = Mm::from(self.fonts.text_width(
+            &title,
+            Font::Title,
+            Pt(self.scaled(TITLE_FONT_SIZE)),
+        ));
         let margin = {
-            if title.len() <= 37 {
-                self.page_size.qrcode_left_edge()
+            if width <= self.qrcode_size() {
+                self.qrcode_left_edge()
             } else {
-                self.page_size.dimensions().margin
+                self.panel().margin
             }
         };
 
-        let y = self.page_size.dimensions().height
-            - self.page_size.dimensions().margin
-            - Mm::from(Pt(font_size));
+        self.insert_title_text_at(title, margin);
+    }
 
-        self.ops.push(Op::StartTextSection);
-        self.ops.push(Op::SetFillColor {
-            col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
-        });
-        self.ops.push(Op::SetTextCursor {
-            pos: Point::new(margin, y),
-        });
-        self.ops.push(Op::SetFont {
-            font: self.title_font.clone(),
-            size: Pt(font_size),
-        });
-        self.ops.push(Op::ShowText {
-            items: vec![TextItem::Text(title)],
-        });
-        self.ops.push(Op::EndTextSection);
+    /// Insert the title and the optional subtitle at the top of the page,
+    /// starting from the given left edge. Long titles are set smaller to fit
+    /// between the left edge and the right margin.
+    fn insert_title_text_at(&mut self, title: String, left: Mm) {
+        let panel = self.panel();
+        let max_width = (panel.width - panel.margin - left).into_pt();
+        let font_size = self
+            .fonts
+            .fit_font_size(
+                &title,
+                Font::Title,
+                Pt(self.scaled(TITLE_FONT_SIZE)),
+                max_width,
+            )
+            .0;
+        let subtitle_font_size = self
+            .subtitle
+            .as_deref()
+            .map(|subtitle| {
+                self.fonts
+                    .fit_font_size(subtitle, Font::Code, Pt(self.scaled(9.0)), max_width)
+                    .0
+            })
+            .unwrap_or_default();
+        let subtitle_line_height = Mm(self.scaled(4.5));
+        let metadata_line =
+            (!self.metadata_texts.is_empty()).then(|| self.metadata_texts.join(" · "));
+        let metadata_font_size = metadata_line
+            .as_deref()
+            .map(|line| {
+                self.fonts
+                    .fit_font_size(line, Font::Code, Pt(self.scaled(7.0)), max_width)
+                    .0
+            })
+            .unwrap_or_default();
+        let metadata_line_height = Mm(self.scaled(3.2));
+
+        let mut y = self.panel().title_baseline(Pt(font_size));
+
+        // Move the title up to keep the subtitle and the metadata line clear
+        // of the QR code
+        if self.subtitle.is_some() || metadata_line.is_some() {
+            y += Mm(self.scaled(4.0));
+        }
+        if self.subtitle.is_some() && metadata_line.is_some() {
+            y += Mm(self.scaled(2.5));
+        }
+
+        self.push_text(title, Font::Title, font_size, Point::new(left, y));
+
+        let mut line_y = y - subtitle_line_height;
+        if let Some(subtitle) = self.subtitle.clone() {
+            debug!("Inserting subtitle: {subtitle}");
+            self.push_text(
+                subtitle,
+                Font::Code,
+                subtitle_font_size,
+                Point::new(left, line_y),
+            );
+            line_y -= metadata_line_height;
+        }
+
+        if let Some(metadata_line) = metadata_line {
+            debug!("Inserting metadata: {metadata_line}");
+            self.push_text(
+                metadata_line,
+                Font::Code,
+                metadata_font_size,
+                Point::new(left, line_y),
+            );
+        }
+    }
+
+    /// The texts of the fields of the metadata line for the ciphertext
+    fn metadata_texts(&self, encrypted: &str) -> Vec<String> {
+        MetadataField::ALL
+            .into_iter()
+            .filter(|field| self.metadata.contains(*field))
+            .map(|field| match field {
+                MetadataField::Date => {
+                    let DateTime { date, time, .. } = self.created;
+                    format!(
+                        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+                        date.year, date.month, date.day, time.hour, time.minute
+                    )
+                }
+                MetadataField::Version => format!("PaperAge v{}", VERSION.unwrap_or("0.0.0")),
+                MetadataField::Sha256 => {
+                    format!(
+                        "SHA-256 {}…",
+                        &encryption::ciphertext_sha256(encrypted)[..16]
+                    )
+                }
+                MetadataField::SheetId => {
+                    lang::fill(self.language.catalog().sheet_id, &[("id", &self.sheet_id)])
+                }
+            })
+            .collect()
     }
 
     /// Insert the given PEM ciphertext in the bottom half of the page
     pub fn insert_pem_text(&mut self, pem: String) {
         debug!("Inserting PEM encoded ciphertext");
 
-        let mut font_size = 13.0;
-        let mut line_height = 15.0;
-
-        // Rudimentary text scaling to get the Ascii Armor text to fit
-        if pem.lines().count() > 42 {
-            font_size = 6.5;
-            line_height = 7.0;
-        } else if pem.lines().count() > 39 {
-            font_size = 7.0;
-            line_height = 8.0;
-        } else if pem.lines().count() > 27 {
-            font_size = 8.0;
-            line_height = 9.0;
-        } else if pem.lines().count() > 22 {
-            font_size = 10.0;
-            line_height = 12.0;
-        }
-
-        self.ops.push(Op::StartTextSection);
-        self.ops.push(Op::SetFillColor {
-            col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
-        });
-
-        self.ops.push(Op::SetTextCursor {
-            pos: Point::new(
-                self.page_size.dimensions().margin,
-                (self.page_size.dimensions().height / 2.0)
-                    - Mm::from(Pt(font_size))
-                    - self.page_size.dimensions().margin,
-            ),
-        });
-        self.ops.push(Op::SetLineHeight {
-            lh: Pt(line_height),
-        });
-        self.ops.push(Op::SetFont {
-            font: self.code_font.clone(),
-            size: Pt(font_size),
-        });
-
-        for line in pem.lines() {
-            self.ops.push(Op::ShowText {
-                items: vec![TextItem::Text(line.to_string())],
-            });
-            self.ops.push(Op::AddLineBreak);
+        let (left, max_width, top) = self.text_area();
+        let margin = self.panel().margin;
+        let line_count = pem.lines().count().max(1) as f32;
+
+        // Set the text as large as possible, up to the full size, so that the
+        // longest line fits in the width and the lines fit above the footer
+        let widest = pem
+            .lines()
+            .map(|line| self.fonts.text_width(line, Font::Code, Pt(1.0)).0)
+            .fold(0.0, f32::max);
+        let lowest = margin + self.footer_height();
+        let available_height = (top - margin - lowest).into_pt().0;
+        let mut font_size = self
+            .scaled(PEM_FONT_SIZE)
+            .min(available_height / (1.0 + PEM_LINE_SPACING * (line_count - 1.0)));
+        if widest > 0.0 {
+            font_size = font_size.min(max_width.into_pt().0 / widest);
         }
+        let line_height = font_size * PEM_LINE_SPACING;
+        debug!("PEM text font size: {font_size:.2}pt");
 
-        self.ops.push(Op::EndTextSection);
+        let baseline = self.pem_baseline(Pt(font_size));
+        for (i, line) in pem.lines().enumerate() {
+            let y = baseline - Mm::from(Pt(line_height * i as f32));
+            self.push_text(line.to_string(), Font::Code, font_size, Point::new(left, y));
+        }
     }
 
     /// Insert the QR code of the PEM encoded ciphertext in the top half of the page
     pub fn insert_qr_code(&mut self, text: String) -> Result<(), Box<dyn std::error::Error>> {
         debug!("Inserting QR code");
 
-        let ops = qrcode_ops::render(text, &self.page_size)?;
-        self.ops.extend(ops);
+        let origin = Point::new(self.qrcode_left_edge(), self.qrcode_bottom_edge());
+        let geometry = qrcode_ops::layout_at(text, origin, self.qrcode_size())?;
+        self.elements.push(Element::QrCode(geometry));
 
         Ok(())
     }
@@ -209,15 +376,13 @@ impl Document {
         let thickness = 0.0;
 
         let mut x = Mm(0.0);
-        let mut y = self.page_size.dimensions().height;
-        while x < self.page_size.dimensions().width {
+        let dimensions = self.dimensions();
+        let mut y = dimensions.height;
+        while x < dimensions.width {
             x += grid_size;
 
             self.draw_line(
-                vec![
-                    Point::new(x, self.page_size.dimensions().height),
-                    Point::new(x, Mm(0.0)),
-                ],
+                vec![Point::new(x, dimensions.height), Point::new(x, Mm(0.0))],
                 thickness,
                 LineDashPattern::default(),
             );
@@ -226,10 +391,7 @@ impl Document {
                 y -= grid_size;
 
                 self.draw_line(
-                    vec![
-                        Point::new(self.page_size.dimensions().width, y),
-                        Point::new(Mm(0.0), y),
-                    ],
+                    vec![Point::new(dimensions.width, y), Point::new(Mm(0.0), y)],
                     thickness,
                     LineDashPattern::default(),
                 );
@@ -241,68 +403,38 @@ impl Document {
     pub fn draw_line(&mut self, points: Vec<Point>, thickness: f32, dash_pattern: LineDashPattern) {
         trace!("Drawing line");
 
-        self.ops.push(Op::SetLineDashPattern { dash: dash_pattern });
-
-        let outline_color = Color::Rgb(Rgb::new(0.75, 0.75, 0.75, None));
-        self.ops.push(Op::SetOutlineColor { col: outline_color });
-
-        self.ops.push(Op::SetOutlineThickness { pt: Pt(thickness) });
-
-        let divider = Line {
-            points: points
-                .iter()
-                .map(|p| LinePoint {
-                    p: *p,
-                    bezier: false,
-                })
-                .collect(),
-            is_closed: false,
-        };
-
-        self.ops.push(Op::DrawLine { line: divider });
+        self.elements.push(Element::Line {
+            points,
+            thickness: Pt(thickness),
+            dash_pattern,
+        });
     }
 
-    /// Insert the notes field label and placeholder in the PDF
+    /// Insert the notes field label and placeholder in the PDF. Long labels
+    /// are set smaller to fit between the left edge of the QR code and the
+    /// right edge of the QR code area.
     pub fn insert_notes_field(&mut self, label: String, skip_line: bool) {
         debug!("Inserting notes/passphrase placeholder");
-        const MAX_LABEL_LEN: usize = 32;
 
-        let baseline =
-            self.page_size.dimensions().height / 2.0 + self.page_size.dimensions().margin;
+        let baseline = self.notes_baseline();
+        let left = self.qrcode_left_edge();
+        let (area_width, _) = self.qrcode_area();
+        let max_width = (self.panel().margin + area_width - left).into_pt();
 
-        let label_len = label.len();
+        let font_size =
+            self.fonts
+                .fit_font_size(&label, Font::Title, Pt(self.scaled(13.0)), max_width);
+        let label_end = left + Mm::from(self.fonts.text_width(&label, Font::Title, font_size));
+        let line_end = left + self.qrcode_size();
 
-        let font_size = 13.0;
-
-        self.ops.push(Op::StartTextSection);
-        self.ops.push(Op::SetFillColor {
-            col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
-        });
-        self.ops.push(Op::SetTextCursor {
-            pos: Point::new(self.page_size.qrcode_left_edge(), baseline),
-        });
-        self.ops.push(Op::SetFont {
-            font: self.title_font.clone(),
-            size: Pt(font_size),
-        });
-        self.ops.push(Op::ShowText {
-            items: vec![TextItem::Text(label)],
-        });
-        self.ops.push(Op::EndTextSection);
+        self.push_text(label, Font::Title, font_size.0, Point::new(left, baseline));
 
         // If the placeholder line would be ridiculously short, don't draw it
-        if label_len <= MAX_LABEL_LEN && !skip_line {
+        if line_end - label_end >= MIN_NOTES_LINE_LENGTH && !skip_line {
             self.draw_line(
                 vec![
-                    Point::new(
-                        self.page_size.qrcode_left_edge()
-                            + Mm::from(Pt(FONT_RATIO * font_size * label_len as f32)),
-                        baseline - Mm(1.0),
-                    ),
-                    Point::new(
-                        self.page_size.qrcode_left_edge() + self.page_size.qrcode_size(),
-                        baseline - Mm(1.0),
-                    ),
+                    Point::new(label_end, baseline - Mm(1.0)),
+                    Point::new(line_end, baseline - Mm(1.0)),
                 ],
                 1.0,
                 LineDashPattern::default(),
@@ -310,72 +442,603 @@ impl Document {
         }
     }
 
+    /// Insert the label and the short fingerprints of the recipients below the
+    /// QR code so that whoever restores the backup knows which private keys can
+    /// be used. Long lists of recipients are split into columns.
+    pub fn insert_recipients(&mut self, label: String, recipients: &[Recipient]) {
+        debug!("Inserting recipient fingerprints");
+
+        let dimensions = self.panel();
+        let (area_width, area_bottom) = self.qrcode_area();
+        let left = self.qrcode_left_edge();
+        let label_font_size = self.scaled(11.0);
+        let label_baseline = self.qrcode_bottom_edge() - Mm(6.0);
+
+        self.push_text(
+            label,
+            Font::Title,
+            label_font_size,
+            Point::new(left, label_baseline),
+        );
+
+        // Shrink the lines and then split them into columns to fit all the
+        // recipients between the label and the divider
+        let first_baseline = label_baseline - Mm(4.5);
+        let lowest_baseline = area_bottom + Mm(2.0);
+        let line_height_for = |rows: usize| {
+            if rows > 1 {
+                MAX_RECIPIENT_LINE_HEIGHT
+                    .min((first_baseline - lowest_baseline) / (rows - 1) as f32)
+            } else {
+                MAX_RECIPIENT_LINE_HEIGHT
+            }
+        };
+        let mut columns = 1;
+        while columns < MAX_RECIPIENT_COLUMNS
+            && line_height_for(recipients.len().div_ceil(columns)) < MIN_RECIPIENT_LINE_HEIGHT
+        {
+            columns += 1;
+        }
+        let rows = recipients.len().div_ceil(columns).max(1);
+        let line_height = line_height_for(rows);
+        let font_size = (line_height.into_pt().0 * 0.85).min(8.0);
+
+        let column_width = (dimensions.margin + area_width - left) / columns as f32;
+        // Keep a space between the columns
+        let max_width =
+            column_width.into_pt() - self.fonts.text_width(" ", Font::Code, Pt(font_size));
+
+        for (i, recipient) in recipients.iter().enumerate() {
+            let number = if recipients.len() > 1 {
+                format!("{}. ", i + 1)
+            } else {
+                String::new()
+            };
+            let line = match recipient.comment() {
+                Some(comment) => format!("{number}{} {comment}", recipient.short_fingerprint()),
+                None => format!("{number}{}", recipient.short_fingerprint()),
+            };
+            trace!("Recipient: {line}");
+
+            let x = left + column_width * (i / rows) as f32;
+            let y = first_baseline - line_height * (i % rows) as f32;
+
+            self.push_text(
+                self.fonts
+                    .truncate_to_width(&line, Font::Code, Pt(font_size), max_width),
+                Font::Code,
+                font_size,
+                Point::new(x, y),
+            );
+        }
+    }
+
+    /// Insert the QR codes of the given parts side by side in the top half of
+    /// the page, each labelled with its part number and the content hash
+    pub fn insert_part_qr_codes(
+        &mut self,
+        parts: &[Part],
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        debug!("Inserting QR codes for {} part(s)", parts.len());
+
+        let dimensions = self.panel();
+        let size = self.part_qrcode_size();
+        let bottom = dimensions.height - dimensions.margin * 2.0 - size;
+        let label_font_size = self.scaled(10.0);
+
+        for (i, part) in parts.iter().enumerate() {
+            let left = dimensions.margin + (size + PART_GAP) * i as f32;
+            let geometry = qrcode_ops::layout_at(part.payload(), Point::new(left, bottom), size)?;
+            self.elements.push(Element::QrCode(geometry));
+
+            let label = part.label_in(self.language);
+            let label_width = Mm::from(self.fonts.text_width(
+                &label,
+                Font::Code,
+                Pt(label_font_size),
+            ));
+            self.push_text(
+                label,
+                Font::Code,
+                label_font_size,
+                Point::new(left + (size - label_width) / 2.0, bottom - Mm(5.0)),
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Insert the page number in the top right corner of the page
+    pub fn insert_page_number(&mut self, page: usize, total: usize) {
+        debug!("Inserting page number {page}/{total}");
+
+        let dimensions = self.panel();
+        let font_size = self.scaled(11.0);
+        let text = lang::fill(
+            self.language.catalog().page_number,
+            &[("page", &page), ("total", &total)],
+        );
+        let width = Mm::from(self.fonts.text_width(&text, Font::Title, Pt(font_size)));
+
+        self.push_text(
+            text,
+            Font::Title,
+            font_size,
+            Point::new(
+                dimensions.width - dimensions.margin - width,
+                dimensions.title_baseline(Pt(self.scaled(TITLE_FONT_SIZE))),
+            ),
+        );
+    }
+
+    /// Insert step by step instructions for restoring the backup on the page,
+    /// for whoever opens the backup years later without knowing what age is.
+    /// The text is set smaller on pages where it doesn't fit at full size.
+    pub fn insert_instructions(&mut self, mode: &EncryptionMode, split: bool) {
+        debug!("Inserting restore instructions");
+
+        let dimensions = self.dimensions();
+        let first_element = self.elements.len();
+        let mut font_size = self.scaled(INSTRUCTIONS_FONT_SIZE);
+        loop {
+            let bottom = self.insert_instructions_at_size(mode, split, font_size);
+            if bottom >= dimensions.margin || font_size <= 4.0 {
+                break;
+            }
+            self.elements.truncate(first_element);
+            font_size *= 0.9;
+        }
+    }
+
+    /// Insert the restore instructions with the body text at the given size
+    /// and return the lowest baseline
+    fn insert_instructions_at_size(
+        &mut self,
+        mode: &EncryptionMode,
+        split: bool,
+        font_size: f32,
+    ) -> Mm {
+        let dimensions = self.dimensions();
+        let left = dimensions.margin;
+        let width = dimensions.inner_width();
+        let line_height = Mm::from(Pt(font_size * INSTRUCTIONS_LINE_SPACING));
+        let indent = Mm::from(self.fonts.text_width("00. ", Font::Title, Pt(font_size)));
+
+        let catalog = self.language.catalog();
+        let title_size = font_size * TITLE_FONT_SIZE / INSTRUCTIONS_FONT_SIZE;
+        let mut y = dimensions.title_baseline(Pt(title_size));
+        self.push_text(
+            catalog.instructions_title.to_string(),
+            Font::Title,
+            title_size,
+            Point::new(left, y),
+        );
+        y -= line_height * 2.0;
+
+        let key = match mode {
+            EncryptionMode::Passphrase(_) => catalog.key_passphrase,
+            EncryptionMode::Recipients(_) => catalog.key_recipients,
+        };
+        y = self.insert_paragraph(
+            &lang::fill(catalog.intro, &[("key", &key)]),
+            left,
+            width,
+            font_size,
+            y,
+        );
+        y -= line_height;
+
+        let scan = if split {
+            catalog.step_scan_split
+        } else {
+            catalog.step_scan
+        };
+        let type_in = if self.line_checksums {
+            format!("{} {}", catalog.step_type, catalog.step_checksums)
+        } else {
+            catalog.step_type.to_string()
+        };
+        let decrypt = match mode {
+            EncryptionMode::Passphrase(_) => (
+                catalog.step_decrypt_passphrase,
+                "age -d -o secret.txt backup.age",
+            ),
+            EncryptionMode::Recipients(_) => (
+                catalog.step_decrypt_recipients,
+                "age -d -i key.txt -o secret.txt backup.age",
+            ),
+        };
+        let steps = [
+            (catalog.step_install.to_string(), None),
+            (scan.to_string(), None),
+            (type_in, None),
+            (decrypt.0.to_string(), Some(decrypt.1)),
+            (catalog.step_keep.to_string(), None),
+        ];
+
+        for (i, (text, command)) in steps.into_iter().enumerate() {
+            self.push_text(
+                format!("{}.", i + 1),
+                Font::Title,
+                font_size,
+                Point::new(left, y),
+            );
+            y = self.insert_paragraph(&text, left + indent, width - indent, font_size, y);
+            if let Some(command) = command {
+                self.push_text(
+                    command.to_string(),
+                    Font::Code,
+                    font_size,
+                    Point::new(left + indent * 2.0, y),
+                );
+                y -= line_height;
+            }
+            y -= line_height / 2.0;
+        }
+
+        let date = self.created.date;
+        let created = lang::fill(
+            catalog.created,
+            &[
+                ("version", &VERSION.unwrap_or("0.0.0")),
+                (
+                    "date",
+                    &format!("{:04}-{:02}-{:02}", date.year, date.month, date.day),
+                ),
+            ],
+        );
+        y -= line_height;
+        self.push_text(created, Font::Code, font_size, Point::new(left, y));
+
+        y
+    }
+
+    /// Insert the text wrapped to the width, starting from the baseline `y`,
+    /// and return the baseline of the next line
+    fn insert_paragraph(&mut self, text: &str, left: Mm, width: Mm, font_size: f32, y: Mm) -> Mm {
+        let line_height = Mm::from(Pt(font_size * INSTRUCTIONS_LINE_SPACING));
+        let lines = self
+            .fonts
+            .wrap_to_width(text, Font::Code, Pt(font_size), width.into_pt());
+
+        let mut y = y;
+        for line in lines {
+            self.push_text(line, Font::Code, font_size, Point::new(left, y));
+            y -= line_height;
+        }
+        y
+    }
+
+    /// Page dimensions in the orientation of the document
+    pub fn dimensions(&self) -> PageDimensions {
+        self.page_size.dimensions_in(self.orientation)
+    }
+
+    /// Dimensions of a single panel. With several copies, the page is split
+    /// into equal panels across its longer side.
+    fn panel(&self) -> PageDimensions {
+        let dimensions = self.dimensions();
+        let copies = self.copies.max(1) as f32;
+
+        if dimensions.height >= dimensions.width {
+            PageDimensions {
+                height: dimensions.height / copies,
+                ..dimensions
+            }
+        } else {
+            PageDimensions {
+                width: dimensions.width / copies,
+                ..dimensions
+            }
+        }
+    }
+
+    /// Orientation of the panel layout. The panels of copies use the
+    /// landscape layout when they are wider than they are tall.
+    fn panel_orientation(&self) -> Orientation {
+        let panel = self.panel();
+        match self.copies {
+            0 | 1 => self.orientation,
+            _ if panel.width > panel.height => Orientation::Landscape,
+            _ => Orientation::Portrait,
+        }
+    }
+
+    /// Width and bottom edge of the area for the QR codes and the notes field
+    /// below the title, starting from the left margin. The area is the top
+    /// half of the page in portrait and the left half in landscape.
+    fn qrcode_area(&self) -> (Mm, Mm) {
+        let dimensions = self.panel();
+        match self.panel_orientation() {
+            Orientation::Portrait => (dimensions.inner_width(), dimensions.height / 2.0),
+            Orientation::Landscape => (
+                dimensions.width / 2.0 - dimensions.margin * 2.0,
+                dimensions.margin + self.footer_height(),
+            ),
+        }
+    }
+
+    /// Left edge, width, and top edge of the area for the PEM text. The area
+    /// is the bottom half of the page in portrait and the right half below
+    /// the title in landscape.
+    fn text_area(&self) -> (Mm, Mm, Mm) {
+        let dimensions = self.panel();
+        match self.panel_orientation() {
+            Orientation::Portrait => (
+                dimensions.margin,
+                dimensions.inner_width(),
+                dimensions.height / 2.0,
+            ),
+            Orientation::Landscape => (
+                dimensions.width / 2.0 + dimensions.margin,
+                dimensions.width / 2.0 - dimensions.margin * 2.0,
+                dimensions.height - dimensions.margin,
+            ),
+        }
+    }
+
+    /// Points of the dashed divider between the QR code and the PEM text
+    fn divider(&self) -> Vec<Point> {
+        let dimensions = self.panel();
+        match self.panel_orientation() {
+            Orientation::Portrait => vec![dimensions.center_left(), dimensions.center_right()],
+            Orientation::Landscape => vec![
+                Point::new(
+                    dimensions.width / 2.0,
+                    dimensions.height - dimensions.margin * 2.0,
+                ),
+                Point::new(
+                    dimensions.width / 2.0,
+                    dimensions.margin + self.footer_height(),
+                ),
+            ],
+        }
+    }
+
+    /// Size of the QR code: as large as fits in the QR code area while
+    /// leaving room for the notes field
+    fn qrcode_size(&self) -> Mm {
+        let dimensions = self.panel();
+        let (width, bottom) = self.qrcode_area();
+        let height = dimensions.height - dimensions.margin * 2.0 - bottom - NOTES_FIELD_HEIGHT;
+
+        width.min(height)
+    }
+
+    /// The left edge of the QR code, centered in the QR code area
+    fn qrcode_left_edge(&self) -> Mm {
+        let (width, _) = self.qrcode_area();
+        self.panel().margin + (width - self.qrcode_size()) / 2.0
+    }
+
+    /// The bottom edge of the QR code
+    fn qrcode_bottom_edge(&self) -> Mm {
+        let dimensions = self.panel();
+        dimensions.height - self.qrcode_size() - dimensions.margin * 2.0
+    }
+
+    /// Baseline of the notes field label, at the bottom of the QR code area
+    fn notes_baseline(&self) -> Mm {
+        let (_, bottom) = self.qrcode_area();
+        bottom + self.panel().margin
+    }
+
+    /// Baseline of the first line of PEM text of the given size, at the top of
+    /// the text area
+    fn pem_baseline(&self, font_size: Pt) -> Mm {
+        let (_, _, top) = self.text_area();
+        top - Mm::from(font_size) - self.panel().margin
+    }
+
+    /// Size of the QR codes of split ciphertexts, placed side by side in the
+    /// QR code area
+    fn part_qrcode_size(&self) -> Mm {
+        let dimensions = self.panel();
+        let (width, bottom) = self.qrcode_area();
+        let count = PARTS_PER_PAGE as f32;
+        let width = (width - PART_GAP * (count - 1.0)) / count;
+        let height = dimensions.height - dimensions.margin * 2.0 - bottom - PART_LABELS_HEIGHT;
+
+        width.min(height)
+    }
+
+    /// Height reserved for the footer at the bottom of the page
+    fn footer_height(&self) -> Mm {
+        Mm::from(Pt(self.scaled(FOOTER_FONT_SIZE) * 2.0))
+    }
+
+    /// Scale the font size or length to the page size
+    fn scaled(&self, size: f32) -> f32 {
+        size * self.page_size.dimensions().font_scale()
+    }
+
+    /// Add a single line of text to the page. The characters that the font
+    /// doesn't cover are set in the fallback font.
+    fn push_text(&mut self, text: String, font: Font, font_size: f32, position: Point) {
+        let size = Pt(font_size);
+        let runs = self.fonts.runs(&text, font);
+        if runs.len() <= 1 {
+            self.elements.push(Element::Text {
+                font: runs.first().map_or(font, |&(_, run_font)| run_font),
+                text,
+                size,
+                position,
+            });
+            return;
+        }
+
+        let mut x = position.x;
+        for (run, run_font) in runs {
+            self.elements.push(Element::Text {
+                text: run.to_string(),
+                font: run_font,
+                size,
+                position: Point { x, y: position.y },
+            });
+            x += self.fonts.text_width(run, run_font, size);
+        }
+    }
+
     /// Add the footer at the bottom of the page
     pub fn insert_footer(&mut self) {
         debug!("Inserting footer");
 
-        self.ops.push(Op::StartTextSection);
-        self.ops.push(Op::SetFillColor {
-            col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
-        });
-        self.ops.push(Op::SetTextCursor {
-            pos: Point::new(
-                self.page_size.dimensions().margin,
-                self.page_size.dimensions().margin,
-            ),
-        });
-        self.ops.push(Op::SetFont {
-            font: self.title_font.clone(),
-            size: Pt(13.0),
-        });
-        self.ops.push(Op::ShowText {
-            items: vec![TextItem::Text(
-                "Scan QR code and decrypt using Age <https://age-encryption.org>".to_string(),
-            )],
-        });
-        self.ops.push(Op::EndTextSection);
-    }
+        let text = self.language.catalog().footer;
 
-    /// Build the final PDF and return as bytes
-    pub fn save_to_bytes(mut self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
-        let dimensions = self.page_size.dimensions();
-        let page = PdfPage::new(dimensions.width, dimensions.height, self.ops);
-        self.doc.pages.push(page);
+        // Shrink the footer to fit narrow panels
+        let font_size = self
+            .fonts
+            .fit_font_size(
+                text,
+                Font::Title,
+                Pt(self.scaled(FOOTER_FONT_SIZE)),
+                self.panel().inner_width().into_pt(),
+            )
+            .0;
+
+        self.push_text(
+            text.to_string(),
+            Font::Title,
+            font_size,
+            self.panel().bottom_left(),
+        );
+    }
 
-        let mut warnings = Vec::new();
-        let bytes = self.doc.save(&PdfSaveOptions::default(), &mut warnings);
+    /// Build the final document in the output format and return as bytes
+    pub fn save_to_bytes(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut bytes = Vec::new();
+        self.save_to_writer(&mut bytes)?;
         Ok(bytes)
     }
 
-    /// Build the final PDF and write to a writer
+    /// Build the final document in the output format and write to a writer
     pub fn save_to_writer<W: Write>(
-        mut self,
+        &self,
         writer: &mut W,
     ) -> Result<(), Box<dyn std::error::Error>> {
-        let dimensions = self.page_size.dimensions();
-        let page = PdfPage::new(dimensions.width, dimensions.height, self.ops);
-        self.doc.pages.push(page);
+        match self.format {
+            Format::Pdf => {
+                let mut canvas = PdfCanvas::new(&self.title, &self.fonts)?;
+                canvas.set_created(self.created);
+                self.draw(&mut canvas);
+                canvas.save_to_writer(writer);
+            }
+            Format::Svg => {
+                let mut canvas = SvgCanvas::new(&self.title, &self.fonts);
+                self.draw(&mut canvas);
+                writer.write_all(canvas.finish().as_bytes())?;
+            }
+            Format::Png => {
+                let mut canvas = PngCanvas::new(self.dpi, &self.fonts)?;
+                self.draw(&mut canvas);
+                writer.write_all(&canvas.finish()?)?;
+            }
+        }
 
-        let mut warnings = Vec::new();
-        self.doc
-            .save_writer(writer, &PdfSaveOptions::default(), &mut warnings);
         Ok(())
     }
 
-    /// Build a PaperAge PDF and return its bytes.
+    /// Draw the pages laid out so far, including the current page, on the
+    /// canvas
+    pub fn draw(&self, canvas: &mut dyn Canvas) {
+        let dimensions = self.dimensions();
+
+        for elements in self.pages.iter().chain(std::iter::once(&self.elements)) {
+            canvas.begin_page(&dimensions);
+            for element in elements {
+                element.draw(canvas);
+            }
+            canvas.end_page();
+        }
+    }
+
+    /// Lay out a PaperAge document and return its bytes in the output format.
     ///
     /// # Arguments
     /// * `grid` - Whether to draw a debug grid
-    /// * `notes_label` - Label for the notes/passphrase field
+    /// * `notes_label` - Label for the notes/passphrase field (defaults to
+    ///   [`EncryptionMode::default_notes_label_in`])
     /// * `skip_notes_line` - Whether to omit the notes placeholder line
     /// * `encrypted` - The encrypted ciphertext to encode as a QR code and PEM block
+    /// * `mode` - How the ciphertext was encrypted
+    pub fn render(
+        mut self,
+        grid: bool,
+        notes_label: Option<String>,
+        skip_notes_line: bool,
+        encrypted: String,
+        mode: &EncryptionMode,
+    ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.layout(grid, notes_label, skip_notes_line, encrypted, mode)?;
+        self.save_to_bytes()
+    }
+
+    /// Build a PaperAge document and return the PDF bytes, whatever the
+    /// [`format`](Document::format) is
+    ///
+    /// The arguments are the same as for [`render`](Document::render).
+    #[deprecated(note = "use `Document::render`, which returns the bytes in the output format")]
     pub fn create_pdf(
         mut self,
         grid: bool,
-        notes_label: String,
+        notes_label: Option<String>,
         skip_notes_line: bool,
         encrypted: String,
+        mode: &EncryptionMode,
     ) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        self.format = Format::Pdf;
+        self.render(grid, notes_label, skip_notes_line, encrypted, mode)
+    }
+
+    /// Lay out the pages of a PaperAge document without building the output,
+    /// e.g. to [`draw`](Document::draw) them on a custom [`Canvas`]
+    ///
+    /// The arguments are the same as for [`render`](Document::render).
+    pub fn layout(
+        &mut self,
+        grid: bool,
+        notes_label: Option<String>,
+        skip_notes_line: bool,
+        encrypted: String,
+        mode: &EncryptionMode,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let notes_label = notes_label.unwrap_or_else(|| mode.default_notes_label_in(self.language));
+        let split = self.split && !qrcode_ops::fits(&encrypted);
+        self.metadata_texts = self.metadata_texts(&encrypted);
+
+        if self.copies > 1 && (self.wallet_card || split) {
+            return Err("Copies can't be combined with wallet cards or split ciphertexts".into());
+        }
+
+        if self.wallet_card {
+            self.layout_cards(grid, notes_label, skip_notes_line, encrypted, mode)?;
+        } else if split {
+            let parts = split::split(&encrypted, split::LINES_PER_PART);
+            self.layout_split(grid, notes_label, skip_notes_line, parts, mode)?;
+        } else if self.copies > 1 {
+            self.layout_copies(grid, notes_label, skip_notes_line, encrypted, mode)?;
+        } else {
+            self.layout_panel(grid, notes_label, skip_notes_line, encrypted, mode)?;
+        }
+
+        if self.instructions {
+            self.new_page();
+            self.insert_instructions(mode, split);
+        }
+
+        Ok(())
+    }
+
+    /// Lay out the title, the QR code, the notes field or the recipients, the
+    /// PEM text, and the footer in the panel
+    fn layout_panel(
+        &mut self,
+        grid: bool,
+        notes_label: String,
+        skip_notes_line: bool,
+        encrypted: String,
+        mode: &EncryptionMode,
+    ) -> Result<(), Box<dyn std::error::Error>> {
         if grid {
             self.draw_grid();
         }
@@ -384,25 +1047,473 @@ impl Document {
 
         self.insert_qr_code(encrypted.clone())?;
 
-        self.insert_notes_field(notes_label, skip_notes_line);
+        match mode {
+            EncryptionMode::Passphrase(_) => self.insert_notes_field(notes_label, skip_notes_line),
+            EncryptionMode::Recipients(recipients) => {
+                self.insert_recipients(notes_label, recipients)
+            }
+        }
+
+        self.draw_line(self.divider(), 1.0, LineDashPattern::new(0.0, &[5.0]));
+
+        if self.line_checksums {
+            self.insert_pem_text(checksums::annotate(&encrypted));
+        } else {
+            self.insert_pem_text(encrypted);
+        }
+
+        self.insert_footer();
+
+        Ok(())
+    }
+
+    /// Lay out a PaperAge document where the ciphertext is split across
+    /// several QR codes and pages. Each page has the QR codes of its parts in
+    /// the top half and their PEM lines in the bottom half.
+    fn layout_split(
+        &mut self,
+        grid: bool,
+        notes_label: String,
+        skip_notes_line: bool,
+        parts: Vec<Part>,
+        mode: &EncryptionMode,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let pages: Vec<&[Part]> = parts.chunks(PARTS_PER_PAGE).collect();
+
+        // The lines are numbered across all the pages
+        let pem: String = parts.iter().map(|part| part.text.as_str()).collect();
+        let mut pem_lines = if self.line_checksums {
+            checksums::annotate(&pem)
+        } else {
+            pem
+        }
+        .lines()
+        .map(|line| format!("{line}\n"))
+        .collect::<Vec<_>>()
+        .into_iter();
+        info!(
+            "Splitting the ciphertext into {} parts on {} pages",
+            parts.len(),
+            pages.len()
+        );
+
+        for (i, page_parts) in pages.iter().enumerate() {
+            if i > 0 {
+                self.new_page();
+            }
+
+            if grid {
+                self.draw_grid();
+            }
+
+            self.insert_title_text_at(self.title.clone(), self.panel().margin);
+            self.insert_page_number(i + 1, pages.len());
+
+            self.insert_part_qr_codes(page_parts)?;
+
+            if i == 0 {
+                match mode {
+                    EncryptionMode::Passphrase(_) => {
+                        self.insert_notes_field(notes_label.clone(), skip_notes_line)
+                    }
+                    EncryptionMode::Recipients(recipients) => {
+                        self.insert_recipients(notes_label.clone(), recipients)
+                    }
+                }
+            }
+
+            self.draw_line(self.divider(), 1.0, LineDashPattern::new(0.0, &[5.0]));
+
+            let line_count: usize = page_parts
+                .iter()
+                .map(|part| part.text.lines().count())
+                .sum();
+            let pem: String = pem_lines.by_ref().take(line_count).collect();
+            self.insert_pem_text(pem);
+
+            self.insert_footer();
+        }
+
+        Ok(())
+    }
+
+    /// Lay out the same panel several times on the page, with cut lines
+    /// between the copies
+    fn layout_copies(
+        &mut self,
+        grid: bool,
+        notes_label: String,
+        skip_notes_line: bool,
+        encrypted: String,
+        mode: &EncryptionMode,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        info!("Printing {} copies on the page", self.copies);
+
+        self.layout_panel(false, notes_label, skip_notes_line, encrypted, mode)?;
+        let panel_elements = std::mem::take(&mut self.elements);
+
+        if grid {
+            self.draw_grid();
+        }
+
+        let dimensions = self.dimensions();
+        let panel = self.panel();
+        let stacked = panel.height < dimensions.height;
+
+        for i in 0..self.copies {
+            let offset = if stacked {
+                Point::new(Mm(0.0), dimensions.height - panel.height * (i + 1) as f32)
+            } else {
+                Point::new(panel.width * i as f32, Mm(0.0))
+            };
+            self.elements.extend(
+                panel_elements
+                    .iter()
+                    .map(|element| element.translated(offset)),
+            );
+
+            if i > 0 {
+                if stacked {
+                    let y = dimensions.height - panel.height * i as f32;
+                    self.insert_cut_line(
+                        Point::new(dimensions.margin, y),
+                        Point::new(dimensions.width - dimensions.margin, y),
+                    );
+                } else {
+                    let x = panel.width * i as f32;
+                    self.insert_cut_line(
+                        Point::new(x, dimensions.height - dimensions.margin),
+                        Point::new(x, dimensions.margin),
+                    );
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Draw a dashed cut line from `from` to `to`, with a pair of scissors at
+    /// the start pointing along the line
+    fn insert_cut_line(&mut self, from: Point, to: Point) {
+        debug!("Inserting cut line");
+
+        let length = ((to.x.0 - from.x.0).powi(2) + (to.y.0 - from.y.0).powi(2)).sqrt();
+        let (dx, dy) = ((to.x.0 - from.x.0) / length, (to.y.0 - from.y.0) / length);
+        // Point `u` mm along the line and `v` mm to the left of it
+        let at = |u: f32, v: f32| {
+            let (u, v) = (Mm(u).into_pt().0, Mm(v).into_pt().0);
+            Point {
+                x: Pt(from.x.0 + dx * u - dy * v),
+                y: Pt(from.y.0 + dy * u + dx * v),
+            }
+        };
+        let thickness = 0.75;
+
+        // Scissors: two round handles and two crossed blades
+        for v in [1.6, -1.6] {
+            let handle = (0..=16)
+                .map(|i| {
+                    let angle = i as f32 * std::f32::consts::TAU / 16.0;
+                    at(1.2 + 1.2 * angle.cos(), v + 1.2 * angle.sin())
+                })
+                .collect();
+            self.draw_line(handle, thickness, LineDashPattern::default());
+            self.draw_line(
+                vec![at(2.2, v * 0.6), at(7.5, -v * 0.4)],
+                thickness,
+                LineDashPattern::default(),
+            );
+        }
 
         self.draw_line(
-            vec![
-                self.page_size.dimensions().center_left(),
-                self.page_size.dimensions().center_right(),
-            ],
-            1.0,
-            LineDashPattern::new(0.0, &[5.0]),
+            vec![at(9.0, 0.0), to],
+            thickness,
+            LineDashPattern::new(0.0, &[3.0, 2.0]),
         );
+    }
 
-        self.insert_pem_text(encrypted);
+    /// Lay out a sheet of identical wallet cards, tiled edge to edge in the
+    /// middle of the page with crop marks around them
+    fn layout_cards(
+        &mut self,
+        grid: bool,
+        notes_label: String,
+        skip_notes_line: bool,
+        encrypted: String,
+        mode: &EncryptionMode,
+    ) -> Result<(), Box<dyn std::error::Error>> {
+        let dimensions = self.dimensions();
+        let (columns, rows) = self.card_grid();
+        let count = self.card_count.unwrap_or(usize::MAX).min(columns * rows);
+        if count == 0 {
+            return Err("The page is too small for a wallet card".into());
+        }
+        info!("Tiling {count} wallet card(s) on the page");
 
-        self.insert_footer();
+        let size = CARD_HEIGHT - CARD_PADDING * 2.0;
+        let qr_code = qrcode_ops::layout_with_min_module_size(
+            encrypted,
+            Point::new(Mm(0.0), Mm(0.0)),
+            size,
+            MIN_CARD_MODULE_SIZE,
+        )?;
 
-        self.save_to_bytes()
+        if grid {
+            self.draw_grid();
+        }
+
+        let columns_used = count.min(columns);
+        let rows_used = count.div_ceil(columns);
+        let left = (dimensions.width - CARD_WIDTH * columns_used as f32) / 2.0;
+        let top = (dimensions.height + CARD_HEIGHT * rows_used as f32) / 2.0;
+
+        for i in 0..count {
+            let card_left = left + CARD_WIDTH * (i % columns) as f32;
+            let card_bottom = top - CARD_HEIGHT * (i / columns + 1) as f32;
+
+            let mut qr_code = qr_code.clone();
+            qr_code.origin = Point::new(card_left + CARD_PADDING, card_bottom + CARD_PADDING);
+            self.elements.push(Element::QrCode(qr_code));
+
+            self.insert_card_text(
+                Point::new(card_left, card_bottom),
+                &notes_label,
+                skip_notes_line,
+                mode,
+            );
+        }
+
+        self.draw_crop_marks(left, top, columns_used, rows_used);
+
+        Ok(())
+    }
+
+    /// Number of wallet card columns and rows that fit between the margins
+    pub fn card_grid(&self) -> (usize, usize) {
+        let dimensions = self.dimensions();
+        let columns = (dimensions.inner_width().0 / CARD_WIDTH.0) as usize;
+        let rows = ((dimensions.height - dimensions.margin * 2.0).0 / CARD_HEIGHT.0) as usize;
+
+        (columns, rows)
+    }
+
+    /// Insert the title, the notes field or the recipients, and a short hint
+    /// on how to restore the backup to the right of the QR code of the wallet
+    /// card whose bottom left corner is at `origin`
+    fn insert_card_text(
+        &mut self,
+        origin: Point,
+        notes_label: &str,
+        skip_notes_line: bool,
+        mode: &EncryptionMode,
+    ) {
+        let left = Mm::from(origin.x) + CARD_HEIGHT;
+        let bottom = Mm::from(origin.y);
+        let width = CARD_WIDTH - CARD_HEIGHT - CARD_PADDING;
+        let fonts = self.fonts.clone();
+        let truncate = |text: &str, font: Font, font_size: f32| {
+            fonts.truncate_to_width(text, font, Pt(font_size), width.into_pt())
+        };
+
+        let title_font_size = 7.0;
+        let small_font_size = 5.0;
+        let line_height = Mm(2.5);
+
+        let mut y = bottom + CARD_HEIGHT - CARD_PADDING - Mm::from(Pt(title_font_size));
+        self.push_text(
+            truncate(&self.title, Font::Title, title_font_size),
+            Font::Title,
+            title_font_size,
+            Point::new(left, y),
+        );
+        for line in self
+            .subtitle
+            .iter()
+            .chain(&self.metadata_texts)
+            .cloned()
+            .collect::<Vec<_>>()
+        {
+            y -= line_height;
+            self.push_text(
+                truncate(&line, Font::Code, small_font_size),
+                Font::Code,
+                small_font_size,
+                Point::new(left, y),
+            );
+        }
+
+        // The hint at the bottom of the card
+        let hint_baseline = bottom + CARD_PADDING;
+        for (i, line) in [self.language.catalog().card_hint, "age-encryption.org"]
+            .iter()
+            .enumerate()
+        {
+            self.push_text(
+                truncate(line, Font::Title, small_font_size),
+                Font::Title,
+                small_font_size,
+                Point::new(left, hint_baseline + line_height * (1 - i) as f32),
+            );
+        }
+
+        let label_baseline = y - line_height * 3.0;
+        match mode {
+            EncryptionMode::Passphrase(_) => {
+                self.push_text(
+                    truncate(notes_label, Font::Title, 6.0),
+                    Font::Title,
+                    6.0,
+                    Point::new(left, label_baseline),
+                );
+                if !skip_notes_line {
+                    let line_baseline = label_baseline - Mm(6.0);
+                    self.draw_line(
+                        vec![
+                            Point::new(left, line_baseline),
+                            Point::new(left + width, line_baseline),
+                        ],
+                        0.5,
+                        LineDashPattern::default(),
+                    );
+                }
+            }
+            EncryptionMode::Recipients(recipients) => {
+                self.push_text(
+                    truncate(notes_label, Font::Title, small_font_size),
+                    Font::Title,
+                    small_font_size,
+                    Point::new(left, label_baseline),
+                );
+
+                // List as many fingerprints as fit above the hint
+                let lowest = hint_baseline + line_height * 3.0;
+                let fit = ((label_baseline - lowest).0 / line_height.0) as usize;
+                let lines: Vec<String> = if recipients.len() > fit {
+                    let mut lines: Vec<String> = recipients[..fit.saturating_sub(1)]
+                        .iter()
+                        .map(|r| r.short_fingerprint())
+                        .collect();
+                    let more = recipients.len() - lines.len();
+                    lines.push(lang::fill(
+                        self.language.catalog().more_recipients,
+                        &[("count", &more)],
+                    ));
+                    lines
+                } else {
+                    recipients.iter().map(|r| r.short_fingerprint()).collect()
+                };
+
+                for (i, line) in lines.into_iter().enumerate() {
+                    self.push_text(
+                        truncate(&line, Font::Code, small_font_size),
+                        Font::Code,
+                        small_font_size,
+                        Point::new(left, label_baseline - line_height * (i + 1) as f32),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Draw crop marks in the margins around the grid of wallet cards whose
+    /// top left corner is at `left` and `top`
+    fn draw_crop_marks(&mut self, left: Mm, top: Mm, columns: usize, rows: usize) {
+        debug!("Drawing crop marks");
+
+        let right = left + CARD_WIDTH * columns as f32;
+        let bottom = top - CARD_HEIGHT * rows as f32;
+        let thickness = 0.5;
+
+        for column in 0..=columns {
+            let x = left + CARD_WIDTH * column as f32;
+            for (from, to) in [
+                (top + CROP_MARK_GAP, top + CROP_MARK_GAP + CROP_MARK_LENGTH),
+                (
+                    bottom - CROP_MARK_GAP,
+                    bottom - CROP_MARK_GAP - CROP_MARK_LENGTH,
+                ),
+            ] {
+                self.draw_line(
+                    vec![Point::new(x, from), Point::new(x, to)],
+                    thickness,
+                    LineDashPattern::default(),
+                );
+            }
+        }
+
+        for row in 0..=rows {
+            let y = top - CARD_HEIGHT * row as f32;
+            for (from, to) in [
+                (
+                    left - CROP_MARK_GAP,
+                    left - CROP_MARK_GAP - CROP_MARK_LENGTH,
+                ),
+                (
+                    right + CROP_MARK_GAP,
+                    right + CROP_MARK_GAP + CROP_MARK_LENGTH,
+                ),
+            ] {
+                self.draw_line(
+                    vec![Point::new(from, y), Point::new(to, y)],
+                    thickness,
+                    LineDashPattern::default(),
+                );
+            }
+        }
+    }
+}
+
+/// Creation date for new documents: the time in the `SOURCE_DATE_EPOCH`
+/// environment variable for reproducible output, or the current time
+pub fn creation_date() -> DateTime {
+    let Ok(epoch) = std::env::var("SOURCE_DATE_EPOCH") else {
+        return DateTime::now();
+    };
+
+    match epoch
+        .trim()
+        .parse::<i64>()
+        .ok()
+        .and_then(|seconds| DateTime::from_unix_timestamp(seconds).ok())
+    {
+        Some(date) => date,
+        None => {
+            warn!("Ignoring the invalid SOURCE_DATE_EPOCH: {epoch}");
+            DateTime::now()
+        }
     }
 }
 
+/// Build the pages of the laid out documents as one PDF, e.g. for printing a
+/// batch of sheets in one go. The fonts and the creation date are taken from
+/// the first document.
+pub fn combined_pdf(
+    title: &str,
+    documents: &[Document],
+) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+    let first = documents.first().ok_or("No documents to combine")?;
+
+    let mut canvas = PdfCanvas::new(title, &first.fonts)?;
+    canvas.set_created(first.created);
+    for document in documents {
+        document.draw(&mut canvas);
+    }
+
+    Ok(canvas.save_to_bytes())
+}
+
+/// Random sheet identifier of eight characters in two groups, e.g. `7QX2-9F3K`
+pub fn random_sheet_id<R: Rng>(rng: &mut R) -> String {
+    let chars: Vec<char> = (0..8)
+        .map(|_| SHEET_ID_ALPHABET[rng.gen_range(0..SHEET_ID_ALPHABET.len())] as char)
+        .collect();
+    format!(
+        "{}-{}",
+        chars[..4].iter().collect::<String>(),
+        chars[4..].iter().collect::<String>()
+    )
+}
+
 #[test]
 fn test_paper_dimensions_default() {
     let default = PageDimensions::default();
@@ -446,3 +1557,551 @@ fn test_qrcode_too_large() {
     assert!(result.is_err());
     assert!(result.unwrap_err().is::<qrcode::types::QrError>());
 }
+
+#[test]
+fn test_create_pdf_with_recipients() {
+    let document = Document::new(String::from("Recipients"), PageSize::A4).unwrap();
+    let mode =
+        EncryptionMode::Recipients(vec![age::x25519::Identity::generate().to_public().into()]);
+    let result = document.render(false, None, false, String::from("payload"), &mode);
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_insert_recipients() {
+    let mut document = Document::new(String::from("Recipients"), PageSize::A4).unwrap();
+    let recipients: Vec<Recipient> = (0..3)
+        .map(|_| age::x25519::Identity::generate().to_public().into())
+        .collect();
+    let elements_before = document.elements.len();
+    document.insert_recipients(String::from("Encrypted to:"), &recipients);
+
+    let lines = document.elements[elements_before..]
+        .iter()
+        .filter(|element| matches!(element, Element::Text { .. }))
+        .count();
+    // The label and one line per recipient
+    assert_eq!(lines, 4);
+}
+
+#[test]
+fn test_insert_recipients_columns() {
+    let mut document = Document::new(String::from("Recipients"), PageSize::Letter).unwrap();
+    let recipients: Vec<Recipient> = (0..6)
+        .map(|_| age::x25519::Identity::generate().to_public().into())
+        .collect();
+    let elements_before = document.elements.len();
+    document.insert_recipients(String::from("Encrypted to:"), &recipients);
+
+    let columns: std::collections::BTreeSet<i64> = document.elements[elements_before..]
+        .iter()
+        .filter_map(|element| match element {
+            Element::Text { position, .. } => Some(position.x.0.round() as i64),
+            _ => None,
+        })
+        .collect();
+    // The label shares the first column's left edge
+    assert!(columns.len() > 1);
+
+    let lowest = document.elements[elements_before..]
+        .iter()
+        .filter_map(|element| match element {
+            Element::Text { position, .. } => Some(position.y),
+            _ => None,
+        })
+        .fold(Pt(f32::INFINITY), |a, b| if b < a { b } else { a });
+    // Every recipient stays above the divider in the middle of the page
+    assert!(lowest > (PageSize::Letter.dimensions().height / 2.0).into_pt());
+}
+
+#[test]
+fn test_create_split_pdf() {
+    let mut document = Document::new(String::from("Split"), PageSize::A4).unwrap();
+    document.split = true;
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) =
+        crate::encryption::encrypt(&mut "x".repeat(5000).as_bytes(), &mode).unwrap();
+    let result = document.render(false, None, false, encrypted, &mode);
+
+    assert!(result.is_ok());
+}
+
+#[test]
+fn test_create_pdf_too_large_without_split() {
+    let document = Document::new(String::from("Too large"), PageSize::A4).unwrap();
+    let mode = EncryptionMode::from("snakeoil");
+    let result = document.render(
+        false,
+        None,
+        false,
+        String::from(include_str!("../tests/data/too_large.txt")),
+        &mode,
+    );
+
+    assert!(result.unwrap_err().is::<qrcode::types::QrError>());
+}
+
+#[test]
+fn test_new_page() {
+    let mut document = Document::new(String::from("Pages"), PageSize::A4).unwrap();
+    document.insert_footer();
+    document.new_page();
+    document.insert_footer();
+
+    assert_eq!(document.pages.len(), 1);
+
+    let bytes = document.save_to_bytes().unwrap();
+    let pdf = String::from_utf8_lossy(&bytes);
+    assert!(pdf.contains("/Count 2"));
+}
+
+#[test]
+fn test_insert_subtitle() {
+    let mut document = Document::new(String::from("Shares"), PageSize::A4).unwrap();
+    document.subtitle = Some(String::from("Share 1 of 3 · 2 needed · Set 1a2b3c4d"));
+    document.insert_title_text(String::from("Shares"));
+
+    let texts = document
+        .elements
+        .iter()
+        .filter(|element| matches!(element, Element::Text { .. }))
+        .count();
+    assert_eq!(texts, 2);
+}
+
+#[test]
+fn test_metadata_line() {
+    let mut document = Document::new(String::from("Metadata"), PageSize::A4).unwrap();
+    document.subtitle = Some(String::from("Share 1 of 3 · 2 needed · Set 1a2b3c4d"));
+    document.metadata = Metadata::from_fields(&[MetadataField::Sha256, MetadataField::SheetId]);
+    document.sheet_id = String::from("7QX2-9F3K");
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    let sha256 = crate::encryption::ciphertext_sha256(&encrypted);
+    document
+        .layout(false, None, false, encrypted, &mode)
+        .unwrap();
+
+    let texts: Vec<(&str, Point)> = document
+        .elements
+        .iter()
+        .filter_map(|element| match element {
+            Element::Text { text, position, .. } => Some((text.as_str(), *position)),
+            _ => None,
+        })
+        .collect();
+    let expected = format!("SHA-256 {}… · Sheet 7QX2-9F3K", &sha256[..16]);
+    let (_, metadata) = texts.iter().find(|(text, _)| *text == expected).unwrap();
+
+    // Below the title and the subtitle, and above the QR code
+    assert!(texts[..2]
+        .iter()
+        .all(|(_, position)| position.y > metadata.y));
+    assert!(Mm::from(metadata.y) > document.qrcode_bottom_edge() + document.qrcode_size());
+}
+
+#[test]
+fn test_metadata_disabled() {
+    let mut document = Document::new(String::from("Metadata"), PageSize::A4).unwrap();
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    document
+        .layout(false, None, false, encrypted, &mode)
+        .unwrap();
+
+    assert!(!document.elements.iter().any(|element| matches!(
+        element,
+        Element::Text { text, .. } if text.contains(&document.sheet_id)
+    )));
+}
+
+#[test]
+fn test_random_sheet_id() {
+    let id = random_sheet_id(&mut rand::thread_rng());
+    assert_eq!(id.len(), 9);
+    assert_eq!(id.chars().nth(4), Some('-'));
+    assert!(id
+        .chars()
+        .filter(|c| *c != '-')
+        .all(|c| SHEET_ID_ALPHABET.contains(&(c as u8))));
+    assert_ne!(id, random_sheet_id(&mut rand::thread_rng()));
+}
+
+#[test]
+fn test_insert_pem_text_with_checksums() {
+    let mut document = Document::new(String::from("Checksums"), PageSize::A4).unwrap();
+    let (_, encrypted) =
+        crate::encryption::encrypt(&mut "x".repeat(1000).as_bytes(), &"snakeoil".into()).unwrap();
+    let annotated = checksums::annotate(&encrypted);
+    document.insert_pem_text(annotated.clone());
+
+    let font_size = document
+        .elements
+        .iter()
+        .find_map(|element| match element {
+            Element::Text { size, .. } => Some(size.0),
+            _ => None,
+        })
+        .unwrap();
+    // 72 characters per line don't fit on A4 at the default 13 pt
+    assert!(font_size < 13.0);
+    let widest = annotated
+        .lines()
+        .map(|line| document.fonts.text_width(line, Font::Code, Pt(font_size)))
+        .fold(Pt(0.0), |a, b| if b > a { b } else { a });
+    assert!(Mm::from(widest) <= Mm(190.0));
+}
+
+#[test]
+fn test_insert_pem_text_lines() {
+    let mut document = Document::new(String::from("PEM"), PageSize::A4).unwrap();
+    document.insert_pem_text(String::from("first\nsecond\nthird\n"));
+
+    let baselines: Vec<Pt> = document
+        .elements
+        .iter()
+        .filter_map(|element| match element {
+            Element::Text { position, .. } => Some(position.y),
+            _ => None,
+        })
+        .collect();
+    // One line of text per PEM line, 15 pt apart below the divider
+    assert_eq!(baselines.len(), 3);
+    assert_eq!(baselines[0], document.pem_baseline(Pt(13.0)).into_pt());
+    assert!((baselines[0].0 - baselines[2].0 - 30.0).abs() < 0.01);
+}
+
+#[test]
+fn test_combined_pdf() {
+    let mode = EncryptionMode::from("snakeoil");
+    let mut documents = vec![];
+    let mut ciphertexts = vec![];
+    for title in ["First", "Second"] {
+        let mut document = Document::new(String::from(title), PageSize::A4).unwrap();
+        let (_, encrypted) = crate::encryption::encrypt(&mut title.as_bytes(), &mode).unwrap();
+        document
+            .layout(false, None, false, encrypted.clone(), &mode)
+            .unwrap();
+        documents.push(document);
+        ciphertexts.push(encrypted);
+    }
+
+    let bytes = combined_pdf("Batch", &documents).unwrap();
+    let texts = crate::verify::decode_qr_codes(&bytes).unwrap();
+    assert_eq!(texts.len(), 2);
+    for (text, encrypted) in texts.iter().zip(&ciphertexts) {
+        assert_eq!(text.trim(), encrypted.trim());
+    }
+
+    assert!(combined_pdf("Batch", &[]).is_err());
+}
+
+#[test]
+fn test_create_svg() {
+    let mut document = Document::new(String::from("SVG"), PageSize::A4).unwrap();
+    document.format = Format::Svg;
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    let bytes = document
+        .render(false, None, false, encrypted, &mode)
+        .unwrap();
+    let svg = String::from_utf8(bytes).unwrap();
+
+    assert!(svg.starts_with("<?xml"));
+    assert!(svg.contains("<title>SVG</title>"));
+    assert!(svg.contains(">-----BEGIN AGE ENCRYPTED FILE-----</text>"));
+    assert!(svg.contains(">Passphrase:</text>"));
+    assert_eq!(svg.matches("fill-rule=\"evenodd\"").count(), 1);
+}
+
+#[test]
+#[allow(deprecated)]
+fn test_create_pdf_ignores_format() {
+    let mut document = Document::new(String::from("PDF"), PageSize::A4).unwrap();
+    document.format = Format::Svg;
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    let bytes = document
+        .create_pdf(false, None, false, encrypted, &mode)
+        .unwrap();
+
+    assert!(bytes.starts_with(b"%PDF"));
+}
+
+#[test]
+fn test_draw_on_custom_canvas() {
+    #[derive(Default)]
+    struct Counter {
+        pages: usize,
+        texts: Vec<String>,
+        lines: usize,
+        polygons: usize,
+    }
+
+    impl Canvas for Counter {
+        fn begin_page(&mut self, _dimensions: &PageDimensions) {
+            self.pages += 1;
+        }
+
+        fn text(&mut self, text: &str, _font: Font, _size: Pt, _position: Point) {
+            self.texts.push(text.to_string());
+        }
+
+        fn line(&mut self, _points: &[Point], _thickness: Pt, _dash_pattern: &LineDashPattern) {
+            self.lines += 1;
+        }
+
+        fn polygon(&mut self, _rings: &[Vec<Point>]) {
+            self.polygons += 1;
+        }
+    }
+
+    let mut document = Document::new(String::from("Canvas"), PageSize::A4).unwrap();
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    document
+        .layout(false, None, false, encrypted, &mode)
+        .unwrap();
+
+    let mut canvas = Counter::default();
+    document.draw(&mut canvas);
+
+    assert_eq!(canvas.pages, 1);
+    assert_eq!(canvas.texts[0], "Canvas");
+    assert!(canvas.texts.contains(&String::from("Passphrase:")));
+    // The notes line and the divider
+    assert_eq!(canvas.lines, 2);
+    assert!(canvas.polygons > 0);
+}
+
+#[test]
+fn test_create_landscape() {
+    let mut document = Document::new(String::from("Landscape"), PageSize::A4).unwrap();
+    document.orientation = Orientation::Landscape;
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    document
+        .layout(false, None, false, encrypted, &mode)
+        .unwrap();
+
+    let center = (document.dimensions().width / 2.0).into_pt();
+    let qr_code = document
+        .elements
+        .iter()
+        .find_map(|element| match element {
+            Element::QrCode(geometry) => Some(geometry),
+            _ => None,
+        })
+        .unwrap();
+    let qr_code_size = Pt(qr_code.module_size.0 * qr_code.modules_count as f32);
+    assert!(qr_code.origin.x + qr_code_size < center);
+    assert!(qr_code_size > PageSize::A4.qrcode_size().into_pt());
+
+    let pem_position = document
+        .elements
+        .iter()
+        .find_map(|element| match element {
+            Element::Text { text, position, .. } if text.starts_with("-----BEGIN") => {
+                Some(position)
+            }
+            _ => None,
+        })
+        .unwrap();
+    assert!(pem_position.x > center);
+}
+
+#[test]
+fn test_create_wallet_cards() {
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    let qr_codes = |document: &Document| {
+        document
+            .elements
+            .iter()
+            .filter(|element| matches!(element, Element::QrCode(_)))
+            .count()
+    };
+
+    for (page_size, count) in [(PageSize::A4, 10), (PageSize::Letter, 8)] {
+        let mut document = Document::new(String::from("Card"), page_size).unwrap();
+        document.wallet_card = true;
+        document
+            .layout(false, None, false, encrypted.clone(), &mode)
+            .unwrap();
+        assert_eq!(qr_codes(&document), count);
+    }
+
+    let mut document = Document::new(String::from("Card"), PageSize::A4).unwrap();
+    document.wallet_card = true;
+    document.card_count = Some(3);
+    document
+        .layout(false, None, false, encrypted, &mode)
+        .unwrap();
+    assert_eq!(qr_codes(&document), 3);
+}
+
+#[test]
+fn test_wallet_card_text_fits() {
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    let width = (CARD_WIDTH - CARD_HEIGHT - CARD_PADDING).into_pt();
+
+    for language in [Language::En, Language::Fi, Language::De, Language::Fr] {
+        let mut document = Document::new(String::from("Card"), PageSize::A4).unwrap();
+        document.wallet_card = true;
+        document.card_count = Some(1);
+        document.language = language;
+        document
+            .layout(false, None, false, encrypted.clone(), &mode)
+            .unwrap();
+
+        for element in &document.elements {
+            if let Element::Text {
+                text, font, size, ..
+            } = element
+            {
+                assert!(
+                    document.fonts.text_width(text, *font, *size) <= width,
+                    "{text:?} is wider than the card"
+                );
+            }
+        }
+    }
+}
+
+#[test]
+fn test_create_wallet_cards_too_large() {
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &[b'a'; 1024][..], &mode).unwrap();
+    let mut document = Document::new(String::from("Card"), PageSize::A4).unwrap();
+    document.wallet_card = true;
+
+    let result = document.layout(false, None, false, encrypted, &mode);
+    assert!(result.unwrap_err().is::<qrcode::types::QrError>());
+}
+
+#[test]
+fn test_create_copies() {
+    let mut document = Document::new(String::from("Copies"), PageSize::A4).unwrap();
+    document.copies = 2;
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    document
+        .layout(false, None, false, encrypted, &mode)
+        .unwrap();
+
+    let center = (document.dimensions().height / 2.0).into_pt();
+    let origins: Vec<Pt> = document
+        .elements
+        .iter()
+        .filter_map(|element| match element {
+            Element::QrCode(geometry) => Some(geometry.origin.y),
+            _ => None,
+        })
+        .collect();
+    assert_eq!(origins.len(), 2);
+    assert!(origins[0] > center);
+    assert!(origins[1] < center);
+
+    let begin_lines = document
+        .elements
+        .iter()
+        .filter(|element| {
+            matches!(element, Element::Text { text, .. } if text.starts_with("-----BEGIN"))
+        })
+        .count();
+    assert_eq!(begin_lines, 2);
+
+    // The dashed cut line across the middle of the page
+    assert!(document.elements.iter().any(|element| matches!(
+        element,
+        Element::Line { points, .. } if points.len() == 2 && points.iter().all(|p| p.y == center)
+    )));
+}
+
+#[test]
+fn test_create_instructions() {
+    for page_size in [PageSize::A4, PageSize::IndexCard] {
+        let mut document = Document::new(String::from("Instructions"), page_size).unwrap();
+        document.instructions = true;
+        let mode = EncryptionMode::from("snakeoil");
+        let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+        document
+            .layout(false, None, false, encrypted, &mode)
+            .unwrap();
+
+        assert_eq!(document.pages.len(), 1);
+        let texts: Vec<(&str, Point)> = document
+            .elements
+            .iter()
+            .filter_map(|element| match element {
+                Element::Text { text, position, .. } => Some((text.as_str(), *position)),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(texts[0].0, "How to restore this backup");
+        assert!(texts
+            .iter()
+            .any(|(text, _)| *text == "age -d -o secret.txt backup.age"));
+        assert!(texts
+            .iter()
+            .any(|(text, _)| text.starts_with("Created with PaperAge")));
+
+        // Everything fits above the bottom margin
+        let margin = document.dimensions().margin.into_pt();
+        assert!(texts.iter().all(|(_, position)| position.y >= margin));
+    }
+}
+
+#[test]
+fn test_create_pdf_in_language() {
+    let mut document = Document::new(String::from("Kieli"), PageSize::A4).unwrap();
+    document.language = Language::Fi;
+    document.instructions = true;
+    let mode = EncryptionMode::from("snakeoil");
+    let (_, encrypted) = crate::encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+    document
+        .layout(false, None, false, encrypted, &mode)
+        .unwrap();
+
+    let texts: Vec<&str> = document
+        .pages
+        .iter()
+        .chain([&document.elements])
+        .flatten()
+        .filter_map(|element| match element {
+            Element::Text { text, .. } => Some(text.as_str()),
+            _ => None,
+        })
+        .collect();
+    let catalog = Language::Fi.catalog();
+    assert!(texts.contains(&catalog.passphrase_label));
+    assert!(texts.contains(&catalog.footer));
+    assert!(texts.contains(&catalog.instructions_title));
+    assert!(!texts.contains(&Language::En.catalog().footer));
+}
+
+#[test]
+fn test_insert_notes_field_fits_label() {
+    let mut document = Document::new(String::from("Notes"), PageSize::A4).unwrap();
+    let label = "A notes label that is far too long to fit the page at full size: ".repeat(2);
+    document.insert_notes_field(label.clone(), false);
+
+    let (size, position) = document
+        .elements
+        .iter()
+        .find_map(|element| match element {
+            Element::Text { size, position, .. } => Some((*size, *position)),
+            _ => None,
+        })
+        .unwrap();
+    let right = position.x + document.fonts.text_width(&label, Font::Title, size);
+    assert!(size < Pt(13.0));
+    assert!(right.0 <= (A4_PAGE.width - A4_PAGE.margin).into_pt().0 + 0.01);
+    // No room left for the placeholder line
+    assert!(!document
+        .elements
+        .iter()
+        .any(|element| matches!(element, Element::Line { .. })));
+}
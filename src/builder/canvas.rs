@@ -0,0 +1,47 @@
+//! Drawing targets for the page layout
+//!
+//! [`Document`](super::Document) lays out the pages as lists of
+//! [`Element`](super::layout::Element)s and draws them on a [`Canvas`]. The
+//! PDF, SVG, and PNG output formats are canvases, and other targets such as
+//! label printer languages can be added by implementing the trait.
+
+use printpdf::{LineDashPattern, Point, Pt};
+
+use super::layout::Font;
+use super::qrcode_ops::QrGeometry;
+use crate::page::PageDimensions;
+
+/// A target that the pages can be drawn on.
+///
+/// Coordinates are in points from the bottom left corner of the page, like in
+/// PDF. Text is black, lines are light gray, and polygons are filled black.
+pub trait Canvas {
+    /// Start a new, blank page
+    fn begin_page(&mut self, dimensions: &PageDimensions);
+
+    /// Draw a line of text starting from the baseline at `position`
+    fn text(&mut self, text: &str, font: Font, size: Pt, position: Point);
+
+    /// Draw a line through the points. A zero thickness is the thinnest line
+    /// the target can draw.
+    fn line(&mut self, points: &[Point], thickness: Pt, dash_pattern: &LineDashPattern);
+
+    /// Fill the polygon made of the closed rings with the even-odd rule, so
+    /// that the inner rings are holes
+    fn polygon(&mut self, rings: &[Vec<Point>]);
+
+    /// Draw a QR code. By default, each connected area of dark modules is
+    /// filled as a polygon.
+    fn qr_code(&mut self, geometry: &QrGeometry) {
+        for chains in &geometry.polygons {
+            let rings: Vec<Vec<Point>> = chains
+                .iter()
+                .map(|chain| chain.iter().map(|&gp| geometry.point(gp)).collect())
+                .collect();
+            self.polygon(&rings);
+        }
+    }
+
+    /// Finish the current page
+    fn end_page(&mut self) {}
+}
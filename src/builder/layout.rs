@@ -0,0 +1,97 @@
+//! Output format independent page layout
+//!
+//! The builder lays out each page as a list of elements, which are then drawn
+//! on a [`Canvas`](super::canvas::Canvas). Coordinates are in points from the bottom
+//! left corner of the page, like in PDF.
+
+use printpdf::{LineDashPattern, Point, Pt};
+
+use super::canvas::Canvas;
+use super::qrcode_ops::QrGeometry;
+
+/// The fonts used on the page
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Font {
+    /// Medium weight font for the title, labels, and the footer
+    Title,
+    /// Regular weight font for the PEM text and the other small print
+    Code,
+    /// User provided font for the characters that the other fonts don't cover
+    Fallback,
+}
+
+/// A single element on the page
+#[derive(Clone, Debug)]
+pub enum Element {
+    /// A line of black text, starting from the baseline at `position`
+    Text {
+        text: String,
+        font: Font,
+        size: Pt,
+        position: Point,
+    },
+    /// A light grey line through the points
+    Line {
+        points: Vec<Point>,
+        thickness: Pt,
+        dash_pattern: LineDashPattern,
+    },
+    /// A black QR code
+    QrCode(QrGeometry),
+}
+
+impl Element {
+    /// The element moved by the offset
+    pub fn translated(&self, offset: Point) -> Element {
+        let translate = |p: &Point| Point {
+            x: p.x + offset.x,
+            y: p.y + offset.y,
+        };
+
+        match self {
+            Element::Text {
+                text,
+                font,
+                size,
+                position,
+            } => Element::Text {
+                text: text.clone(),
+                font: *font,
+                size: *size,
+                position: translate(position),
+            },
+            Element::Line {
+                points,
+                thickness,
+                dash_pattern,
+            } => Element::Line {
+                points: points.iter().map(translate).collect(),
+                thickness: *thickness,
+                dash_pattern: dash_pattern.clone(),
+            },
+            Element::QrCode(geometry) => {
+                let mut geometry = geometry.clone();
+                geometry.origin = translate(&geometry.origin);
+                Element::QrCode(geometry)
+            }
+        }
+    }
+
+    /// Draw the element on the canvas
+    pub fn draw(&self, canvas: &mut dyn Canvas) {
+        match self {
+            Element::Text {
+                text,
+                font,
+                size,
+                position,
+            } => canvas.text(text, *font, *size, *position),
+            Element::Line {
+                points,
+                thickness,
+                dash_pattern,
+            } => canvas.line(points, *thickness, dash_pattern),
+            Element::QrCode(geometry) => canvas.qr_code(geometry),
+        }
+    }
+}
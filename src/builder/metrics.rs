@@ -0,0 +1,447 @@
+//! Text measurements with the metrics of the fonts
+//!
+//! The layout measures text with the advance widths of the glyphs, like they
+//! are set in the PDF, to fit it in the space available on the page.
+//!
+//! The embedded IBM Plex Mono covers Latin, Greek, and Cyrillic scripts. It
+//! can be replaced with user provided [`UserFont`]s, and text in the other
+//! scripts, such as CJK titles, is set in a user provided fallback font.
+
+use std::sync::{Arc, OnceLock};
+
+use ab_glyph::{FontArc, FontRef};
+use printpdf::{ParsedFont, Pt};
+use unicode_segmentation::UnicodeSegmentation;
+
+use super::layout::Font;
+use super::{CODE_FONT_BYTES, TITLE_FONT_BYTES};
+
+/// The parsed embedded font
+fn font_ref(font: Font) -> &'static FontRef<'static> {
+    static TITLE_FONT: OnceLock<FontRef<'static>> = OnceLock::new();
+    static CODE_FONT: OnceLock<FontRef<'static>> = OnceLock::new();
+
+    let (cell, bytes) = match font {
+        Font::Title | Font::Fallback => (&TITLE_FONT, TITLE_FONT_BYTES),
+        Font::Code => (&CODE_FONT, CODE_FONT_BYTES),
+    };
+    cell.get_or_init(|| FontRef::try_from_slice(bytes).expect("the embedded font is valid"))
+}
+
+/// The embedded font parsed like in the PDF, for the glyph coverage checks
+fn parsed_font(font: Font) -> &'static ParsedFont {
+    static TITLE_FONT: OnceLock<ParsedFont> = OnceLock::new();
+    static CODE_FONT: OnceLock<ParsedFont> = OnceLock::new();
+
+    let (cell, bytes) = match font {
+        Font::Title | Font::Fallback => (&TITLE_FONT, TITLE_FONT_BYTES),
+        Font::Code => (&CODE_FONT, CODE_FONT_BYTES),
+    };
+    cell.get_or_init(|| {
+        ParsedFont::from_bytes(bytes, 0, &mut Vec::new()).expect("the embedded font is valid")
+    })
+}
+
+/// Whether the font has a glyph for the character
+fn has_glyph(font: &ParsedFont, c: char) -> bool {
+    font.lookup_glyph_index(c as u32)
+        .is_some_and(|index| index != 0)
+}
+
+/// Width of the text in ems, i.e. at a font size of one point
+fn em_width<F: ab_glyph::Font>(font: &F, text: &str) -> f32 {
+    let units_per_em = font.units_per_em().unwrap_or(1000.0);
+    let advance: f32 = text
+        .chars()
+        .map(|c| font.h_advance_unscaled(font.glyph_id(c)))
+        .sum();
+
+    advance / units_per_em
+}
+
+/// A user provided TrueType or OpenType font
+#[derive(Clone)]
+pub struct UserFont {
+    /// The raw font file
+    pub bytes: Arc<[u8]>,
+    /// The font parsed for the PDF
+    pub parsed: Arc<ParsedFont>,
+    /// The font parsed for measuring and rasterizing the text
+    pub font: FontArc,
+}
+
+impl UserFont {
+    /// Parse the font file
+    pub fn from_bytes(bytes: Vec<u8>) -> Result<UserFont, Box<dyn std::error::Error>> {
+        let parsed =
+            ParsedFont::from_bytes(&bytes, 0, &mut Vec::new()).ok_or("Invalid font file")?;
+        let font = FontArc::try_from_vec(bytes.clone())?;
+
+        Ok(UserFont {
+            bytes: bytes.into(),
+            parsed: Arc::new(parsed),
+            font,
+        })
+    }
+
+    /// The name, copyright, and license of the font from its naming table
+    pub fn license(&self) -> Option<String> {
+        let face = ttf_parser::Face::parse(&self.bytes, 0).ok()?;
+        let name = |id: u16| {
+            face.names()
+                .into_iter()
+                .filter(|name| name.name_id == id)
+                .find_map(|name| name.to_string())
+        };
+
+        let lines: Vec<String> = [
+            ttf_parser::name_id::FULL_NAME,
+            ttf_parser::name_id::COPYRIGHT_NOTICE,
+            ttf_parser::name_id::LICENSE,
+            ttf_parser::name_id::LICENSE_URL,
+        ]
+        .into_iter()
+        .filter_map(name)
+        .collect();
+
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+}
+
+/// The fonts of the page: the embedded IBM Plex Mono fonts or user provided
+/// replacements, and the optional fallback font
+#[derive(Clone, Default)]
+pub struct Fonts {
+    /// Font for the title, the labels, and the footer instead of IBM Plex
+    /// Mono Medium
+    pub title: Option<UserFont>,
+    /// Font for the PEM text and the other small print instead of IBM Plex
+    /// Mono Regular
+    pub code: Option<UserFont>,
+    /// Font for the characters that the other fonts don't cover
+    pub fallback: Option<UserFont>,
+}
+
+impl Fonts {
+    /// The user provided font, if any
+    pub fn user_font(&self, font: Font) -> Option<&UserFont> {
+        match font {
+            Font::Title => self.title.as_ref(),
+            Font::Code => self.code.as_ref(),
+            Font::Fallback => self.fallback.as_ref(),
+        }
+    }
+
+    /// The font file, or `None` for the fallback font if there is none
+    pub fn bytes(&self, font: Font) -> Option<&[u8]> {
+        match (font, self.user_font(font)) {
+            (_, Some(user_font)) => Some(&user_font.bytes),
+            (Font::Title, None) => Some(TITLE_FONT_BYTES),
+            (Font::Code, None) => Some(CODE_FONT_BYTES),
+            (Font::Fallback, None) => None,
+        }
+    }
+
+    /// The font parsed for the PDF, or `None` for the fallback font if there
+    /// is none
+    pub fn parsed(&self, font: Font) -> Option<&ParsedFont> {
+        match (font, self.user_font(font)) {
+            (_, Some(user_font)) => Some(&user_font.parsed),
+            (Font::Fallback, None) => None,
+            (font, None) => Some(parsed_font(font)),
+        }
+    }
+
+    /// The font for rasterizing the text, or `None` for the fallback font if
+    /// there is none
+    pub fn glyphs(&self, font: Font) -> Option<FontArc> {
+        match (font, self.user_font(font)) {
+            (_, Some(user_font)) => Some(user_font.font.clone()),
+            (Font::Fallback, None) => None,
+            (font, None) => Some(FontArc::new(font_ref(font).clone())),
+        }
+    }
+
+    /// Width of the text in ems when set in the font
+    fn em_width(&self, text: &str, font: Font) -> f32 {
+        match self.user_font(font) {
+            Some(user_font) => em_width(&user_font.font, text),
+            None => em_width(font_ref(font), text),
+        }
+    }
+
+    /// Whether the font has glyphs for all the characters of the grapheme
+    fn covers(&self, font: Font, grapheme: &str) -> bool {
+        match self.parsed(font) {
+            Some(parsed) => grapheme
+                .chars()
+                .all(|c| c.is_control() || has_glyph(parsed, c)),
+            None => false,
+        }
+    }
+
+    /// The font to set the grapheme in: the given font if it covers the
+    /// grapheme, otherwise the fallback font if that covers it
+    fn font_for(&self, font: Font, grapheme: &str) -> Font {
+        if font != Font::Fallback
+            && !self.covers(font, grapheme)
+            && self.covers(Font::Fallback, grapheme)
+        {
+            Font::Fallback
+        } else {
+            font
+        }
+    }
+
+    /// Split the text into runs of graphemes set in the same font
+    pub fn runs<'a>(&self, text: &'a str, font: Font) -> Vec<(&'a str, Font)> {
+        let mut runs: Vec<(&'a str, Font)> = vec![];
+        let mut start = 0;
+        let mut current = None;
+        for (i, grapheme) in text.grapheme_indices(true) {
+            let run_font = self.font_for(font, grapheme);
+            if let Some(current) = current.filter(|&current| current != run_font) {
+                runs.push((&text[start..i], current));
+                start = i;
+            }
+            current = Some(run_font);
+        }
+        if let Some(current) = current {
+            runs.push((&text[start..], current));
+        }
+        runs
+    }
+
+    /// Characters of the text that neither the font nor the fallback font
+    /// have a glyph for
+    pub fn missing_glyphs(&self, text: &str, font: Font) -> Vec<char> {
+        let mut missing: Vec<char> = vec![];
+        for grapheme in text.graphemes(true) {
+            if self.covers(font, grapheme) || self.covers(Font::Fallback, grapheme) {
+                continue;
+            }
+            for c in grapheme.chars() {
+                if !missing.contains(&c) {
+                    missing.push(c);
+                }
+            }
+        }
+        missing
+    }
+
+    /// Width of the text when set in the font at the given size, with the
+    /// characters that the font doesn't cover set in the fallback font
+    pub fn text_width(&self, text: &str, font: Font, size: Pt) -> Pt {
+        let ems: f32 = self
+            .runs(text, font)
+            .into_iter()
+            .map(|(run, font)| self.em_width(run, font))
+            .sum();
+
+        Pt(ems * size.0)
+    }
+
+    /// The largest font size, up to `max_size`, at which the text fits in `width`
+    pub fn fit_font_size(&self, text: &str, font: Font, max_size: Pt, width: Pt) -> Pt {
+        let unit_width = self.text_width(text, font, Pt(1.0)).0;
+        if unit_width <= 0.0 {
+            return max_size;
+        }
+
+        Pt(max_size.0.min(width.0 / unit_width))
+    }
+
+    /// Break the text into lines that fit in `width` between words. Words
+    /// that are wider than `width`, such as text in scripts that don't
+    /// separate words with spaces, are broken between graphemes.
+    pub fn wrap_to_width(&self, text: &str, font: Font, size: Pt, width: Pt) -> Vec<String> {
+        let mut lines = vec![];
+        let mut line = String::new();
+        for word in text.split_whitespace() {
+            let candidate = if line.is_empty() {
+                word.to_string()
+            } else {
+                format!("{line} {word}")
+            };
+            if self.text_width(&candidate, font, size) <= width {
+                line = candidate;
+                continue;
+            }
+
+            if self.text_width(word, font, size) <= width {
+                lines.push(std::mem::replace(&mut line, word.to_string()));
+                continue;
+            }
+
+            // Fill up the line with the start of the word and break the rest
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            for grapheme in word.graphemes(true) {
+                let candidate = format!("{line}{grapheme}");
+                if self.text_width(&candidate, font, size) > width && !line.trim().is_empty() {
+                    lines.push(std::mem::take(&mut line).trim_end().to_string());
+                    line.push_str(grapheme);
+                } else {
+                    line = candidate;
+                }
+            }
+        }
+        if !line.is_empty() {
+            lines.push(line);
+        }
+        lines
+    }
+
+    /// Truncate the text to fit in `width`, marking the cut with an ellipsis.
+    /// The text is only cut between graphemes.
+    pub fn truncate_to_width(&self, text: &str, font: Font, size: Pt, width: Pt) -> String {
+        if self.text_width(text, font, size) <= width {
+            return text.to_string();
+        }
+
+        let available = width.0 - self.text_width("…", font, size).0;
+        let mut used = 0.0;
+        let mut truncated = String::new();
+        for grapheme in text.graphemes(true) {
+            used += self.text_width(grapheme, font, size).0;
+            if used > available {
+                break;
+            }
+            truncated.push_str(grapheme);
+        }
+        truncated.push('…');
+        truncated
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plex_fallback() -> Fonts {
+        Fonts {
+            fallback: Some(UserFont::from_bytes(TITLE_FONT_BYTES.to_vec()).unwrap()),
+            ..Fonts::default()
+        }
+    }
+
+    #[test]
+    fn test_text_width() {
+        let fonts = Fonts::default();
+        // IBM Plex Mono glyphs are 600 units wide on a 1000 unit em square
+        assert_eq!(fonts.text_width("abcd", Font::Code, Pt(10.0)), Pt(24.0));
+        assert_eq!(fonts.text_width("äöüé", Font::Title, Pt(10.0)), Pt(24.0));
+        assert_eq!(fonts.text_width("", Font::Code, Pt(10.0)), Pt(0.0));
+    }
+
+    #[test]
+    fn test_fit_font_size() {
+        let fonts = Fonts::default();
+        assert_eq!(
+            fonts.fit_font_size("abcd", Font::Code, Pt(13.0), Pt(100.0)),
+            Pt(13.0)
+        );
+        assert_eq!(
+            fonts.fit_font_size("abcd", Font::Code, Pt(13.0), Pt(12.0)),
+            Pt(5.0)
+        );
+        assert_eq!(
+            fonts.fit_font_size("", Font::Code, Pt(13.0), Pt(0.0)),
+            Pt(13.0)
+        );
+    }
+
+    #[test]
+    fn test_truncate_to_width() {
+        let fonts = Fonts::default();
+        let width = |chars: f32| Pt(6.0 * chars);
+        assert_eq!(
+            fonts.truncate_to_width("short", Font::Code, Pt(10.0), width(10.0)),
+            "short"
+        );
+        assert_eq!(
+            fonts.truncate_to_width("ssh-ed25519 SHA256:abc", Font::Code, Pt(10.0), width(13.0)),
+            "ssh-ed25519 …"
+        );
+        // The combining diaeresis stays with its base character
+        assert_eq!(
+            fonts.truncate_to_width("Pa\u{308}a\u{308}syavain", Font::Code, Pt(10.0), width(3.0)),
+            "Pa\u{308}…"
+        );
+    }
+
+    #[test]
+    fn test_wrap_to_width() {
+        let fonts = Fonts::default();
+        let width = |chars: f32| Pt(6.0 * chars);
+        assert_eq!(
+            fonts.wrap_to_width("Scan the QR code", Font::Code, Pt(10.0), width(10.0)),
+            vec!["Scan the", "QR code"]
+        );
+        assert_eq!(
+            fonts.wrap_to_width("see age-encryption.org", Font::Code, Pt(10.0), width(10.0)),
+            vec!["see age-en", "cryption.o", "rg"]
+        );
+        assert!(fonts
+            .wrap_to_width("", Font::Code, Pt(10.0), width(10.0))
+            .is_empty());
+    }
+
+    #[test]
+    fn test_runs() {
+        let fonts = plex_fallback();
+        assert_eq!(
+            fonts.runs("Pääsyavain – Tuotanto", Font::Title),
+            vec![("Pääsyavain – Tuotanto", Font::Title)]
+        );
+        // Neither font covers CJK, so the text stays in the given font
+        assert_eq!(
+            fonts.runs("鍵 key", Font::Code),
+            vec![("鍵 key", Font::Code)]
+        );
+        assert_eq!(fonts.runs("", Font::Code), vec![]);
+    }
+
+    #[test]
+    fn test_missing_glyphs() {
+        assert_eq!(
+            Fonts::default().missing_glyphs("Pääsyavain – Tuotanto", Font::Title),
+            vec![]
+        );
+        assert_eq!(
+            Fonts::default().missing_glyphs("秘密の鍵 鍵", Font::Title),
+            vec!['秘', '密', 'の', '鍵']
+        );
+        assert_eq!(
+            plex_fallback().missing_glyphs("秘密", Font::Title),
+            vec!['秘', '密']
+        );
+    }
+
+    #[test]
+    fn test_invalid_user_font() {
+        assert!(UserFont::from_bytes(b"not a font".to_vec()).is_err());
+    }
+
+    #[test]
+    fn test_user_fonts() {
+        let fonts = Fonts {
+            code: Some(UserFont::from_bytes(TITLE_FONT_BYTES.to_vec()).unwrap()),
+            ..Fonts::default()
+        };
+        assert_eq!(fonts.bytes(Font::Title), Some(TITLE_FONT_BYTES));
+        assert_eq!(fonts.bytes(Font::Code), Some(TITLE_FONT_BYTES));
+        assert_eq!(fonts.bytes(Font::Fallback), None);
+        assert!(fonts.parsed(Font::Fallback).is_none());
+        assert_eq!(fonts.text_width("abcd", Font::Code, Pt(10.0)), Pt(24.0));
+    }
+
+    #[test]
+    fn test_user_font_license() {
+        let font = UserFont::from_bytes(CODE_FONT_BYTES.to_vec()).unwrap();
+        let license = font.license().unwrap();
+        assert!(license.starts_with("IBM Plex Mono"));
+        assert!(license.contains("SIL Open Font License"));
+    }
+}
@@ -0,0 +1,202 @@
+//! PDF output
+
+use std::io::Write;
+
+use log::debug;
+use printpdf::{
+    Color, DateTime, Line, LineDashPattern, LinePoint, Op, PaintMode, PdfDocument, PdfFontHandle,
+    PdfPage, PdfSaveOptions, Point, Polygon, PolygonRing, Pt, Rect, Rgb, TextItem, WindingOrder,
+};
+
+use super::canvas::Canvas;
+use super::layout::Font;
+use super::metrics::Fonts;
+use super::qrcode_ops::QrGeometry;
+use super::{creation_date, VERSION};
+use crate::page::PageDimensions;
+
+/// A canvas that draws the pages of a PDF document
+pub struct PdfCanvas {
+    /// The printpdf PDF document
+    pub doc: PdfDocument,
+
+    /// The medium weight font handle
+    pub title_font: PdfFontHandle,
+
+    /// The regular weight font handle
+    pub code_font: PdfFontHandle,
+
+    /// The fallback font handle, if the document has a fallback font
+    pub fallback_font: Option<PdfFontHandle>,
+
+    /// Operations to perform on the current page
+    ops: Vec<Op>,
+
+    /// Dimensions of the current page
+    dimensions: PageDimensions,
+}
+
+impl PdfCanvas {
+    /// Initialize the PDF with the given fonts. Also sets the title, the
+    /// producer, and the [`creation_date`] in the PDF metadata.
+    pub fn new(title: &str, fonts: &Fonts) -> Result<PdfCanvas, Box<dyn std::error::Error>> {
+        debug!("Initializing PDF");
+
+        let mut doc = PdfDocument::new(title);
+
+        let producer = format!("PaperAge v{}", VERSION.unwrap_or("0.0.0"));
+        doc.metadata.info.producer = producer;
+
+        let mut add_font = |font: Font| {
+            fonts
+                .parsed(font)
+                .map(|parsed| PdfFontHandle::External(doc.add_font(parsed)))
+        };
+        let title_font = add_font(Font::Title).ok_or("Failed to load the title font")?;
+        let code_font = add_font(Font::Code).ok_or("Failed to load the code font")?;
+        let fallback_font = add_font(Font::Fallback);
+
+        let mut canvas = PdfCanvas {
+            doc,
+            title_font,
+            code_font,
+            fallback_font,
+            ops: vec![],
+            dimensions: PageDimensions::default(),
+        };
+        canvas.set_created(creation_date());
+
+        Ok(canvas)
+    }
+
+    /// Set the creation and modification dates in the PDF metadata
+    pub fn set_created(&mut self, created: DateTime) {
+        self.doc.metadata.info.creation_date = created;
+        self.doc.metadata.info.modification_date = created;
+    }
+
+    /// Build the final PDF and return as bytes
+    pub fn save_to_bytes(self) -> Vec<u8> {
+        let mut warnings = Vec::new();
+        self.doc.save(&PdfSaveOptions::default(), &mut warnings)
+    }
+
+    /// Build the final PDF and write to a writer
+    pub fn save_to_writer<W: Write>(self, writer: &mut W) {
+        let mut warnings = Vec::new();
+        self.doc
+            .save_writer(writer, &PdfSaveOptions::default(), &mut warnings);
+    }
+}
+
+impl Canvas for PdfCanvas {
+    fn begin_page(&mut self, dimensions: &PageDimensions) {
+        self.dimensions = *dimensions;
+        self.ops = page_background(dimensions);
+    }
+
+    fn text(&mut self, text: &str, font: Font, size: Pt, position: Point) {
+        let font = match font {
+            Font::Title => self.title_font.clone(),
+            Font::Code => self.code_font.clone(),
+            Font::Fallback => self
+                .fallback_font
+                .clone()
+                .unwrap_or(self.title_font.clone()),
+        };
+
+        self.ops.push(Op::StartTextSection);
+        self.ops.push(Op::SetFillColor {
+            col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
+        });
+        self.ops.push(Op::SetTextCursor { pos: position });
+        self.ops.push(Op::SetFont { font, size });
+        self.ops.push(Op::ShowText {
+            items: vec![TextItem::Text(text.to_string())],
+        });
+        self.ops.push(Op::EndTextSection);
+    }
+
+    fn line(&mut self, points: &[Point], thickness: Pt, dash_pattern: &LineDashPattern) {
+        self.ops.push(Op::SetLineDashPattern {
+            dash: dash_pattern.clone(),
+        });
+
+        let outline_color = Color::Rgb(Rgb::new(0.75, 0.75, 0.75, None));
+        self.ops.push(Op::SetOutlineColor { col: outline_color });
+
+        self.ops.push(Op::SetOutlineThickness { pt: thickness });
+
+        let line = Line {
+            points: points
+                .iter()
+                .map(|p| LinePoint {
+                    p: *p,
+                    bezier: false,
+                })
+                .collect(),
+            is_closed: false,
+        };
+
+        self.ops.push(Op::DrawLine { line });
+    }
+
+    fn polygon(&mut self, rings: &[Vec<Point>]) {
+        self.ops.push(Op::SetFillColor {
+            col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
+        });
+        self.ops.push(Op::DrawPolygon {
+            polygon: Polygon {
+                rings: rings
+                    .iter()
+                    .map(|ring| PolygonRing {
+                        points: ring
+                            .iter()
+                            .map(|p| LinePoint {
+                                p: *p,
+                                bezier: false,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+                mode: PaintMode::Fill,
+                winding_order: WindingOrder::EvenOdd,
+            },
+        });
+    }
+
+    fn qr_code(&mut self, geometry: &QrGeometry) {
+        self.ops.extend(geometry.ops());
+    }
+
+    fn end_page(&mut self) {
+        let ops = std::mem::take(&mut self.ops);
+        let page = PdfPage::new(self.dimensions.width, self.dimensions.height, ops);
+        self.doc.pages.push(page);
+    }
+}
+
+/// Operations for a blank page with a white background
+fn page_background(dimensions: &PageDimensions) -> Vec<Op> {
+    vec![
+        // White background
+        Op::SetFillColor {
+            col: Color::Rgb(Rgb::new(1.0, 1.0, 1.0, None)),
+        },
+        Op::DrawPolygon {
+            polygon: Rect {
+                x: Pt(0.0),
+                y: Pt(0.0),
+                width: dimensions.width.into_pt(),
+                height: dimensions.height.into_pt(),
+                mode: Some(PaintMode::Fill),
+                winding_order: Some(WindingOrder::NonZero),
+            }
+            .to_polygon(),
+        },
+        // Reset fill color to black for text and QR code
+        Op::SetFillColor {
+            col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
+        },
+    ]
+}
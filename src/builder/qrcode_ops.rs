@@ -8,7 +8,7 @@ use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet, VecDeque};
 
 use log::{debug, info};
 use printpdf::{
-    Color, LinePoint, Op, PaintMode, Point, Polygon, PolygonRing, Pt, Rgb, WindingOrder,
+    Color, LinePoint, Mm, Op, PaintMode, Point, Polygon, PolygonRing, Pt, Rgb, WindingOrder,
 };
 use qrcode::{types::Color as ModuleColor, types::QrError, EcLevel, QrCode};
 
@@ -22,7 +22,7 @@ use crate::page::PageSize;
 ///
 /// For a module at grid position (r, c), its four corners are:
 /// `(r, c)`, `(r, c+1)`, `(r+1, c)`, `(r+1, c+1)`.
-type GridPoint = (i32, i32);
+pub type GridPoint = (i32, i32);
 
 /// An undirected edge between two grid-corner points, stored in sorted order.
 type Edge = (GridPoint, GridPoint);
@@ -257,6 +257,74 @@ fn extract_polygons(code: &QrCode) -> Vec<Vec<Vec<GridPoint>>> {
 // Public API
 // ---------------------------------------------------------------------------
 
+/// Position and shape of a QR code on the page, independent of the output
+/// format
+#[derive(Clone, Debug)]
+pub struct QrGeometry {
+    /// Bottom-left corner of the QR code
+    pub origin: Point,
+    /// Width and height of a single module
+    pub module_size: Pt,
+    /// Number of modules on each side
+    pub modules_count: u32,
+    /// Connected components of dark modules, each a list of closed chains of
+    /// grid-corner points (the outer boundary first, then the holes)
+    pub polygons: Vec<Vec<Vec<GridPoint>>>,
+    /// Whether each module is dark, row by row from the top
+    pub modules: Vec<bool>,
+}
+
+impl QrGeometry {
+    /// Convert a grid-corner point (row, col) to page coordinates
+    pub fn point(&self, pt: GridPoint) -> Point {
+        let module_pt = self.module_size.0;
+        let x = self.origin.x.0 + pt.1 as f32 * module_pt;
+        // The y-axis points up. Row 0 is at the top of the QR code.
+        let y = self.origin.y.0 + (self.modules_count as f32 - pt.0 as f32) * module_pt;
+        Point { x: Pt(x), y: Pt(y) }
+    }
+
+    /// Whether the module at (row, col) is dark
+    pub fn is_dark(&self, row: u32, col: u32) -> bool {
+        self.modules[(row * self.modules_count + col) as usize]
+    }
+
+    /// The `printpdf` drawing operations for the QR code
+    pub fn ops(&self) -> Vec<Op> {
+        let mut ops = vec![Op::SetFillColor {
+            col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
+        }];
+
+        for chains in &self.polygons {
+            // Each connected component becomes a single polygon with multiple
+            // rings (outer boundary + holes), using even-odd fill rule.
+            let rings: Vec<PolygonRing> = chains
+                .iter()
+                .map(|chain| {
+                    let points: Vec<LinePoint> = chain
+                        .iter()
+                        .map(|&gp| LinePoint {
+                            p: self.point(gp),
+                            bezier: false,
+                        })
+                        .collect();
+                    PolygonRing { points }
+                })
+                .collect();
+
+            ops.push(Op::DrawPolygon {
+                polygon: Polygon {
+                    rings,
+                    mode: PaintMode::Fill,
+                    winding_order: WindingOrder::EvenOdd,
+                },
+            });
+        }
+
+        ops
+    }
+}
+
 /// Build a QR code and return the `printpdf` drawing operations for it.
 ///
 /// The QR code is positioned and sized for the given `page_size`:
@@ -267,6 +335,46 @@ fn extract_polygons(code: &QrCode) -> Vec<Vec<Vec<GridPoint>>> {
 /// The error correction level is chosen automatically (H → Q → M → L),
 /// returning the highest level that fits the data.
 pub fn render(text: String, page_size: &PageSize) -> Result<Vec<Op>, QrError> {
+    Ok(layout(text, page_size)?.ops())
+}
+
+/// Build a QR code and return its geometry, positioned and sized for the
+/// given `page_size` like [`render`]
+pub fn layout(text: String, page_size: &PageSize) -> Result<QrGeometry, QrError> {
+    layout_at(
+        text,
+        Point::new(page_size.qrcode_left_edge(), page_size.qrcode_bottom_edge()),
+        page_size.qrcode_size(),
+    )
+}
+
+/// Whether the text fits in a single QR code
+pub fn fits(text: &str) -> bool {
+    QrCode::with_error_correction_level(text, EcLevel::L).is_ok()
+}
+
+/// Build a QR code of the given `size` with its bottom-left corner at
+/// `origin` and return the `printpdf` drawing operations for it.
+pub fn render_at(text: String, origin: Point, size: Mm) -> Result<Vec<Op>, QrError> {
+    Ok(layout_at(text, origin, size)?.ops())
+}
+
+/// Build a QR code of the given `size` with its bottom-left corner at
+/// `origin` and return its geometry
+pub fn layout_at(text: String, origin: Point, size: Mm) -> Result<QrGeometry, QrError> {
+    layout_with_min_module_size(text, origin, size, Mm(0.0))
+}
+
+/// Build a QR code like [`layout_at`], but lower the error correction level
+/// as far as needed to keep the modules at least `min_module_size` wide.
+/// Small QR codes, e.g. on wallet cards, scan more reliably with larger
+/// modules than with more error correction.
+pub fn layout_with_min_module_size(
+    text: String,
+    origin: Point,
+    size: Mm,
+    min_module_size: Mm,
+) -> Result<QrGeometry, QrError> {
     // Error Correction Capability (approx.): H 30% / Q 25% / M 15% / L 7%
     let levels = [EcLevel::H, EcLevel::Q, EcLevel::M, EcLevel::L];
 
@@ -274,8 +382,10 @@ pub fn render(text: String, page_size: &PageSize) -> Result<Vec<Op>, QrError> {
     for &ec_level in &levels {
         debug!("Trying EC level {:?}", ec_level);
         result = QrCode::with_error_correction_level(text.clone(), ec_level);
-        if result.is_ok() {
-            break;
+        match &result {
+            Ok(code) if size / code.width() as f32 >= min_module_size => break,
+            Ok(_) => result = Err(QrError::DataTooLong),
+            Err(_) => {}
         }
     }
     let code = result?;
@@ -283,70 +393,19 @@ pub fn render(text: String, page_size: &PageSize) -> Result<Vec<Op>, QrError> {
     info!("QR code EC level: {:?}", code.error_correction_level());
     info!("QR code version: {:?}", code.version());
 
-    // Extract polygon groups
-    let polygon_groups = extract_polygons(&code);
-
     let modules_count = code.width() as u32;
 
-    // --- Coordinate calculations ---
-    let desired_pt = page_size.qrcode_size().into_pt().0;
-    let module_pt = desired_pt / modules_count as f32;
-    let qr_size_pt = module_pt * modules_count as f32;
-
-    let page_width_pt = page_size.dimensions().width.into_pt().0;
-    let page_height_pt = page_size.dimensions().height.into_pt().0;
-    let margin_pt = page_size.dimensions().margin.into_pt().0;
-
-    // Bottom-left origin of the QR code in PDF coordinates (y-axis up).
-    let origin_x = (page_width_pt - qr_size_pt) / 2.0;
-    let origin_y = page_height_pt - qr_size_pt - margin_pt * 2.0;
-
-    /// Convert a grid-corner point (row, col) to PDF coordinates.
-    fn grid_to_pdf(
-        pt: GridPoint,
-        origin_x: f32,
-        origin_y: f32,
-        module_pt: f32,
-        modules_count: u32,
-    ) -> Point {
-        let x = origin_x + pt.1 as f32 * module_pt;
-        // In PDF, y-axis points up. Row 0 is at the top of the QR code.
-        let y = origin_y + (modules_count as f32 - pt.0 as f32) * module_pt;
-        Point { x: Pt(x), y: Pt(y) }
-    }
-
-    // --- Build ops ---
-    let mut ops = vec![Op::SetFillColor {
-        col: Color::Rgb(Rgb::new(0.0, 0.0, 0.0, None)),
-    }];
-
-    for chains in &polygon_groups {
-        // Each connected component becomes a single polygon with multiple
-        // rings (outer boundary + holes), using even-odd fill rule.
-        let rings: Vec<PolygonRing> = chains
+    Ok(QrGeometry {
+        origin,
+        module_size: Pt(size.into_pt().0 / modules_count as f32),
+        modules_count,
+        polygons: extract_polygons(&code),
+        modules: code
+            .to_colors()
             .iter()
-            .map(|chain| {
-                let points: Vec<LinePoint> = chain
-                    .iter()
-                    .map(|&gp| LinePoint {
-                        p: grid_to_pdf(gp, origin_x, origin_y, module_pt, modules_count),
-                        bezier: false,
-                    })
-                    .collect();
-                PolygonRing { points }
-            })
-            .collect();
-
-        ops.push(Op::DrawPolygon {
-            polygon: Polygon {
-                rings,
-                mode: PaintMode::Fill,
-                winding_order: WindingOrder::EvenOdd,
-            },
-        });
-    }
-
-    Ok(ops)
+            .map(|&color| color == ModuleColor::Dark)
+            .collect(),
+    })
 }
 
 // ---------------------------------------------------------------------------
@@ -462,6 +521,54 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_pdf_qrcode_render_at() {
+        let origin = Point::new(Mm(20.0), Mm(30.0));
+        let ops = render_at(String::from("hi"), origin, Mm(50.0)).unwrap();
+
+        let points: Vec<Point> = ops[1..]
+            .iter()
+            .flat_map(|op| match op {
+                Op::DrawPolygon { polygon } => polygon
+                    .rings
+                    .iter()
+                    .flat_map(|ring| ring.points.iter().map(|lp| lp.p))
+                    .collect::<Vec<_>>(),
+                _ => vec![],
+            })
+            .collect();
+
+        let x_min = points.iter().map(|p| p.x.0).fold(f32::INFINITY, f32::min);
+        let x_max = points
+            .iter()
+            .map(|p| p.x.0)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let y_min = points.iter().map(|p| p.y.0).fold(f32::INFINITY, f32::min);
+        assert!((x_min - Mm(20.0).into_pt().0).abs() < 0.01);
+        assert!((x_max - Mm(70.0).into_pt().0).abs() < 0.01);
+        assert!((y_min - Mm(30.0).into_pt().0).abs() < 0.01);
+    }
+
+    #[test]
+    fn test_layout_geometry() {
+        let origin = Point::new(Mm(20.0), Mm(30.0));
+        let geometry = layout_at(String::from("hi"), origin, Mm(50.0)).unwrap();
+        let code = QrCode::with_error_correction_level("hi", EcLevel::H).unwrap();
+
+        assert_eq!(geometry.modules_count, code.width() as u32);
+        assert_eq!(geometry.point((geometry.modules_count as i32, 0)), origin);
+        // The top left finder pattern is dark in the corner and has a light ring
+        assert!(geometry.is_dark(0, 0));
+        assert!(!geometry.is_dark(1, 1));
+        assert!(geometry.is_dark(3, 3));
+    }
+
+    #[test]
+    fn test_fits() {
+        assert!(fits("Some value"));
+        assert!(!fits(include_str!("../../tests/data/too_large.txt")));
+    }
+
     #[test]
     fn test_pdf_qrcode_too_large() {
         let result = render(
@@ -0,0 +1,515 @@
+//! PNG output
+//!
+//! The pages are rasterized straight from the layout and stacked from top to
+//! bottom like in SVG output. The QR code modules are snapped to whole pixels
+//! so that the QR codes stay crisp at any resolution.
+
+use ab_glyph::{point, Font as _, FontArc, Glyph, PxScale, ScaleFont};
+use printpdf::{LineDashPattern, Point, Pt};
+
+use super::canvas::Canvas;
+use super::layout::Font;
+use super::metrics::Fonts;
+use super::qrcode_ops::QrGeometry;
+use crate::page::PageDimensions;
+
+/// Gray level of the notes line, the divider, and the debug grid
+const LINE_GRAY: u8 = 191;
+
+/// A canvas that rasterizes the pages into a grayscale PNG image
+pub struct PngCanvas {
+    /// Resolution in dots per inch
+    dpi: u32,
+    title_font: FontArc,
+    code_font: FontArc,
+    fallback_font: Option<FontArc>,
+    /// The pages drawn so far
+    pages: Vec<Bitmap>,
+}
+
+impl PngCanvas {
+    /// Start an empty image with the given resolution and fonts
+    pub fn new(dpi: u32, fonts: &Fonts) -> Result<PngCanvas, Box<dyn std::error::Error>> {
+        Ok(PngCanvas {
+            dpi,
+            title_font: fonts
+                .glyphs(Font::Title)
+                .ok_or("Failed to load the title font")?,
+            code_font: fonts
+                .glyphs(Font::Code)
+                .ok_or("Failed to load the code font")?,
+            fallback_font: fonts.glyphs(Font::Fallback),
+            pages: vec![],
+        })
+    }
+
+    /// Build the final PNG image with the pages stacked from top to bottom
+    pub fn finish(self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let width = self.pages.iter().map(|page| page.width).max().unwrap_or(1);
+        let height = self
+            .pages
+            .iter()
+            .map(|page| page.height)
+            .sum::<u32>()
+            .max(1);
+
+        let mut image = Bitmap::new(width, height);
+        // The offsets of large stacked pages don't fit in u32
+        let width = width as usize;
+        let mut top = 0;
+        for page in &self.pages {
+            let page_width = page.width as usize;
+            for y in 0..page.height as usize {
+                let row = &page.pixels[y * page_width..(y + 1) * page_width];
+                let start = (top + y) * width;
+                image.pixels[start..start + page_width].copy_from_slice(row);
+            }
+            top += page.height as usize;
+        }
+
+        image.encode_png(self.dpi)
+    }
+
+    /// Pixels per point
+    fn scale(&self) -> f32 {
+        self.dpi as f32 / 72.0
+    }
+
+    /// Convert the point to pixels on the current page, flipping the y-axis
+    /// which points up on the page and down in the image
+    fn to_px(&self, p: &Point) -> (f32, f32) {
+        let height = self.pages.last().map_or(0, |page| page.height) as f32;
+        (p.x.0 * self.scale(), height - p.y.0 * self.scale())
+    }
+
+    /// The current page
+    fn page(&mut self) -> &mut Bitmap {
+        self.pages
+            .last_mut()
+            .expect("begin_page is called before drawing")
+    }
+}
+
+impl Canvas for PngCanvas {
+    fn begin_page(&mut self, dimensions: &PageDimensions) {
+        let width = (dimensions.width.into_pt().0 * self.scale()).round() as u32;
+        let height = (dimensions.height.into_pt().0 * self.scale()).round() as u32;
+        self.pages.push(Bitmap::new(width, height));
+    }
+
+    fn text(&mut self, text: &str, font: Font, size: Pt, position: Point) {
+        let font = match font {
+            Font::Title => self.title_font.clone(),
+            Font::Code => self.code_font.clone(),
+            Font::Fallback => self
+                .fallback_font
+                .clone()
+                .unwrap_or(self.title_font.clone()),
+        };
+        let size = size.0 * self.scale();
+        let (x, y) = self.to_px(&position);
+        self.page().draw_text(text, &font, size, x, y);
+    }
+
+    fn line(&mut self, points: &[Point], thickness: Pt, dash_pattern: &LineDashPattern) {
+        let scale = self.scale();
+        // A zero thickness is the thinnest line the device can draw in PDF
+        let width = (thickness.0 * scale).max(1.0);
+        let points: Vec<(f32, f32)> = points.iter().map(|p| self.to_px(p)).collect();
+        let pattern: Vec<f32> = dash_pattern.pattern.iter().map(|l| l * scale).collect();
+
+        for (a, b) in dashes(&points, &pattern, dash_pattern.offset * scale) {
+            self.page().stroke(a, b, width, LINE_GRAY);
+        }
+    }
+
+    fn polygon(&mut self, rings: &[Vec<Point>]) {
+        let rings: Vec<Vec<(f32, f32)>> = rings
+            .iter()
+            .map(|ring| ring.iter().map(|p| self.to_px(p)).collect())
+            .collect();
+        self.page().fill_polygon(&rings, 0);
+    }
+
+    fn qr_code(&mut self, geometry: &QrGeometry) {
+        let size = geometry.module_size.0 * geometry.modules_count as f32;
+        let (left, top) = self.to_px(&Point {
+            x: geometry.origin.x,
+            y: Pt(geometry.origin.y.0 + size),
+        });
+        let size = size * self.scale();
+        self.page().draw_qr_code(geometry, left, top, size);
+    }
+}
+
+/// A grayscale image with a white background
+struct Bitmap {
+    width: u32,
+    height: u32,
+    pixels: Vec<u8>,
+}
+
+impl Bitmap {
+    fn new(width: u32, height: u32) -> Bitmap {
+        Bitmap {
+            width,
+            height,
+            pixels: vec![255; width as usize * height as usize],
+        }
+    }
+
+    /// Blend the gray level into the pixel with the given coverage
+    fn blend(&mut self, x: i64, y: i64, coverage: f32, gray: u8) {
+        if x < 0 || y < 0 || x >= self.width as i64 || y >= self.height as i64 {
+            return;
+        }
+        let pixel = &mut self.pixels[y as usize * self.width as usize + x as usize];
+        let blended = *pixel as f32 + (gray as f32 - *pixel as f32) * coverage.clamp(0.0, 1.0);
+        *pixel = blended.round() as u8;
+    }
+
+    /// Fill the rectangle from (x0, y0) up to but not including (x1, y1)
+    fn fill_rect(&mut self, x0: i64, y0: i64, x1: i64, y1: i64, gray: u8) {
+        for y in y0.max(0)..y1.min(self.height as i64) {
+            for x in x0.max(0)..x1.min(self.width as i64) {
+                self.pixels[y as usize * self.width as usize + x as usize] = gray;
+            }
+        }
+    }
+
+    /// Fill the polygon made of the closed rings with the even-odd rule,
+    /// sampling the center of each pixel
+    fn fill_polygon(&mut self, rings: &[Vec<(f32, f32)>], gray: u8) {
+        let edges: Vec<((f32, f32), (f32, f32))> = rings
+            .iter()
+            .flat_map(|ring| ring.iter().zip(ring.iter().cycle().skip(1)))
+            .map(|(a, b)| (*a, *b))
+            .collect();
+        let points = rings.iter().flatten();
+        let y0 = points.clone().map(|p| p.1).fold(f32::INFINITY, f32::min);
+        let y1 = points.map(|p| p.1).fold(f32::NEG_INFINITY, f32::max);
+        if !y0.is_finite() || !y1.is_finite() {
+            return;
+        }
+
+        for y in (y0.floor().max(0.0) as i64)..(y1.ceil().min(self.height as f32) as i64) {
+            let center = y as f32 + 0.5;
+            let mut crossings: Vec<f32> = edges
+                .iter()
+                .filter(|(a, b)| (a.1 > center) != (b.1 > center))
+                .map(|(a, b)| a.0 + (center - a.1) * (b.0 - a.0) / (b.1 - a.1))
+                .collect();
+            crossings.sort_by(|a, b| a.total_cmp(b));
+
+            for span in crossings.chunks_exact(2) {
+                let x0 = (span[0] - 0.5).ceil() as i64;
+                let x1 = (span[1] - 0.5).ceil() as i64;
+                self.fill_rect(x0, y, x1, y + 1, gray);
+            }
+        }
+    }
+
+    /// Draw the QR code in the square of the given size whose top left corner
+    /// is at (left, top), with every module the same whole number of pixels
+    fn draw_qr_code(&mut self, geometry: &QrGeometry, left: f32, top: f32, size: f32) {
+        let count = geometry.modules_count as i64;
+        let module = (size / count as f32).round().max(1.0) as i64;
+        // Keep the snapped QR code centered on its place in the layout
+        let inset = (size - (module * count) as f32) / 2.0;
+        let left = (left + inset).round() as i64;
+        let top = (top + inset).round() as i64;
+
+        for row in 0..count {
+            for col in 0..count {
+                if geometry.is_dark(row as u32, col as u32) {
+                    let x = left + col * module;
+                    let y = top + row * module;
+                    self.fill_rect(x, y, x + module, y + module, 0);
+                }
+            }
+        }
+    }
+
+    /// Draw a straight line of the given width from a to b
+    fn stroke(&mut self, a: (f32, f32), b: (f32, f32), width: f32, gray: u8) {
+        let half = width / 2.0;
+        let x0 = (a.0.min(b.0) - half - 1.0).floor() as i64;
+        let x1 = (a.0.max(b.0) + half + 1.0).ceil() as i64;
+        let y0 = (a.1.min(b.1) - half - 1.0).floor() as i64;
+        let y1 = (a.1.max(b.1) + half + 1.0).ceil() as i64;
+
+        let (dx, dy) = (b.0 - a.0, b.1 - a.1);
+        let length = (dx * dx + dy * dy).sqrt();
+
+        for y in y0.max(0)..y1.min(self.height as i64) {
+            for x in x0.max(0)..x1.min(self.width as i64) {
+                let (px, py) = (x as f32 + 0.5, y as f32 + 0.5);
+                // Distance along and across the line from the pixel center
+                let (along, across) = if length > 0.0 {
+                    let along = ((px - a.0) * dx + (py - a.1) * dy) / length;
+                    let across = ((px - a.0) * dy - (py - a.1) * dx).abs() / length;
+                    (along, across)
+                } else {
+                    (0.0, ((px - a.0).powi(2) + (py - a.1).powi(2)).sqrt())
+                };
+
+                // Butt caps, with the edges antialiased over a pixel
+                let coverage = (half + 0.5 - across).clamp(0.0, 1.0)
+                    * (along + 0.5).clamp(0.0, 1.0)
+                    * (length - along + 0.5).clamp(0.0, 1.0);
+                if coverage > 0.0 {
+                    self.blend(x, y, coverage, gray);
+                }
+            }
+        }
+    }
+
+    /// Draw a line of black text starting from the baseline at (x, y)
+    fn draw_text(&mut self, text: &str, font: &FontArc, size: f32, x: f32, y: f32) {
+        // The font size is the size of the em square, while ab_glyph scales
+        // the height from the ascender to the descender
+        let units_per_em = font.units_per_em().unwrap_or(1000.0);
+        let scale = PxScale::from(size * font.height_unscaled() / units_per_em);
+        let scaled = font.as_scaled(scale);
+
+        let mut caret = x;
+        let mut previous = None;
+        for c in text.chars() {
+            let id = font.glyph_id(c);
+            if let Some(previous) = previous {
+                caret += scaled.kern(previous, id);
+            }
+            previous = Some(id);
+
+            let glyph = Glyph {
+                id,
+                scale,
+                position: point(caret, y),
+            };
+            caret += scaled.h_advance(id);
+
+            if let Some(outlined) = font.outline_glyph(glyph) {
+                let bounds = outlined.px_bounds();
+                outlined.draw(|gx, gy, coverage| {
+                    self.blend(
+                        bounds.min.x as i64 + gx as i64,
+                        bounds.min.y as i64 + gy as i64,
+                        coverage,
+                        0,
+                    );
+                });
+            }
+        }
+    }
+
+    /// Encode the image as a PNG with the resolution in its metadata
+    fn encode_png(&self, dpi: u32) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut bytes = Vec::new();
+        {
+            let mut encoder = png::Encoder::new(&mut bytes, self.width, self.height);
+            encoder.set_color(png::ColorType::Grayscale);
+            encoder.set_depth(png::BitDepth::Eight);
+            encoder.set_pixel_dims(Some(png::PixelDimensions {
+                xppu: (dpi as f32 / 0.0254).round() as u32,
+                yppu: (dpi as f32 / 0.0254).round() as u32,
+                unit: png::Unit::Meter,
+            }));
+            let mut writer = encoder.write_header()?;
+            writer.write_image_data(&self.pixels)?;
+        }
+        Ok(bytes)
+    }
+}
+
+/// Split the line through the points into the dashes of the pattern, which
+/// alternates between dash and gap lengths. An empty pattern is a solid line.
+fn dashes(points: &[(f32, f32)], pattern: &[f32], offset: f32) -> Vec<((f32, f32), (f32, f32))> {
+    let segments = points.windows(2).map(|w| (w[0], w[1]));
+    if pattern.is_empty() || pattern.iter().all(|l| *l <= 0.0) {
+        return segments.collect();
+    }
+
+    // Start from the offset into the pattern
+    let mut index = 0;
+    let mut remaining = pattern[0];
+    let mut skip = offset.rem_euclid(pattern.iter().sum::<f32>() * 2.0);
+    while skip > 0.0 {
+        let step = skip.min(remaining);
+        skip -= step;
+        remaining -= step;
+        if remaining <= 0.0 {
+            index += 1;
+            remaining = pattern[index % pattern.len()];
+        }
+    }
+
+    let mut dashes = vec![];
+    for (a, b) in segments {
+        let length = ((b.0 - a.0).powi(2) + (b.1 - a.1).powi(2)).sqrt();
+        let at = |t: f32| {
+            (
+                a.0 + (b.0 - a.0) * t / length,
+                a.1 + (b.1 - a.1) * t / length,
+            )
+        };
+
+        let mut position = 0.0;
+        while position < length {
+            let step = remaining.min(length - position);
+            // Even entries are dashes, odd entries are gaps
+            if index % 2 == 0 {
+                dashes.push((at(position), at(position + step)));
+            }
+            position += step;
+            remaining -= step;
+            if remaining <= 0.0 {
+                index += 1;
+                remaining = pattern[index % pattern.len()];
+            }
+        }
+    }
+
+    dashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::qrcode_ops;
+    use crate::page::{PageSize, A4_PAGE};
+    use printpdf::Mm;
+
+    /// Draw the pages and decode the PNG image
+    fn draw(dpi: u32, pages: usize, f: impl Fn(&mut PngCanvas)) -> image::GrayImage {
+        let mut canvas = PngCanvas::new(dpi, &Fonts::default()).unwrap();
+        for _ in 0..pages {
+            canvas.begin_page(&A4_PAGE);
+            f(&mut canvas);
+            canvas.end_page();
+        }
+        let bytes = canvas.finish().unwrap();
+        image::load_from_memory(&bytes).unwrap().to_luma8()
+    }
+
+    #[test]
+    fn test_dashes() {
+        let solid = dashes(&[(0.0, 0.0), (10.0, 0.0)], &[], 0.0);
+        assert_eq!(solid, vec![((0.0, 0.0), (10.0, 0.0))]);
+
+        let dashed = dashes(&[(0.0, 0.0), (10.0, 0.0)], &[3.0], 0.0);
+        assert_eq!(
+            dashed,
+            vec![((0.0, 0.0), (3.0, 0.0)), ((6.0, 0.0), (9.0, 0.0))]
+        );
+
+        // Dashes continue around corners
+        let cornered = dashes(&[(0.0, 0.0), (2.0, 0.0), (2.0, 4.0)], &[3.0, 1.0], 0.0);
+        assert_eq!(
+            cornered,
+            vec![
+                ((0.0, 0.0), (2.0, 0.0)),
+                ((2.0, 0.0), (2.0, 1.0)),
+                ((2.0, 2.0), (2.0, 4.0))
+            ]
+        );
+
+        let offset = dashes(&[(0.0, 0.0), (10.0, 0.0)], &[3.0], 4.0);
+        assert_eq!(
+            offset,
+            vec![((2.0, 0.0), (5.0, 0.0)), ((8.0, 0.0), (10.0, 0.0))]
+        );
+    }
+
+    #[test]
+    fn test_render_size() {
+        let image = draw(72, 2, |_| {});
+
+        // 210 × 297 mm at 72 DPI, two pages stacked
+        assert_eq!(image.width(), 595);
+        assert_eq!(image.height(), 842 * 2);
+        assert!(image.pixels().all(|p| p.0[0] == 255));
+    }
+
+    #[test]
+    fn test_render_qr_code_modules() {
+        let geometry = qrcode_ops::layout(String::from("hi"), &PageSize::A4).unwrap();
+        let count = geometry.modules_count;
+        let image = draw(150, 1, |canvas| canvas.qr_code(&geometry));
+
+        let module = ((geometry.module_size.0 * 150.0 / 72.0).round()) as u32;
+        let dark: Vec<(u32, u32)> = image
+            .enumerate_pixels()
+            .filter(|(_, _, p)| p.0[0] == 0)
+            .map(|(x, y, _)| (x, y))
+            .collect();
+        let left = dark.iter().map(|p| p.0).min().unwrap();
+        let top = dark.iter().map(|p| p.1).min().unwrap();
+
+        // Every module is a whole number of pixels, and every pixel is either
+        // black or white
+        assert!(image.pixels().all(|p| p.0[0] == 0 || p.0[0] == 255));
+        for row in 0..count {
+            for col in 0..count {
+                let x = left + col * module + module / 2;
+                let y = top + row * module + module / 2;
+                let pixel = image.get_pixel(x, y).0[0];
+                assert_eq!(pixel == 0, geometry.is_dark(row, col), "({row}, {col})");
+            }
+        }
+    }
+
+    #[test]
+    fn test_render_text_and_lines() {
+        let image = draw(300, 1, |canvas| {
+            canvas.text(
+                "PaperAge",
+                Font::Title,
+                Pt(14.0),
+                Point::new(Mm(10.0), Mm(287.0)),
+            );
+            canvas.line(
+                &[A4_PAGE.center_left(), A4_PAGE.center_right()],
+                Pt(1.0),
+                &LineDashPattern::new(0.0, &[5.0]),
+            );
+        });
+
+        // The text is black and sits above its baseline, 10 mm from the top
+        let baseline = (Mm(10.0).into_pt().0 * 300.0 / 72.0).round() as u32;
+        let text_pixels = (0..image.width())
+            .flat_map(|x| (baseline - 40..baseline).map(move |y| (x, y)))
+            .filter(|&(x, y)| image.get_pixel(x, y).0[0] < 64)
+            .count();
+        assert!(text_pixels > 100);
+
+        // The dashed divider in the middle of the page is light gray
+        let middle = image.height() / 2;
+        let row: Vec<u8> = (0..image.width())
+            .map(|x| image.get_pixel(x, middle).0[0])
+            .collect();
+        assert!(row.contains(&LINE_GRAY));
+        assert!(row.iter().all(|&p| p >= LINE_GRAY));
+    }
+
+    #[test]
+    fn test_polygon() {
+        // A 30 mm square with a 10 mm hole in the middle
+        let square = |x: f32, size: f32| {
+            vec![
+                Point::new(Mm(x), Mm(x)),
+                Point::new(Mm(x + size), Mm(x)),
+                Point::new(Mm(x + size), Mm(x + size)),
+                Point::new(Mm(x), Mm(x + size)),
+            ]
+        };
+        let image = draw(72, 1, |canvas| {
+            canvas.polygon(&[square(10.0, 30.0), square(20.0, 10.0)])
+        });
+
+        let px = |mm: f32| (Mm(mm).into_pt().0).round() as u32;
+        let bottom = image.height() - 1;
+        assert_eq!(image.get_pixel(px(15.0), bottom - px(15.0)).0[0], 0);
+        assert_eq!(image.get_pixel(px(25.0), bottom - px(25.0)).0[0], 255);
+        assert_eq!(image.get_pixel(px(45.0), bottom - px(15.0)).0[0], 255);
+    }
+}
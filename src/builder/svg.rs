@@ -0,0 +1,294 @@
+//! SVG output
+//!
+//! The pages are stacked from top to bottom in a single SVG image. The fonts
+//! are embedded so that the text, and in particular the easily confused
+//! characters of the PEM text, look the same as in the PDF.
+
+use std::fmt::Write;
+
+use base64::prelude::{Engine, BASE64_STANDARD};
+use printpdf::{LineDashPattern, Mm, Point, Pt};
+
+use super::canvas::Canvas;
+use super::layout::Font;
+use super::metrics::Fonts;
+use super::qrcode_ops::QrGeometry;
+use crate::page::PageDimensions;
+
+/// Color of the notes line, the divider, and the debug grid
+const LINE_COLOR: &str = "#bfbfbf";
+
+/// A canvas that draws the pages in an SVG image
+pub struct SvgCanvas {
+    /// Title of the image
+    title: String,
+    /// Elements of the pages drawn so far
+    body: String,
+    /// Width of the widest page
+    width: Mm,
+    /// Total height of the pages
+    height: Mm,
+    /// Top and height of the current page in points
+    page_top: f32,
+    page_height: f32,
+    /// The fonts to embed
+    fonts: Fonts,
+}
+
+impl SvgCanvas {
+    /// Start an empty SVG image with the given title and fonts
+    pub fn new(title: &str, fonts: &Fonts) -> SvgCanvas {
+        SvgCanvas {
+            title: title.to_string(),
+            body: String::new(),
+            width: Mm(0.0),
+            height: Mm(0.0),
+            page_top: 0.0,
+            page_height: 0.0,
+            fonts: fonts.clone(),
+        }
+    }
+
+    /// Build the final SVG image
+    pub fn finish(self) -> String {
+        let mut svg = String::new();
+        svg.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        let _ = writeln!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}mm\" height=\"{}mm\" viewBox=\"0 0 {} {}\">",
+            num(self.width.0),
+            num(self.height.0),
+            num(self.width.into_pt().0),
+            num(self.height.into_pt().0)
+        );
+        let _ = writeln!(svg, "<title>{}</title>", escape(&self.title));
+
+        svg.push_str("<defs><style>\n");
+        for font in [Font::Title, Font::Code, Font::Fallback] {
+            let Some(bytes) = self.fonts.bytes(font) else {
+                continue;
+            };
+            let family = font_family(font);
+            let _ = writeln!(
+                svg,
+                "@font-face {{ font-family: \"{family}\"; src: url(data:font/ttf;base64,{}) format(\"truetype\"); }}",
+                BASE64_STANDARD.encode(bytes)
+            );
+        }
+        svg.push_str("</style></defs>\n");
+
+        svg.push_str(&self.body);
+        svg.push_str("</svg>\n");
+        svg
+    }
+
+    /// Convert the point to SVG coordinates, flipping the y-axis which points
+    /// up on the page and down in SVG
+    fn point(&self, p: &Point) -> String {
+        format!(
+            "{},{}",
+            num(p.x.0),
+            num(self.page_top + self.page_height - p.y.0)
+        )
+    }
+    /// Path data for the closed rings
+    fn path(&self, rings: impl Iterator<Item = Vec<Point>>) -> String {
+        let mut path = String::new();
+        for ring in rings {
+            for (i, p) in ring.iter().enumerate() {
+                path.push(if i == 0 { 'M' } else { 'L' });
+                path.push_str(&self.point(p));
+            }
+            path.push('Z');
+        }
+        path
+    }
+}
+
+impl Canvas for SvgCanvas {
+    fn begin_page(&mut self, dimensions: &PageDimensions) {
+        // Stack the pages from top to bottom
+        self.page_top = self.height.into_pt().0;
+        self.page_height = dimensions.height.into_pt().0;
+        self.width = self.width.max(dimensions.width);
+        self.height += dimensions.height;
+
+        self.body.push_str("<g>\n");
+        let _ = writeln!(
+            self.body,
+            "<rect x=\"0\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"#fff\"/>",
+            num(self.page_top),
+            num(dimensions.width.into_pt().0),
+            num(self.page_height)
+        );
+    }
+
+    fn text(&mut self, text: &str, font: Font, size: Pt, position: Point) {
+        let _ = writeln!(
+            self.body,
+            "<text x=\"{}\" y=\"{}\" font-family=\"'{}', monospace\" font-size=\"{}\" fill=\"#000\" xml:space=\"preserve\">{}</text>",
+            num(position.x.0),
+            num(self.page_top + self.page_height - position.y.0),
+            font_family(font),
+            num(size.0),
+            escape(text)
+        );
+    }
+
+    fn line(&mut self, points: &[Point], thickness: Pt, dash_pattern: &LineDashPattern) {
+        let points: Vec<String> = points.iter().map(|p| self.point(p)).collect();
+        let _ = write!(
+            self.body,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{LINE_COLOR}\"",
+            points.join(" ")
+        );
+        if thickness == Pt(0.0) {
+            self.body
+                .push_str(" stroke-width=\"1\" vector-effect=\"non-scaling-stroke\"");
+        } else {
+            let _ = write!(self.body, " stroke-width=\"{}\"", num(thickness.0));
+        }
+        if !dash_pattern.pattern.is_empty() {
+            let pattern: Vec<String> = dash_pattern.pattern.iter().map(|l| num(*l)).collect();
+            let _ = write!(self.body, " stroke-dasharray=\"{}\"", pattern.join(" "));
+            if dash_pattern.offset != 0.0 {
+                let _ = write!(
+                    self.body,
+                    " stroke-dashoffset=\"{}\"",
+                    num(dash_pattern.offset)
+                );
+            }
+        }
+        self.body.push_str("/>\n");
+    }
+
+    fn polygon(&mut self, rings: &[Vec<Point>]) {
+        let path = self.path(rings.iter().map(|ring| ring.to_vec()));
+        let _ = writeln!(
+            self.body,
+            "<path d=\"{path}\" fill=\"#000\" fill-rule=\"evenodd\"/>"
+        );
+    }
+
+    fn qr_code(&mut self, geometry: &QrGeometry) {
+        // A single path for the whole QR code keeps the image smaller
+        let rings = geometry
+            .polygons
+            .iter()
+            .flatten()
+            .map(|chain| chain.iter().map(|&gp| geometry.point(gp)).collect());
+        let path = self.path(rings);
+        let _ = writeln!(
+            self.body,
+            "<path d=\"{path}\" fill=\"#000\" fill-rule=\"evenodd\"/>"
+        );
+    }
+
+    fn end_page(&mut self) {
+        self.body.push_str("</g>\n");
+    }
+}
+
+fn font_family(font: Font) -> &'static str {
+    match font {
+        Font::Title => "PaperAge Title",
+        Font::Code => "PaperAge Code",
+        Font::Fallback => "PaperAge Fallback",
+    }
+}
+
+/// Format the number with at most three decimals
+fn num(value: f32) -> String {
+    let formatted = format!("{value:.3}");
+    let trimmed = formatted.trim_end_matches('0').trim_end_matches('.');
+    match trimmed {
+        "-0" => "0".to_string(),
+        _ => trimmed.to_string(),
+    }
+}
+
+/// Escape the text for use in SVG text and attributes
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::qrcode_ops;
+    use crate::page::{PageSize, A4_PAGE};
+
+    #[test]
+    fn test_num() {
+        assert_eq!(num(1.0), "1");
+        assert_eq!(num(1.25), "1.25");
+        assert_eq!(num(0.0001), "0");
+        assert_eq!(num(-0.0001), "0");
+        assert_eq!(num(2.0 / 3.0), "0.667");
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<Tom & \"Jerry\">"),
+            "&lt;Tom &amp; &quot;Jerry&quot;&gt;"
+        );
+    }
+
+    #[test]
+    fn test_canvas() {
+        let mut canvas = SvgCanvas::new("Title", &Fonts::default());
+        let qr_code = qrcode_ops::layout(String::from("hi"), &PageSize::A4).unwrap();
+        for _ in 0..2 {
+            canvas.begin_page(&A4_PAGE);
+            canvas.text(
+                "Hello & goodbye",
+                Font::Title,
+                Pt(14.0),
+                Point::new(Mm(10.0), Mm(287.0)),
+            );
+            canvas.line(
+                &[A4_PAGE.center_left(), A4_PAGE.center_right()],
+                Pt(1.0),
+                &LineDashPattern::new(0.0, &[5.0]),
+            );
+            canvas.qr_code(&qr_code);
+            canvas.end_page();
+        }
+        let svg = canvas.finish();
+
+        assert!(svg.starts_with("<?xml"));
+        assert!(svg.contains("width=\"210mm\" height=\"594mm\""));
+        assert!(svg.contains("<title>Title</title>"));
+        assert!(svg.contains(">Hello &amp; goodbye</text>"));
+        // 10 mm from the top of the first page
+        assert!(svg.contains("x=\"28.346\" y=\"28.346\""));
+        assert!(svg.contains("stroke-dasharray=\"5\""));
+        assert_eq!(svg.matches("fill-rule=\"evenodd\"").count(), 2);
+        assert!(svg.ends_with("</svg>\n"));
+    }
+
+    #[test]
+    fn test_polygon() {
+        let mut canvas = SvgCanvas::new("Polygon", &Fonts::default());
+        canvas.begin_page(&A4_PAGE);
+        let square = |x: f32, size: f32| {
+            vec![
+                Point::new(Mm(x), Mm(x)),
+                Point::new(Mm(x + size), Mm(x)),
+                Point::new(Mm(x + size), Mm(x + size)),
+                Point::new(Mm(x), Mm(x + size)),
+            ]
+        };
+        canvas.polygon(&[square(0.0, 30.0), square(10.0, 10.0)]);
+        canvas.end_page();
+        let svg = canvas.finish();
+
+        let path = svg.lines().find(|line| line.starts_with("<path")).unwrap();
+        assert_eq!(path.matches('M').count(), 2);
+        assert_eq!(path.matches('Z').count(), 2);
+    }
+}
@@ -0,0 +1,347 @@
+//! Line numbers and checksums for the printed PEM text
+//!
+//! Each line between the BEGIN and END lines is printed as
+//! `07 YWdlLWVuY3J5cHRpb24ub3JnL3YxCi0+IHNjcnlwdCBkZzJ6 3F0A`, i.e. the line
+//! number, the base64 text, and the start of the SHA-256 hash of the line
+//! number and text. When the text is typed back in, a line with a wrong
+//! checksum is corrected by trying the characters that are easy to mix up.
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::convenience::PaperAgeError;
+use crate::encryption::{PEM_BEGIN, PEM_END};
+
+/// Characters that are easily mistaken for one another when reading or
+/// typing in the text
+const CONFUSABLE: &[&[char]] = &[&['0', 'O'], &['1', 'l', 'I'], &['5', 'S']];
+
+/// Maximum number of characters corrected on a single line
+const MAX_CORRECTIONS: usize = 2;
+
+/// A line that was corrected to match its checksum
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Correction {
+    /// Line number
+    pub line: usize,
+    /// Changed characters as (column, typed, corrected), columns starting from 1
+    pub changes: Vec<(usize, char, char)>,
+}
+
+/// The checked PEM text and the corrections made to it
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CheckedPem {
+    /// The PEM encoded ciphertext without the line numbers and checksums
+    pub pem: String,
+    /// The lines that had to be corrected
+    pub corrections: Vec<Correction>,
+}
+
+/// Checksum of the numbered line
+pub fn line_checksum(number: usize, line: &str) -> String {
+    let digest = Sha256::digest(format!("{number}:{line}").as_bytes());
+    digest[..2].iter().map(|b| format!("{b:02X}")).collect()
+}
+
+/// Add line numbers and checksums to the lines between the BEGIN and END lines
+pub fn annotate(pem: &str) -> String {
+    let body_lines = pem
+        .lines()
+        .filter(|line| *line != PEM_BEGIN && *line != PEM_END)
+        .count();
+    let width = body_lines.to_string().len().max(2);
+
+    let mut number = 0;
+    pem.lines()
+        .map(|line| {
+            if line == PEM_BEGIN || line == PEM_END {
+                format!("{line}\n")
+            } else {
+                number += 1;
+                let checksum = line_checksum(number, line);
+                format!("{number:0width$} {line} {checksum}\n")
+            }
+        })
+        .collect()
+}
+
+/// Check the lines of annotated PEM text against their checksums, correcting
+/// the lines where possible.
+///
+/// Returns `None` if the text doesn't have line numbers and checksums.
+pub fn check(text: &str) -> Result<Option<CheckedPem>, PaperAgeError> {
+    let lines: Vec<&str> = text.lines().map(str::trim).collect();
+    let Some(begin) = lines.iter().position(|line| *line == PEM_BEGIN) else {
+        return Ok(None);
+    };
+    let Some(end) = lines.iter().skip(begin).position(|line| *line == PEM_END) else {
+        return Ok(None);
+    };
+    let body: Vec<&str> = lines[begin + 1..begin + end]
+        .iter()
+        .copied()
+        .filter(|line| !line.is_empty())
+        .collect();
+
+    if !body.iter().any(|line| parse_line(line).is_some()) {
+        return Ok(None);
+    }
+
+    let mut numbered: BTreeMap<usize, (String, String)> = BTreeMap::new();
+    for line in &body {
+        let (number, text, checksum) = parse_line(line).ok_or_else(|| {
+            PaperAgeError::InvalidCiphertext(format!(
+                "the line \"{line}\" has no line number or checksum"
+            ))
+        })?;
+        if numbered.insert(number, (text, checksum)).is_some() {
+            return Err(PaperAgeError::InvalidCiphertext(format!(
+                "line {number} is there more than once"
+            )));
+        }
+    }
+
+    let last = numbered.keys().last().copied().unwrap_or(0);
+    let missing: Vec<String> = (1..=last)
+        .filter(|n| !numbered.contains_key(n))
+        .map(|n| n.to_string())
+        .collect();
+
+    let mut corrections = vec![];
+    let mut invalid = vec![];
+    let mut pem = format!("{PEM_BEGIN}\n");
+
+    for (number, (text, checksum)) in numbered {
+        if line_checksum(number, &text) == checksum {
+            pem.push_str(&text);
+        } else if let Some(corrected) = correct(number, &text, &checksum) {
+            pem.push_str(&corrected);
+            let changes = text
+                .chars()
+                .zip(corrected.chars())
+                .enumerate()
+                .filter(|(_, (a, b))| a != b)
+                .map(|(i, (a, b))| (i + 1, a, b))
+                .collect();
+            corrections.push(Correction {
+                line: number,
+                changes,
+            });
+        } else {
+            invalid.push(number.to_string());
+        }
+        pem.push('\n');
+    }
+    pem.push_str(PEM_END);
+    pem.push('\n');
+
+    let mut problems = vec![];
+    if !invalid.is_empty() {
+        problems.push(format!(
+            "line(s) {} don't match their checksums",
+            invalid.join(", ")
+        ));
+    }
+    if !missing.is_empty() {
+        problems.push(format!("line(s) {} are missing", missing.join(", ")));
+    }
+    if !problems.is_empty() {
+        return Err(PaperAgeError::InvalidCiphertext(problems.join(" and ")));
+    }
+
+    Ok(Some(CheckedPem { pem, corrections }))
+}
+
+/// Parse a `07 base64 3F0A` line into the line number, text, and checksum
+fn parse_line(line: &str) -> Option<(usize, String, String)> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    if words.len() < 3 {
+        return None;
+    }
+
+    let number = normalize_digits(words[0]).parse().ok()?;
+    let checksum = normalize_digits(words[words.len() - 1]).to_uppercase();
+    if checksum.len() != 4 || !checksum.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let text = words[1..words.len() - 1].concat();
+
+    Some((number, text, checksum))
+}
+
+/// Replace letters that look like digits in a line number or checksum, which
+/// only have digits and the letters A to F
+fn normalize_digits(word: &str) -> String {
+    word.chars()
+        .map(|c| match c {
+            'O' | 'o' => '0',
+            'I' | 'l' | 'i' | 'L' => '1',
+            'S' | 's' => '5',
+            c => c,
+        })
+        .collect()
+}
+
+/// Find a variant of the line with up to `MAX_CORRECTIONS` confusable
+/// characters replaced that matches the checksum. Ambiguous corrections are
+/// rejected.
+fn correct(number: usize, text: &str, checksum: &str) -> Option<String> {
+    let chars: Vec<char> = text.chars().collect();
+    let confusable: Vec<usize> = (0..chars.len())
+        .filter(|&i| alternatives(chars[i]).is_some())
+        .collect();
+
+    for count in 1..=MAX_CORRECTIONS {
+        let mut matches = vec![];
+        for_each_variant(&chars, &confusable, count, &mut |variant| {
+            let candidate: String = variant.iter().collect();
+            if line_checksum(number, &candidate) == checksum {
+                matches.push(candidate);
+            }
+        });
+
+        match matches.len() {
+            0 => continue,
+            1 => return matches.pop(),
+            _ => return None,
+        }
+    }
+
+    None
+}
+
+/// Call `f` with every variant of `chars` where exactly `count` of the
+/// characters at `positions` are replaced with a confusable character
+fn for_each_variant(chars: &[char], positions: &[usize], count: usize, f: &mut dyn FnMut(&[char])) {
+    if count == 0 {
+        f(chars);
+        return;
+    }
+
+    for (i, &position) in positions.iter().enumerate() {
+        let original = chars[position];
+        for &replacement in alternatives(original).unwrap_or(&[]) {
+            if replacement == original {
+                continue;
+            }
+            let mut variant = chars.to_vec();
+            variant[position] = replacement;
+            for_each_variant(&variant, &positions[i + 1..], count - 1, f);
+        }
+    }
+}
+
+fn alternatives(c: char) -> Option<&'static [char]> {
+    CONFUSABLE.iter().copied().find(|class| class.contains(&c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pem() -> String {
+        let (_, pem) = crate::encryption::encrypt(&mut &b"Hello"[..], &"snakeoil".into()).unwrap();
+        pem
+    }
+
+    #[test]
+    fn test_annotate() {
+        let annotated = annotate(&pem());
+        let lines: Vec<&str> = annotated.lines().collect();
+
+        assert_eq!(lines[0], PEM_BEGIN);
+        assert_eq!(lines[lines.len() - 1], PEM_END);
+        assert!(lines[1].starts_with("01 "));
+
+        let (number, text, checksum) = parse_line(lines[1]).unwrap();
+        assert_eq!(number, 1);
+        assert_eq!(checksum, line_checksum(1, &text));
+        assert_eq!(checksum.len(), 4);
+    }
+
+    #[test]
+    fn test_check() {
+        let pem = pem();
+        let checked = check(&annotate(&pem)).unwrap().unwrap();
+
+        assert_eq!(checked.pem, pem);
+        assert!(checked.corrections.is_empty());
+    }
+
+    #[test]
+    fn test_check_plain_pem() {
+        assert_eq!(check(&pem()).unwrap(), None);
+        assert_eq!(check("no PEM here").unwrap(), None);
+    }
+
+    #[test]
+    fn test_check_typed_with_mistakes() {
+        let pem = pem();
+        let annotated = annotate(&pem);
+        let mut lines: Vec<String> = annotated.lines().map(String::from).collect();
+
+        // Mix up a confusable character on the first line
+        let (_, text, _) = parse_line(&lines[1]).unwrap();
+        let column = text.find(['0', 'O', '1', 'l', 'I', '5', 'S']).unwrap();
+        let typo = match text.as_bytes()[column] {
+            b'0' => "O",
+            b'O' => "0",
+            b'1' | b'I' => "l",
+            b'l' => "1",
+            b'5' => "S",
+            _ => "5",
+        };
+        let start = lines[1].find(' ').unwrap() + 1 + column;
+        lines[1].replace_range(start..start + 1, typo);
+
+        // Lowercase checksums, extra spaces, and indentation
+        lines[2] = format!("  {}", lines[2].to_lowercase().replace("0", "o"));
+        let typed = lines.join("\n");
+
+        let error = check(&typed);
+        // The lowercased line can't be corrected
+        assert!(error
+            .unwrap_err()
+            .to_string()
+            .contains("line(s) 2 don't match"));
+
+        lines[2] = format!(
+            "  {}",
+            annotated.lines().nth(2).unwrap().replacen(' ', "   ", 1)
+        );
+        let checked = check(&lines.join("\n")).unwrap().unwrap();
+        assert_eq!(checked.pem, pem);
+        assert_eq!(checked.corrections.len(), 1);
+        assert_eq!(checked.corrections[0].line, 1);
+        assert_eq!(checked.corrections[0].changes[0].0, column + 1);
+    }
+
+    #[test]
+    fn test_check_missing_and_wrong_lines() {
+        let annotated = annotate(&pem());
+        let mut lines: Vec<String> = annotated.lines().map(String::from).collect();
+        lines[1] = lines[1].replacen(" ", " ZZ", 1);
+        lines.remove(2);
+
+        let error = check(&lines.join("\n")).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid ciphertext: line(s) 1 don't match their checksums and line(s) 2 are missing"
+        );
+    }
+
+    #[test]
+    fn test_normalize_digits() {
+        assert_eq!(normalize_digits("O1"), "01");
+        assert_eq!(normalize_digits("3FlS"), "3F15");
+    }
+
+    #[test]
+    fn test_correct_two_characters() {
+        let text = "AB0lCD";
+        let checksum = line_checksum(3, text);
+
+        assert_eq!(correct(3, "ABO1CD", &checksum).unwrap(), text);
+        assert_eq!(correct(3, "XBO1CD", &checksum), None);
+    }
+}
@@ -1,47 +1,178 @@
 //! Command line arguments
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use clap_verbosity_flag::Verbosity;
 
-use crate::page::PageSize;
+use crate::format::Format;
+use crate::lang::Language;
+use crate::metadata::MetadataField;
+use crate::page::{Orientation, PageSize};
 
 /// Command line arguments
 #[derive(Parser, Debug)]
-#[command(author, version, about, long_about = None)]
+#[command(author, version, about, long_about = None, args_conflicts_with_subcommands = true)]
 pub struct Args {
+    /// Subcommand to run instead of creating a PDF
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     /// Page title (max. 64 characters)
     #[arg(short, long, default_value = "PaperAge")]
     pub title: String,
 
-    /// Notes label below the QR code (max. 32 characters)
-    #[arg(short, long, default_value = "Passphrase:")]
-    pub notes_label: String,
+    /// Notes label below the QR code (max. 32 characters) [default: "Passphrase:", or "Notes:"
+    /// with recipients]
+    #[arg(short, long)]
+    pub notes_label: Option<String>,
 
     /// Skip the notes placeholder line (e.g. Passphrase: ________)
     #[arg(long, default_value_t = false)]
     pub skip_notes_line: bool,
 
-    /// Output file name. Use - for STDOUT.
-    #[arg(short, long, default_value = "out.pdf")]
-    pub output: PathBuf,
+    /// Encrypt to the given age recipient instead of a passphrase. Can be repeated.
+    #[arg(short, long, value_name = "RECIPIENT")]
+    pub recipient: Vec<String>,
+
+    /// Encrypt to the given SSH public key, or the keys in a .pub or authorized_keys file. Can be
+    /// repeated.
+    #[arg(long, value_name = "KEY")]
+    pub ssh_recipient: Vec<String>,
+
+    /// Encrypt to the age recipients or SSH public keys listed in the file. Can be repeated.
+    #[arg(short = 'R', long, value_name = "PATH")]
+    pub recipients_file: Vec<PathBuf>,
+
+    /// Output file name. Use - for STDOUT. [default: out.pdf, or out.svg or out.png with --format]
+    #[arg(short, long)]
+    pub output: Option<PathBuf>,
 
-    /// Paper size
+    /// Output format
+    #[arg(long, default_value_t = Format::Pdf)]
+    pub format: Format,
+
+    /// Resolution of PNG output in dots per inch
+    #[arg(long, default_value_t = 300, value_parser = clap::value_parser!(u32).range(72..=1200))]
+    pub dpi: u32,
+
+    /// Paper size: a3, a4, a5, b5, letter, legal, executive, index-card (5x8
+    /// in), or custom:WIDTHxHEIGHTmm
     #[arg(short = 's', long, default_value_t = PageSize::A4)]
     pub page_size: PageSize,
 
+    /// Page orientation. Landscape places the QR code in the left half and
+    /// the PEM text in the right half.
+    #[arg(long, default_value_t = Orientation::Portrait)]
+    pub orientation: Orientation,
+
     /// Overwrite the output file if it already exists
     #[arg(short, long, default_value_t = false)]
     pub force: bool,
 
+    /// Split ciphertexts that don't fit in one QR code across several QR codes
+    /// and pages
+    #[arg(long, default_value_t = false)]
+    pub split: bool,
+
+    /// Split the plaintext into N Shamir shares, each encrypted and written to
+    /// its own PDF (e.g. out-share-1.pdf)
+    #[arg(long, value_name = "N", requires = "threshold", value_parser = clap::value_parser!(u8).range(2..))]
+    pub shares: Option<u8>,
+
+    /// Number of shares needed to restore the plaintext
+    #[arg(long, value_name = "K", requires = "shares", value_parser = clap::value_parser!(u8).range(2..))]
+    pub threshold: Option<u8>,
+
+    /// Print line numbers and checksums on the lines of the PEM text to catch typos when typing it
+    /// back in
+    #[arg(long, default_value_t = false)]
+    pub line_checksums: bool,
+
+    /// Print the same backup this many times on each page, separated by cut lines, so that the
+    /// copies can be stored in different places
+    #[arg(long, value_name = "N", default_value_t = 1, conflicts_with_all = ["split", "wallet_card"], value_parser = clap::value_parser!(u8).range(1..=2))]
+    pub copies: u8,
+
+    /// Tile credit card sized wallet cards with the QR code on the page instead of the full page
+    /// layout, for short secrets
+    #[arg(long, default_value_t = false, conflicts_with_all = ["split", "line_checksums"])]
+    pub wallet_card: bool,
+
+    /// Maximum number of wallet cards on the page [default: as many as fit]
+    #[arg(long, value_name = "N", requires = "wallet_card", value_parser = clap::value_parser!(u8).range(1..))]
+    pub cards: Option<u8>,
+
+    /// Add a page with step by step instructions for restoring the backup
+    #[arg(long, default_value_t = false)]
+    pub instructions: bool,
+
+    /// Language of the fixed text on the page and on the instructions page
+    #[arg(long, default_value_t = Language::En)]
+    pub lang: Language,
+
+    /// Print a metadata line below the title with the given fields, or all of them if none are
+    /// given
+    #[arg(
+        long,
+        value_name = "FIELDS",
+        num_args = 0..,
+        require_equals = true,
+        value_delimiter = ',',
+        default_missing_values = ["date", "version", "sha256", "sheet-id"]
+    )]
+    pub metadata: Vec<MetadataField>,
+
+    /// TrueType or OpenType font for the title, the labels, and the footer instead of the embedded
+    /// IBM Plex Mono Medium
+    #[arg(long, value_name = "PATH")]
+    pub title_font: Option<PathBuf>,
+
+    /// TrueType or OpenType font for the PEM text and the other small print instead of the
+    /// embedded IBM Plex Mono Regular, e.g. OCR-B for easier manual recovery
+    #[arg(long, value_name = "PATH")]
+    pub code_font: Option<PathBuf>,
+
+    /// Font for the characters of the title and the labels that the other fonts don't cover, e.g.
+    /// CJK
+    #[arg(long, value_name = "PATH")]
+    pub fallback_font: Option<PathBuf>,
+
+    /// Verify that the QR codes in the PDF decode and decrypt back to the input before writing it
+    #[arg(long, default_value_t = false)]
+    pub verify: bool,
+
+    /// Seed the random numbers with SEED so that the same input and passphrase give the same
+    /// output, e.g. for comparing snapshots. INSECURE: never use it for real secrets
+    #[cfg(feature = "insecure-deterministic")]
+    #[arg(
+        long,
+        value_name = "SEED",
+        conflicts_with_all = ["recipient", "ssh_recipient", "recipients_file", "fonts_license"]
+    )]
+    pub insecure_deterministic: Option<u64>,
+
     /// Draw a grid pattern for debugging layout issues
     #[arg(short, long, default_value_t = false)]
     pub grid: bool,
 
-    /// Print out the license for the embedded fonts
-    #[arg(long, default_value_t = false, exclusive = true)]
+    /// Print out the license for the embedded fonts, including the fonts given with --title-font,
+    /// --code-font, and --fallback-font
+    #[arg(long, default_value_t = false, conflicts_with_all = [
+        "notes_label", "skip_notes_line", "recipient", "ssh_recipient", "recipients_file",
+        "output", "format", "dpi", "page_size", "orientation", "force", "split", "shares",
+        "threshold", "line_checksums", "copies", "wallet_card", "cards", "instructions", "lang", "metadata", "verify", "grid", "input",
+    ])]
     pub fonts_license: bool,
 
+    /// Read the default options from this TOML file instead of
+    /// $XDG_CONFIG_HOME/paper-age/config.toml
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Use the options in the [profile.NAME] table of the config file
+    #[arg(long, value_name = "NAME")]
+    pub profile: Option<String>,
+
     /// Verbose output for debugging
     #[clap(flatten)]
     pub verbose: Verbosity,
@@ -50,6 +181,47 @@ pub struct Args {
     pub input: Option<PathBuf>,
 }
 
+/// Subcommands
+#[derive(Subcommand, Debug)]
+pub enum Command {
+    /// Decrypt a PEM encoded ciphertext from a PaperAge backup
+    Restore(RestoreArgs),
+
+    /// Create the sheets listed in a TOML manifest in one run
+    Batch(BatchArgs),
+}
+
+/// Command line arguments for restoring a backup
+#[derive(clap::Args, Debug)]
+pub struct RestoreArgs {
+    /// Output file name for the plaintext. Use - for STDOUT.
+    #[arg(short, long, default_value = "-")]
+    pub output: PathBuf,
+
+    /// Overwrite the output file if it already exists
+    #[arg(short, long, default_value_t = false)]
+    pub force: bool,
+
+    /// The paths to the PEM encoded ciphertexts, e.g. one for each share. Defaults to standard
+    /// input.
+    pub input: Vec<PathBuf>,
+}
+
+/// Command line arguments for creating a batch of sheets
+#[derive(clap::Args, Debug)]
+pub struct BatchArgs {
+    /// Write all the sheets to this PDF instead of the output files of the sheets
+    #[arg(long, value_name = "OUTPUT")]
+    pub combined: Option<PathBuf>,
+
+    /// Overwrite the output files if they already exist
+    #[arg(short, long, default_value_t = false)]
+    pub force: bool,
+
+    /// The path to the manifest that lists the sheets, with paths relative to its directory
+    pub manifest: PathBuf,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -71,6 +243,7 @@ mod tests {
             "--notes-label",
             "Notes:",
             "--skip-notes-line",
+            "--verify",
             "--output",
             "test.pdf",
             "input.txt",
@@ -78,9 +251,10 @@ mod tests {
         assert!(args.force);
         assert!(args.grid);
         assert_eq!(args.title, "Hello");
-        assert_eq!(args.notes_label, "Notes:");
+        assert_eq!(args.notes_label.unwrap(), "Notes:");
         assert!(args.skip_notes_line);
-        assert_eq!(args.output.to_str().unwrap(), "test.pdf");
+        assert!(args.verify);
+        assert_eq!(args.output.unwrap().to_str().unwrap(), "test.pdf");
         assert_eq!(args.input.unwrap().to_str().unwrap(), "input.txt");
     }
 
@@ -88,11 +262,241 @@ mod tests {
     fn test_defaults() {
         let args = Args::parse_from(["paper-age"]);
         assert_eq!(args.title, "PaperAge");
-        assert_eq!(args.notes_label, "Passphrase:");
+        assert_eq!(args.notes_label, None);
+        assert!(args.recipient.is_empty());
+        assert!(args.ssh_recipient.is_empty());
+        assert!(args.recipients_file.is_empty());
         assert!(!args.skip_notes_line);
-        assert_eq!(args.output.to_str().unwrap(), "out.pdf");
+        assert_eq!(args.output, None);
+        assert_eq!(args.format, Format::Pdf);
+        assert_eq!(args.dpi, 300);
         assert_eq!(args.input, None);
         assert!(!args.force);
+        assert!(!args.split);
+        assert_eq!(args.shares, None);
+        assert_eq!(args.threshold, None);
+        assert!(!args.verify);
+        assert!(!args.line_checksums);
+        assert!(args.command.is_none());
+    }
+
+    #[test]
+    fn test_restore() {
+        let args = Args::parse_from(["paper-age", "restore", "-f", "-o", "out.txt", "in.txt"]);
+        let Some(Command::Restore(restore)) = args.command else {
+            panic!("Expected the restore subcommand");
+        };
+        assert!(restore.force);
+        assert_eq!(restore.output.to_str().unwrap(), "out.txt");
+        assert_eq!(restore.input, vec![PathBuf::from("in.txt")]);
+    }
+
+    #[test]
+    fn test_restore_defaults() {
+        let args = Args::parse_from(["paper-age", "restore"]);
+        let Some(Command::Restore(restore)) = args.command else {
+            panic!("Expected the restore subcommand");
+        };
+        assert!(!restore.force);
+        assert_eq!(restore.output.to_str().unwrap(), "-");
+        assert!(restore.input.is_empty());
+    }
+
+    #[test]
+    fn test_batch() {
+        let args = Args::parse_from(["paper-age", "batch", "sheets.toml"]);
+        let Some(Command::Batch(batch)) = args.command else {
+            panic!("Expected the batch subcommand");
+        };
+        assert_eq!(batch.manifest, PathBuf::from("sheets.toml"));
+        assert_eq!(batch.combined, None);
+        assert!(!batch.force);
+
+        let args = Args::parse_from([
+            "paper-age",
+            "batch",
+            "-f",
+            "--combined",
+            "all.pdf",
+            "sheets.toml",
+        ]);
+        let Some(Command::Batch(batch)) = args.command else {
+            panic!("Expected the batch subcommand");
+        };
+        assert_eq!(batch.combined, Some(PathBuf::from("all.pdf")));
+        assert!(batch.force);
+    }
+
+    #[test]
+    fn test_restore_verbosity() {
+        let args = Args::parse_from(["paper-age", "restore", "-vv"]);
+        assert_eq!(args.verbose.log_level_filter(), log::LevelFilter::Info);
+    }
+
+    #[test]
+    fn test_recipients() {
+        let args = Args::parse_from([
+            "paper-age",
+            "-r",
+            "age1alice",
+            "--recipient",
+            "age1bob",
+            "--ssh-recipient",
+            "ssh-ed25519 AAAA",
+            "-R",
+            "team.txt",
+        ]);
+        assert_eq!(args.recipient, vec!["age1alice", "age1bob"]);
+        assert_eq!(args.ssh_recipient, vec!["ssh-ed25519 AAAA"]);
+        assert_eq!(args.recipients_file, vec![PathBuf::from("team.txt")]);
+    }
+
+    #[test]
+    fn test_shares() {
+        let args = Args::parse_from(["paper-age", "--shares", "5", "--threshold", "3"]);
+        assert_eq!(args.shares, Some(5));
+        assert_eq!(args.threshold, Some(3));
+
+        let args = Args::parse_from(["paper-age", "restore", "share-1.txt", "share-2.txt"]);
+        let Some(Command::Restore(restore)) = args.command else {
+            panic!("Expected the restore subcommand");
+        };
+        assert_eq!(restore.input.len(), 2);
+    }
+
+    #[test]
+    fn test_format() {
+        let args = Args::parse_from(["paper-age", "--format", "svg"]);
+        assert_eq!(args.format, Format::Svg);
+
+        let args = Args::parse_from(["paper-age", "--format", "png", "--dpi", "600"]);
+        assert_eq!(args.format, Format::Png);
+        assert_eq!(args.dpi, 600);
+
+        assert!(Args::try_parse_from(["paper-age", "--format", "docx"]).is_err());
+        assert!(Args::try_parse_from(["paper-age", "--dpi", "10"]).is_err());
+    }
+
+    #[test]
+    fn test_orientation() {
+        let args = Args::parse_from(["paper-age"]);
+        assert_eq!(args.orientation, Orientation::Portrait);
+
+        let args = Args::parse_from(["paper-age", "--orientation", "landscape"]);
+        assert_eq!(args.orientation, Orientation::Landscape);
+    }
+
+    #[test]
+    fn test_copies() {
+        let args = Args::parse_from(["paper-age"]);
+        assert_eq!(args.copies, 1);
+
+        let args = Args::parse_from(["paper-age", "--copies", "2"]);
+        assert_eq!(args.copies, 2);
+
+        assert!(Args::try_parse_from(["paper-age", "--copies", "3"]).is_err());
+        assert!(Args::try_parse_from(["paper-age", "--copies", "2", "--split"]).is_err());
+    }
+
+    #[test]
+    fn test_wallet_card() {
+        let args = Args::parse_from(["paper-age", "--wallet-card", "--cards", "8"]);
+        assert!(args.wallet_card);
+        assert_eq!(args.cards, Some(8));
+
+        assert!(Args::try_parse_from(["paper-age", "--cards", "8"]).is_err());
+        assert!(Args::try_parse_from(["paper-age", "--wallet-card", "--split"]).is_err());
+        assert!(Args::try_parse_from(["paper-age", "--wallet-card", "--cards", "0"]).is_err());
+    }
+
+    #[test]
+    fn test_instructions() {
+        let args = Args::parse_from(["paper-age", "--instructions"]);
+        assert!(args.instructions);
+    }
+
+    #[test]
+    fn test_lang() {
+        let args = Args::parse_from(["paper-age"]);
+        assert_eq!(args.lang, Language::En);
+
+        let args = Args::parse_from(["paper-age", "--lang", "fi"]);
+        assert_eq!(args.lang, Language::Fi);
+
+        assert!(Args::try_parse_from(["paper-age", "--lang", "sv"]).is_err());
+    }
+
+    #[test]
+    fn test_metadata() {
+        let args = Args::parse_from(["paper-age"]);
+        assert!(args.metadata.is_empty());
+
+        let args = Args::parse_from(["paper-age", "--metadata", "secret.txt"]);
+        assert_eq!(args.metadata, MetadataField::ALL);
+        assert_eq!(args.input, Some(PathBuf::from("secret.txt")));
+
+        let args = Args::parse_from(["paper-age", "--metadata=sha256,sheet-id"]);
+        assert_eq!(
+            args.metadata,
+            vec![MetadataField::Sha256, MetadataField::SheetId]
+        );
+
+        assert!(Args::try_parse_from(["paper-age", "--metadata=serial"]).is_err());
+    }
+
+    #[test]
+    #[cfg(feature = "insecure-deterministic")]
+    fn test_insecure_deterministic() {
+        let args = Args::parse_from(["paper-age"]);
+        assert_eq!(args.insecure_deterministic, None);
+
+        let args = Args::parse_from(["paper-age", "--insecure-deterministic", "42"]);
+        assert_eq!(args.insecure_deterministic, Some(42));
+
+        assert!(Args::try_parse_from([
+            "paper-age",
+            "--insecure-deterministic",
+            "42",
+            "--recipient",
+            "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p",
+        ])
+        .is_err());
+    }
+
+    #[test]
+    fn test_config() {
+        let args = Args::parse_from(["paper-age", "--config", "team.toml", "--profile", "prod"]);
+        assert_eq!(args.config, Some(PathBuf::from("team.toml")));
+        assert_eq!(args.profile.as_deref(), Some("prod"));
+    }
+
+    #[test]
+    fn test_fallback_font() {
+        let args = Args::parse_from(["paper-age", "--fallback-font", "NotoSansJP.otf"]);
+        assert_eq!(args.fallback_font, Some(PathBuf::from("NotoSansJP.otf")));
+    }
+
+    #[test]
+    fn test_user_fonts() {
+        let args = Args::parse_from([
+            "paper-age",
+            "--title-font",
+            "Inter.ttf",
+            "--code-font",
+            "OCR-B.otf",
+        ]);
+        assert_eq!(args.title_font, Some(PathBuf::from("Inter.ttf")));
+        assert_eq!(args.code_font, Some(PathBuf::from("OCR-B.otf")));
+
+        let args = Args::parse_from(["paper-age", "--fonts-license", "--code-font", "OCR-B.otf"]);
+        assert!(args.fonts_license);
+    }
+
+    #[test]
+    fn test_shares_requires_threshold() {
+        assert!(Args::try_parse_from(["paper-age", "--shares", "5"]).is_err());
+        assert!(Args::try_parse_from(["paper-age", "--threshold", "3"]).is_err());
+        assert!(Args::try_parse_from(["paper-age", "--shares", "5", "--threshold", "1"]).is_err());
     }
 
     #[test]
@@ -0,0 +1,377 @@
+//! Default command line options from a TOML config file
+//!
+//! The keys of the config file are the long names of the command line
+//! options, e.g.:
+//!
+//! ```toml
+//! title = "Production secrets"
+//! page-size = "letter"
+//!
+//! [profile.prod-root]
+//! title = "Production root key"
+//! notes-label = "Vault:"
+//! recipient = ["age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p"]
+//! ```
+//!
+//! The options of a profile override the top level options, and the options
+//! given on the command line override both.
+use std::ffi::OsString;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use clap::parser::ValueSource;
+use clap::{Arg, ArgMatches, Command, CommandFactory};
+
+use crate::cli::Args;
+
+/// Options that can't be set in the config file
+const NOT_CONFIGURABLE: [&str; 8] = [
+    "config",
+    "profile",
+    "fonts_license",
+    "insecure_deterministic",
+    "verbose",
+    "quiet",
+    "help",
+    "version",
+];
+
+/// The config file could not be read or has invalid options
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The config file could not be read
+    Read(PathBuf, std::io::Error),
+    /// The config file isn't valid TOML
+    Parse(PathBuf, String),
+    /// The profile isn't in the config file
+    UnknownProfile(PathBuf, String),
+    /// The config file has a key that isn't a command line option
+    UnknownOption(PathBuf, String),
+    /// The value doesn't fit the option
+    InvalidValue(PathBuf, String),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::Read(path, error) => {
+                write!(
+                    f,
+                    "Failed to read the config file {}: {error}",
+                    path.display()
+                )
+            }
+            ConfigError::Parse(path, msg) => {
+                write!(
+                    f,
+                    "Failed to parse the config file {}: {msg}",
+                    path.display()
+                )
+            }
+            ConfigError::UnknownProfile(path, name) => {
+                write!(f, "There is no profile {name} in {}", path.display())
+            }
+            ConfigError::UnknownOption(path, key) => {
+                write!(f, "Unknown option in {}: {key}", path.display())
+            }
+            ConfigError::InvalidValue(path, key) => {
+                write!(f, "Invalid value for {key} in {}", path.display())
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// Default location of the config file, `$XDG_CONFIG_HOME/paper-age/config.toml`
+/// or `~/.config/paper-age/config.toml`
+pub fn default_path() -> Option<PathBuf> {
+    config_home(
+        std::env::var_os("XDG_CONFIG_HOME"),
+        std::env::var_os("HOME"),
+    )
+    .map(|dir| dir.join("paper-age").join("config.toml"))
+}
+
+/// The XDG config directory, ignoring relative paths like the spec says
+fn config_home(xdg_config_home: Option<OsString>, home: Option<OsString>) -> Option<PathBuf> {
+    xdg_config_home
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| home.map(|home| PathBuf::from(home).join(".config")))
+}
+
+/// Insert the options from the config file before the command line
+/// arguments, leaving out the options that are given on the command line.
+///
+/// The config file is the one given with `--config`, or the default one if it
+/// exists. Nothing is inserted when running a subcommand.
+pub fn with_config(args: Vec<OsString>) -> Result<Vec<OsString>, ConfigError> {
+    let matches = Args::command().get_matches_from(&args);
+    if matches.subcommand().is_some() || matches.get_flag("fonts_license") {
+        return Ok(args);
+    }
+
+    let profile = matches.get_one::<String>("profile");
+    let (path, text) = match matches.get_one::<PathBuf>("config") {
+        Some(path) => (path.clone(), read(path)?),
+        None => match default_path() {
+            Some(path) if path.exists() => {
+                let text = read(&path)?;
+                (path, text)
+            }
+            Some(path) if profile.is_some() => {
+                return Err(ConfigError::Read(path, std::io::ErrorKind::NotFound.into()))
+            }
+            _ => return Ok(args),
+        },
+    };
+
+    let mut options = toml::from_str::<toml::value::Table>(&text)
+        .map_err(|e| ConfigError::Parse(path.clone(), e.to_string()))?;
+    let profiles = options.remove("profile");
+    if let Some(name) = profile {
+        let profile = profiles
+            .as_ref()
+            .and_then(|profiles| profiles.get(name))
+            .and_then(|profile| profile.as_table())
+            .ok_or_else(|| ConfigError::UnknownProfile(path.clone(), name.clone()))?;
+        options.extend(profile.clone());
+    }
+
+    let config_args = option_args(options, Some(&matches)).map_err(|error| match error {
+        OptionError::Unknown(key) => ConfigError::UnknownOption(path.clone(), key),
+        OptionError::InvalidValue(key) => ConfigError::InvalidValue(path.clone(), key),
+    })?;
+
+    let mut args = args.into_iter();
+    Ok(args
+        .next()
+        .into_iter()
+        .chain(config_args)
+        .chain(args)
+        .collect())
+}
+
+/// A key of the options table that can't be turned into a command line
+/// argument
+#[derive(Debug)]
+pub(crate) enum OptionError {
+    /// The key isn't a configurable option
+    Unknown(String),
+    /// The value doesn't fit the option
+    InvalidValue(String),
+}
+
+/// Turn the keys of the options table into command line arguments, leaving
+/// out the options that are given on the command line or conflict with them
+pub(crate) fn option_args(
+    options: toml::value::Table,
+    matches: Option<&ArgMatches>,
+) -> Result<Vec<OsString>, OptionError> {
+    let command = Args::command();
+    let given: Vec<&Arg> = match matches {
+        Some(matches) => command
+            .get_arguments()
+            .filter(|arg| {
+                matches.value_source(arg.get_id().as_str()) == Some(ValueSource::CommandLine)
+            })
+            .collect(),
+        None => vec![],
+    };
+
+    let mut args = vec![];
+    for (key, value) in options {
+        let arg = command
+            .get_arguments()
+            .find(|arg| arg.get_long() == Some(key.as_str()))
+            .filter(|arg| !NOT_CONFIGURABLE.contains(&arg.get_id().as_str()))
+            .ok_or_else(|| OptionError::Unknown(key.clone()))?;
+        if given
+            .iter()
+            .any(|given| given.get_id() == arg.get_id() || conflicts(&command, given, arg))
+        {
+            continue;
+        }
+
+        let values = match value {
+            toml::Value::Array(values) => values,
+            value => vec![value],
+        };
+        for value in values {
+            let arg = match (value, arg.get_action().takes_values()) {
+                (toml::Value::Boolean(false), false) => continue,
+                (toml::Value::Boolean(true), false) => format!("--{key}"),
+                (toml::Value::String(value), true) => format!("--{key}={value}"),
+                (toml::Value::Integer(value), true) => format!("--{key}={value}"),
+                (toml::Value::Float(value), true) => format!("--{key}={value}"),
+                _ => return Err(OptionError::InvalidValue(key)),
+            };
+            args.push(OsString::from(arg));
+        }
+    }
+
+    Ok(args)
+}
+
+/// Whether the arguments conflict, whichever of them declares the conflict
+fn conflicts(command: &Command, a: &Arg, b: &Arg) -> bool {
+    let declares = |a: &Arg, b: &Arg| {
+        command
+            .get_arg_conflicts_with(a)
+            .iter()
+            .any(|arg| arg.get_id() == b.get_id())
+    };
+
+    declares(a, b) || declares(b, a)
+}
+
+/// Read the config file
+fn read(path: &Path) -> Result<String, ConfigError> {
+    std::fs::read_to_string(path).map_err(|e| ConfigError::Read(path.to_path_buf(), e))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use assert_fs::prelude::*;
+    use clap::Parser;
+
+    const CONFIG: &str = r#"
+title = "Team secrets"
+page-size = "letter"
+split = true
+skip-notes-line = false
+
+[profile.prod-root]
+title = "Production root key"
+recipient = [
+    "age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p",
+    "age1lggyhqrw2nlhcxprm67z43rta597azn8gknawjehu9d9dl0jq3yqqvfafg",
+]
+"#;
+
+    fn parse(config: &str, args: &[&str]) -> Result<Args, ConfigError> {
+        let file = assert_fs::NamedTempFile::new("config.toml").unwrap();
+        file.write_str(config).unwrap();
+        let path = file.path().to_str().unwrap();
+        let args = ["paper-age", "--config", path]
+            .iter()
+            .chain(args)
+            .map(OsString::from)
+            .collect();
+
+        with_config(args).map(Args::parse_from)
+    }
+
+    #[test]
+    fn test_config() {
+        let args = parse(CONFIG, &[]).unwrap();
+        assert_eq!(args.title, "Team secrets");
+        assert_eq!(args.page_size, crate::page::PageSize::Letter);
+        assert!(args.split);
+        assert!(!args.skip_notes_line);
+        assert!(args.recipient.is_empty());
+    }
+
+    #[test]
+    fn test_command_line_overrides_config() {
+        let args = parse(CONFIG, &["--title", "Mine", "--page-size=a4"]).unwrap();
+        assert_eq!(args.title, "Mine");
+        assert_eq!(args.page_size, crate::page::PageSize::A4);
+        assert!(args.split);
+    }
+
+    #[test]
+    fn test_command_line_conflicts_with_config() {
+        // wallet-card declares the conflict with split
+        let args = parse(CONFIG, &["--wallet-card"]).unwrap();
+        assert!(args.wallet_card);
+        assert!(!args.split);
+        assert_eq!(args.title, "Team secrets");
+
+        let args = parse("wallet-card = true", &["--split"]).unwrap();
+        assert!(args.split);
+        assert!(!args.wallet_card);
+    }
+
+    #[test]
+    #[cfg(feature = "insecure-deterministic")]
+    fn test_insecure_deterministic_ignores_config_recipients() {
+        let args = parse(
+            CONFIG,
+            &["--profile", "prod-root", "--insecure-deterministic", "1"],
+        )
+        .unwrap();
+        assert_eq!(args.insecure_deterministic, Some(1));
+        assert!(args.recipient.is_empty());
+        assert_eq!(args.title, "Production root key");
+    }
+
+    #[test]
+    fn test_profile() {
+        let args = parse(CONFIG, &["--profile", "prod-root"]).unwrap();
+        assert_eq!(args.title, "Production root key");
+        assert_eq!(args.page_size, crate::page::PageSize::Letter);
+        assert_eq!(args.recipient.len(), 2);
+
+        let args = parse(CONFIG, &["--profile", "prod-root", "-r", "age1mine"]).unwrap();
+        assert_eq!(args.recipient, vec!["age1mine"]);
+
+        assert!(matches!(
+            parse(CONFIG, &["--profile", "staging"]),
+            Err(ConfigError::UnknownProfile(_, name)) if name == "staging"
+        ));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        assert!(matches!(
+            parse("title = ", &[]),
+            Err(ConfigError::Parse(..))
+        ));
+        assert!(matches!(
+            parse("colour = \"blue\"", &[]),
+            Err(ConfigError::UnknownOption(_, key)) if key == "colour"
+        ));
+        assert!(matches!(
+            parse("insecure-deterministic = 1", &[]),
+            Err(ConfigError::UnknownOption(..))
+        ));
+        assert!(matches!(
+            parse("split = \"yes\"", &[]),
+            Err(ConfigError::InvalidValue(_, key)) if key == "split"
+        ));
+        assert!(matches!(
+            parse("title = true", &[]),
+            Err(ConfigError::InvalidValue(_, key)) if key == "title"
+        ));
+        assert!(matches!(
+            parse("recipient = false", &[]),
+            Err(ConfigError::InvalidValue(_, key)) if key == "recipient"
+        ));
+    }
+
+    #[test]
+    fn test_subcommand_ignores_config() {
+        let args: Vec<OsString> = ["paper-age", "restore", "backup.txt"]
+            .iter()
+            .map(OsString::from)
+            .collect();
+
+        assert_eq!(with_config(args.clone()).unwrap(), args);
+    }
+
+    #[test]
+    fn test_config_home() {
+        assert_eq!(
+            config_home(Some("/etc/xdg".into()), Some("/home/me".into())),
+            Some(PathBuf::from("/etc/xdg"))
+        );
+        assert_eq!(
+            config_home(Some("relative".into()), Some("/home/me".into())),
+            Some(PathBuf::from("/home/me/.config"))
+        );
+        assert_eq!(config_home(None, None), None);
+    }
+}
@@ -6,11 +6,12 @@ use std::io::BufRead;
 use age::secrecy::SecretString;
 
 use crate::builder;
-use crate::encryption;
+use crate::encryption::{self, EncryptionMode};
 use crate::page::PageSize;
 
 /// Errors that can occur during PDF generation
 #[derive(Debug)]
+#[non_exhaustive]
 pub enum PaperAgeError {
     /// The plaintext data could not be encrypted
     Encryption(String),
@@ -18,6 +19,18 @@ pub enum PaperAgeError {
     DocumentInit(String),
     /// The PDF could not be created (e.g. QR code too large)
     PdfCreation(String),
+    /// The ciphertext could not be decrypted with the given passphrase
+    IncorrectPassphrase,
+    /// The ciphertext is corrupted or isn't a valid age file
+    InvalidCiphertext(String),
+    /// The ciphertext could not be decrypted for some other reason
+    Decryption(String),
+    /// A recipient could not be parsed
+    InvalidRecipient(String),
+    /// The secret could not be split into shares or the shares can't be combined
+    InvalidShares(String),
+    /// The generated PDF doesn't restore to the ciphertext and plaintext
+    Verification(String),
 }
 
 impl fmt::Display for PaperAgeError {
@@ -26,13 +39,20 @@ impl fmt::Display for PaperAgeError {
             PaperAgeError::Encryption(msg) => write!(f, "Encryption failed: {msg}"),
             PaperAgeError::DocumentInit(msg) => write!(f, "Document initialization failed: {msg}"),
             PaperAgeError::PdfCreation(msg) => write!(f, "PDF creation failed: {msg}"),
+            PaperAgeError::IncorrectPassphrase => write!(f, "Incorrect passphrase"),
+            PaperAgeError::InvalidCiphertext(msg) => write!(f, "Invalid ciphertext: {msg}"),
+            PaperAgeError::Decryption(msg) => write!(f, "Decryption failed: {msg}"),
+            PaperAgeError::InvalidRecipient(msg) => write!(f, "Invalid recipient: {msg}"),
+            PaperAgeError::InvalidShares(msg) => write!(f, "Invalid shares: {msg}"),
+            PaperAgeError::Verification(msg) => write!(f, "Verification failed: {msg}"),
         }
     }
 }
 
 impl std::error::Error for PaperAgeError {}
 
-/// Generate a PaperAge PDF from plaintext data and a passphrase.
+/// Generate a PaperAge PDF from plaintext data and a passphrase or a list of
+/// recipients.
 ///
 /// This is a high-level convenience function that handles encryption and PDF
 /// generation in a single call.
@@ -41,8 +61,9 @@ impl std::error::Error for PaperAgeError {}
 ///
 /// * `title` - The document title (appears in the PDF and its metadata)
 /// * `data` - A buffered reader providing the plaintext data to encrypt
-/// * `passphrase` - The passphrase used to encrypt the data
-/// * `notes_label` - Label for the notes field (defaults to `"Passphrase:"`)
+/// * `encryption` - The passphrase or [`EncryptionMode`] used to encrypt the data
+/// * `notes_label` - Label for the notes field (defaults to
+///   [`EncryptionMode::default_notes_label_in`])
 /// * `skip_notes_line` - Whether to omit the notes placeholder line (defaults to `false`)
 /// * `page_size` - The page size to use (defaults to [`PageSize::A4`])
 /// * `grid` - Whether to draw a debug grid on the page (defaults to `false`)
@@ -68,35 +89,83 @@ impl std::error::Error for PaperAgeError {}
 ///     None,
 /// ).expect("PDF generation failed");
 /// ```
+///
+/// Encrypting to age recipients instead of a passphrase:
+///
+/// ```no_run
+/// use paper_age::convenience::create_pdf;
+/// use paper_age::encryption::{parse_recipient, EncryptionMode};
+///
+/// let recipient = parse_recipient("age1...").expect("Invalid recipient");
+///
+/// let pdf_bytes = create_pdf(
+///     "My Secret".to_string(),
+///     &mut &b"secret data to encrypt"[..],
+///     EncryptionMode::Recipients(vec![recipient]),
+///     None,
+///     None,
+///     None,
+///     None,
+/// ).expect("PDF generation failed");
+/// ```
 pub fn create_pdf(
     title: String,
     data: &mut dyn BufRead,
-    passphrase: &str,
+    encryption: impl Into<EncryptionMode>,
     notes_label: Option<String>,
     skip_notes_line: Option<bool>,
     page_size: Option<PageSize>,
     grid: Option<bool>,
 ) -> Result<Vec<u8>, PaperAgeError> {
-    let notes_label = notes_label.unwrap_or_else(|| "Passphrase:".to_string());
+    let mode = encryption.into();
     let skip_notes_line = skip_notes_line.unwrap_or(false);
     let page_size = page_size.unwrap_or(PageSize::A4);
     let grid = grid.unwrap_or(false);
 
-    let passphrase_secret = SecretString::from(passphrase.to_owned());
-
-    let (_plaintext_len, encrypted) = encryption::encrypt_plaintext(data, passphrase_secret)
-        .map_err(|e| PaperAgeError::Encryption(e.to_string()))?;
+    let (_plaintext_len, encrypted) =
+        encryption::encrypt(data, &mode).map_err(|e| PaperAgeError::Encryption(e.to_string()))?;
 
     let pdf = builder::Document::new(title, page_size)
         .map_err(|e| PaperAgeError::DocumentInit(e.to_string()))?;
 
     let bytes = pdf
-        .create_pdf(grid, notes_label, skip_notes_line, encrypted)
+        .render(grid, notes_label, skip_notes_line, encrypted, &mode)
         .map_err(|e| PaperAgeError::PdfCreation(e.to_string()))?;
 
     Ok(bytes)
 }
 
+/// Restore the plaintext from a PEM encoded PaperAge ciphertext.
+///
+/// The ciphertext can be surrounded by other text and may contain extra
+/// whitespace, for example when it has been typed in by hand.
+///
+/// # Arguments
+///
+/// * `data` - A buffered reader providing the PEM encoded ciphertext
+/// * `passphrase` - The passphrase used to encrypt the data
+///
+/// # Returns
+///
+/// The decrypted plaintext, or a [`PaperAgeError`] describing what went wrong.
+/// An incorrect passphrase is reported as [`PaperAgeError::IncorrectPassphrase`]
+/// and a damaged ciphertext as [`PaperAgeError::InvalidCiphertext`].
+///
+/// # Example
+///
+/// ```no_run
+/// use paper_age::convenience::restore;
+///
+/// let ciphertext = std::fs::read_to_string("backup.txt").unwrap();
+/// let plaintext = restore(&mut ciphertext.as_bytes(), "hunter2")
+///     .expect("Decryption failed");
+/// ```
+pub fn restore(data: &mut dyn BufRead, passphrase: &str) -> Result<Vec<u8>, PaperAgeError> {
+    let passphrase_secret = SecretString::from(passphrase.to_owned());
+
+    encryption::decrypt_ciphertext(data, passphrase_secret)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -131,6 +200,35 @@ mod tests {
         assert!(result.is_ok());
     }
 
+    #[test]
+    fn test_create_pdf_with_recipients() {
+        let identity = age::x25519::Identity::generate();
+        let result = create_pdf(
+            "Recipients".to_string(),
+            &mut &b"secret data"[..],
+            EncryptionMode::Recipients(vec![identity.to_public().into()]),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_create_pdf_without_recipients() {
+        let result = create_pdf(
+            "No recipients".to_string(),
+            &mut &b"secret data"[..],
+            EncryptionMode::Recipients(vec![]),
+            None,
+            None,
+            None,
+            None,
+        );
+        assert!(matches!(result, Err(PaperAgeError::Encryption(_))));
+    }
+
     #[test]
     fn test_create_pdf_empty_data() {
         let result = create_pdf(
@@ -159,5 +257,23 @@ mod tests {
             PaperAgeError::PdfCreation("QR too large".to_string()).to_string(),
             "PDF creation failed: QR too large"
         );
+        assert_eq!(
+            PaperAgeError::IncorrectPassphrase.to_string(),
+            "Incorrect passphrase"
+        );
+        assert_eq!(
+            PaperAgeError::InvalidCiphertext("bad armor".to_string()).to_string(),
+            "Invalid ciphertext: bad armor"
+        );
+    }
+
+    #[test]
+    fn test_restore() {
+        let passphrase = SecretString::from("hunter2".to_owned());
+        let (_, encrypted) =
+            encryption::encrypt_plaintext(&mut &b"restore me"[..], passphrase).unwrap();
+
+        let result = restore(&mut encrypted.as_bytes(), "hunter2");
+        assert_eq!(result.unwrap(), b"restore me");
     }
 }
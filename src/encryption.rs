@@ -1,22 +1,312 @@
 //! Age based encryption
-use std::io::Write;
+use std::fmt;
+use std::io::{BufRead, Read, Write};
+use std::iter;
 
-use age::armor::ArmoredWriter;
 use age::armor::Format::AsciiArmor;
+use age::armor::{ArmoredReader, ArmoredWriter};
+#[cfg(feature = "insecure-deterministic")]
+use age::secrecy::ExposeSecret;
 use age::secrecy::SecretString;
-use log::debug;
+use age::ssh::ParseRecipientKeyError;
+use age::DecryptError;
+use base64::prelude::{Engine, BASE64_STANDARD_NO_PAD};
+#[cfg(feature = "insecure-deterministic")]
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305,
+};
+#[cfg(feature = "insecure-deterministic")]
+use hkdf::Hkdf;
+#[cfg(feature = "insecure-deterministic")]
+use hmac::{Hmac, Mac};
+use log::{debug, warn};
+#[cfg(feature = "insecure-deterministic")]
+use rand::{rngs::StdRng, CryptoRng, RngCore, SeedableRng};
+use sha2::{Digest, Sha256};
 
-/// Encrypt the data from the reader and PEM encode the ciphertext
+use crate::convenience::PaperAgeError;
+use crate::lang::{self, Language};
+
+/// First line of a PEM encoded age ciphertext
+pub const PEM_BEGIN: &str = "-----BEGIN AGE ENCRYPTED FILE-----";
+
+/// Last line of a PEM encoded age ciphertext
+pub const PEM_END: &str = "-----END AGE ENCRYPTED FILE-----";
+
+/// Line length of the base64 encoded lines in the PEM block
+const PEM_LINE_LEN: usize = 64;
+
+/// scrypt work factor (log2 of N) of the passphrase in seeded encryption,
+/// which is what age picks on a typical computer
+#[cfg(feature = "insecure-deterministic")]
+const SEEDED_LOG_N: u8 = 18;
+
+/// Size of the plaintext chunks of the age payload
+#[cfg(feature = "insecure-deterministic")]
+const PAYLOAD_CHUNK_SIZE: usize = 64 * 1024;
+
+/// How the plaintext is encrypted
+#[derive(Clone, Debug)]
+pub enum EncryptionMode {
+    /// Encrypt with a passphrase
+    Passphrase(SecretString),
+    /// Encrypt to one or more recipients, any of which can decrypt
+    Recipients(Vec<Recipient>),
+}
+
+impl EncryptionMode {
+    /// Default label for the notes field on the page
+    pub fn default_notes_label(&self) -> &'static str {
+        let catalog = Language::En.catalog();
+        match self {
+            EncryptionMode::Passphrase(_) => catalog.passphrase_label,
+            EncryptionMode::Recipients(_) => catalog.recipient_label,
+        }
+    }
+
+    /// Default label for the notes field on the page in the language, with the
+    /// number of recipients when there are several
+    pub fn default_notes_label_in(&self, language: Language) -> String {
+        let catalog = language.catalog();
+        match self {
+            EncryptionMode::Passphrase(_) => catalog.passphrase_label.to_string(),
+            EncryptionMode::Recipients(recipients) if recipients.len() > 1 => {
+                lang::fill(catalog.recipients_label, &[("count", &recipients.len())])
+            }
+            EncryptionMode::Recipients(_) => catalog.recipient_label.to_string(),
+        }
+    }
+}
+
+impl From<SecretString> for EncryptionMode {
+    fn from(passphrase: SecretString) -> Self {
+        EncryptionMode::Passphrase(passphrase)
+    }
+}
+
+impl From<&str> for EncryptionMode {
+    fn from(passphrase: &str) -> Self {
+        EncryptionMode::Passphrase(SecretString::from(passphrase.to_owned()))
+    }
+}
+
+impl From<Vec<Recipient>> for EncryptionMode {
+    fn from(recipients: Vec<Recipient>) -> Self {
+        EncryptionMode::Recipients(recipients)
+    }
+}
+
+/// A recipient the plaintext can be encrypted to
+#[derive(Clone, Debug)]
+pub enum Recipient {
+    /// A native age X25519 recipient (`age1...`)
+    X25519(age::x25519::Recipient),
+    /// An `ssh-ed25519` or `ssh-rsa` public key
+    Ssh {
+        /// The parsed SSH public key
+        recipient: age::ssh::Recipient,
+        /// The comment after the public key, usually `user@host`
+        comment: Option<String>,
+    },
+}
+
+impl Recipient {
+    /// The key type of the recipient
+    pub fn key_type(&self) -> &'static str {
+        match self {
+            Recipient::X25519(_) => "X25519",
+            Recipient::Ssh {
+                recipient: age::ssh::Recipient::SshEd25519(..),
+                ..
+            } => "ssh-ed25519",
+            Recipient::Ssh {
+                recipient: age::ssh::Recipient::SshRsa(..),
+                ..
+            } => "ssh-rsa",
+        }
+    }
+
+    /// Fingerprint of the recipient. SSH keys use the same SHA256 fingerprint
+    /// format as `ssh-keygen -l` and X25519 recipients are their own
+    /// fingerprint.
+    pub fn fingerprint(&self) -> String {
+        match self {
+            Recipient::X25519(recipient) => recipient.to_string(),
+            Recipient::Ssh { recipient, .. } => {
+                let key = match recipient {
+                    age::ssh::Recipient::SshEd25519(key, _) => key,
+                    age::ssh::Recipient::SshRsa(key, _) => key,
+                };
+                let digest = Sha256::digest(key);
+                format!("SHA256:{}", BASE64_STANDARD_NO_PAD.encode(digest))
+            }
+        }
+    }
+
+    /// Short fingerprint of the recipient for listing on the page. X25519
+    /// recipients show the start and the end of the recipient, and SSH keys
+    /// the start of the SHA256 fingerprint.
+    pub fn short_fingerprint(&self) -> String {
+        let fingerprint = self.fingerprint();
+        let chars: Vec<char> = fingerprint.chars().collect();
+
+        match self {
+            Recipient::X25519(_) => format!(
+                "{}…{}",
+                chars[..10].iter().collect::<String>(),
+                chars[chars.len() - 6..].iter().collect::<String>()
+            ),
+            Recipient::Ssh { .. } => format!("{}…", chars[..23].iter().collect::<String>()),
+        }
+    }
+
+    /// The comment of an SSH public key
+    pub fn comment(&self) -> Option<&str> {
+        match self {
+            Recipient::X25519(_) => None,
+            Recipient::Ssh { comment, .. } => comment.as_deref(),
+        }
+    }
+
+    /// The age recipient used for encryption
+    fn as_age_recipient(&self) -> &dyn age::Recipient {
+        match self {
+            Recipient::X25519(recipient) => recipient,
+            Recipient::Ssh { recipient, .. } => recipient,
+        }
+    }
+}
+
+impl fmt::Display for Recipient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Recipient::X25519(recipient) => write!(f, "{recipient}"),
+            Recipient::Ssh { recipient, comment } => match comment {
+                Some(comment) => write!(f, "{recipient} {comment}"),
+                None => write!(f, "{recipient}"),
+            },
+        }
+    }
+}
+
+impl From<age::x25519::Recipient> for Recipient {
+    fn from(recipient: age::x25519::Recipient) -> Self {
+        Recipient::X25519(recipient)
+    }
+}
+
+impl From<age::ssh::Recipient> for Recipient {
+    fn from(recipient: age::ssh::Recipient) -> Self {
+        Recipient::Ssh {
+            recipient,
+            comment: None,
+        }
+    }
+}
+
+/// Parse an age recipient (`age1...`) or an SSH public key
+pub fn parse_recipient(recipient: &str) -> Result<Recipient, PaperAgeError> {
+    let recipient = recipient.trim();
+
+    if recipient.starts_with("age1") {
+        recipient
+            .parse::<age::x25519::Recipient>()
+            .map(Recipient::X25519)
+            .map_err(|e| PaperAgeError::InvalidRecipient(format!("{recipient}: {e}")))
+    } else {
+        parse_ssh_recipient(recipient)
+    }
+}
+
+/// Parse an SSH public key (`ssh-ed25519 AAAA... comment`). Lines from
+/// `authorized_keys` files may also include options before the key type.
+pub fn parse_ssh_recipient(line: &str) -> Result<Recipient, PaperAgeError> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+
+    let start = words
+        .iter()
+        .position(|w| w.starts_with("ssh-") || w.starts_with("sk-") || w.starts_with("ecdsa-"))
+        .ok_or_else(|| {
+            PaperAgeError::InvalidRecipient(format!(
+                "{}: not an age or SSH public key",
+                line.trim()
+            ))
+        })?;
+    let key = words[start..]
+        .iter()
+        .take(2)
+        .copied()
+        .collect::<Vec<_>>()
+        .join(" ");
+    let comment = words.get(start + 2..).map(|c| c.join(" "));
+
+    let recipient = key.parse::<age::ssh::Recipient>().map_err(|e| {
+        let reason = match e {
+            ParseRecipientKeyError::Unsupported(key_type) => {
+                format!("unsupported SSH key type {key_type}")
+            }
+            ParseRecipientKeyError::RsaModulusTooLarge => "RSA key is too large".to_string(),
+            ParseRecipientKeyError::RsaModulusTooSmall => "RSA key is too small".to_string(),
+            _ => "invalid SSH public key".to_string(),
+        };
+        PaperAgeError::InvalidRecipient(format!("{}: {reason}", words[start]))
+    })?;
+
+    Ok(Recipient::Ssh {
+        recipient,
+        comment: comment.filter(|c| !c.is_empty()),
+    })
+}
+
+/// Read recipients from a recipients or `authorized_keys` file with one
+/// recipient per line. Empty lines and lines starting with `#` are ignored.
+pub fn read_recipients(reader: &mut dyn BufRead) -> Result<Vec<Recipient>, PaperAgeError> {
+    let mut recipients = vec![];
+
+    for line in reader.lines() {
+        let line = line.map_err(|e| PaperAgeError::InvalidRecipient(e.to_string()))?;
+        let line = line.trim();
+
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        recipients.push(parse_recipient(line)?);
+    }
+
+    Ok(recipients)
+}
+
+/// Encrypt the data from the reader with a passphrase and PEM encode the
+/// ciphertext
 pub fn encrypt_plaintext(
     reader: &mut dyn std::io::BufRead,
     passphrase: SecretString,
+) -> Result<(usize, String), Box<dyn std::error::Error>> {
+    encrypt(reader, &EncryptionMode::Passphrase(passphrase))
+}
+
+/// Encrypt the data from the reader using the given encryption mode and PEM
+/// encode the ciphertext
+pub fn encrypt(
+    reader: &mut dyn std::io::BufRead,
+    mode: &EncryptionMode,
 ) -> Result<(usize, String), Box<dyn std::error::Error>> {
     debug!("Encrypting plaintext");
 
     let mut plaintext: Vec<u8> = vec![];
     reader.read_to_end(&mut plaintext)?;
 
-    let encryptor = age::Encryptor::with_user_passphrase(passphrase);
+    let encryptor = match mode {
+        EncryptionMode::Passphrase(passphrase) => {
+            age::Encryptor::with_user_passphrase(passphrase.clone())
+        }
+        EncryptionMode::Recipients(recipients) => {
+            debug!("Encrypting to {} recipient(s)", recipients.len());
+            age::Encryptor::with_recipients(recipients.iter().map(|r| r.as_age_recipient()))?
+        }
+    };
 
     let mut encrypted = vec![];
 
@@ -33,10 +323,244 @@ pub fn encrypt_plaintext(
     Ok((plaintext.len(), utf8))
 }
 
+/// Encrypt the data from the reader with a passphrase like [`encrypt`], but
+/// take the file key, the salt, and the nonce from a random number generator
+/// seeded with `seed` instead of the operating system.
+///
+/// The same plaintext, passphrase, and seed always give the same ciphertext,
+/// which is useful for comparing the output of test runs. Anyone who knows the
+/// seed can decrypt the ciphertext, so never use this for real secrets.
+#[cfg(feature = "insecure-deterministic")]
+pub fn encrypt_with_seed(
+    reader: &mut dyn std::io::BufRead,
+    mode: &EncryptionMode,
+    seed: u64,
+) -> Result<(usize, String), Box<dyn std::error::Error>> {
+    encrypt_with_rng(reader, mode, &mut StdRng::seed_from_u64(seed))
+}
+
+/// Encrypt the data from the reader with a passphrase like [`encrypt`], but
+/// take the file key, the salt, and the nonce from the given random number
+/// generator instead of the operating system.
+///
+/// This writes the age v1 format by hand because the age crate always takes
+/// its random numbers from the operating system, with no way to inject a
+/// random number generator.
+#[cfg(feature = "insecure-deterministic")]
+pub(crate) fn encrypt_with_rng<R: RngCore + CryptoRng>(
+    reader: &mut dyn std::io::BufRead,
+    mode: &EncryptionMode,
+    rng: &mut R,
+) -> Result<(usize, String), Box<dyn std::error::Error>> {
+    debug!("Encrypting plaintext with the given random number generator");
+
+    let EncryptionMode::Passphrase(passphrase) = mode else {
+        return Err(PaperAgeError::Encryption(
+            "only passphrases can be used with a seeded random number generator".to_string(),
+        )
+        .into());
+    };
+    let aead_error = |_| PaperAgeError::Encryption("ChaCha20-Poly1305 failed".to_string());
+
+    let mut plaintext: Vec<u8> = vec![];
+    reader.read_to_end(&mut plaintext)?;
+
+    let mut file_key = [0; 16];
+    let mut salt = [0; 16];
+    let mut nonce = [0; 16];
+    rng.fill_bytes(&mut file_key);
+    rng.fill_bytes(&mut salt);
+    rng.fill_bytes(&mut nonce);
+
+    // Wrap the file key with the passphrase in an scrypt stanza
+    let scrypt_salt = [b"age-encryption.org/v1/scrypt".as_slice(), &salt].concat();
+    let params = scrypt::Params::new(SEEDED_LOG_N, 8, 1, 32)
+        .map_err(|e| PaperAgeError::Encryption(e.to_string()))?;
+    let mut wrap_key = [0; 32];
+    scrypt::scrypt(
+        passphrase.expose_secret().as_bytes(),
+        &scrypt_salt,
+        &params,
+        &mut wrap_key,
+    )
+    .map_err(|e| PaperAgeError::Encryption(e.to_string()))?;
+    let wrapped_key = ChaCha20Poly1305::new(&wrap_key.into())
+        .encrypt(&[0; 12].into(), file_key.as_slice())
+        .map_err(aead_error)?;
+
+    // The header MAC covers everything up to and including the "---"
+    let mut header = format!(
+        "age-encryption.org/v1\n-> scrypt {} {SEEDED_LOG_N}\n{}\n---",
+        BASE64_STANDARD_NO_PAD.encode(salt),
+        BASE64_STANDARD_NO_PAD.encode(wrapped_key)
+    );
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&hkdf(&[], b"header", &file_key))?;
+    mac.update(header.as_bytes());
+    header.push_str(&format!(
+        " {}\n",
+        BASE64_STANDARD_NO_PAD.encode(mac.finalize().into_bytes())
+    ));
+
+    // Encrypt the payload in chunks with the STREAM construction, where the
+    // last byte of the chunk nonce marks the last chunk
+    let payload = ChaCha20Poly1305::new(&hkdf(&nonce, b"payload", &file_key).into());
+    let chunks: Vec<&[u8]> = if plaintext.is_empty() {
+        vec![&[]]
+    } else {
+        plaintext.chunks(PAYLOAD_CHUNK_SIZE).collect()
+    };
+    let mut binary = header.into_bytes();
+    binary.extend_from_slice(&nonce);
+    for (counter, chunk) in chunks.iter().enumerate() {
+        let mut chunk_nonce = [0; 12];
+        chunk_nonce[..11].copy_from_slice(&(counter as u128).to_be_bytes()[5..]);
+        chunk_nonce[11] = u8::from(counter == chunks.len() - 1);
+        binary.extend(
+            payload
+                .encrypt(&chunk_nonce.into(), *chunk)
+                .map_err(aead_error)?,
+        );
+    }
+
+    let mut encrypted = vec![];
+    let mut armored_writer = ArmoredWriter::wrap_output(&mut encrypted, AsciiArmor)?;
+    armored_writer.write_all(&binary)?;
+    armored_writer.finish()?;
+
+    let utf8 = std::string::String::from_utf8(encrypted)?;
+
+    Ok((plaintext.len(), utf8))
+}
+
+/// HKDF-SHA-256 of the input key material, as used by age
+#[cfg(feature = "insecure-deterministic")]
+fn hkdf(salt: &[u8], label: &[u8], ikm: &[u8]) -> [u8; 32] {
+    let mut okm = [0; 32];
+    Hkdf::<Sha256>::new(Some(salt), ikm)
+        .expand(label, &mut okm)
+        .expect("the output is the correct length");
+    okm
+}
+
+/// SHA-256 hash of the PEM encoded ciphertext as hex, which stays the same
+/// when the ciphertext is scanned or typed back in
+pub fn ciphertext_sha256(pem: &str) -> String {
+    let pem = normalize_pem(pem).unwrap_or_else(|| pem.to_string());
+    let digest = Sha256::digest(pem.as_bytes());
+    digest.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Join the parts of a split ciphertext, check and correct the line
+/// checksums, and extract the PEM encoded ciphertext from the text
+pub fn armored_ciphertext(text: &str) -> Result<String, PaperAgeError> {
+    let mut text = text.to_string();
+
+    if crate::split::is_split(&text) {
+        text = crate::split::join(&text)?;
+    }
+
+    if let Some(checked) = crate::checksums::check(&text)? {
+        for correction in &checked.corrections {
+            for (column, typed, corrected) in &correction.changes {
+                warn!(
+                    "Line {}, column {column}: corrected {typed} to {corrected}",
+                    correction.line
+                );
+            }
+        }
+        text = checked.pem;
+    }
+
+    normalize_pem(&text).ok_or_else(|| {
+        PaperAgeError::InvalidCiphertext("no PEM encoded age ciphertext found".to_string())
+    })
+}
+
+/// Decrypt a PEM encoded ciphertext using a passphrase
+pub fn decrypt_ciphertext(
+    reader: &mut dyn std::io::BufRead,
+    passphrase: SecretString,
+) -> Result<Vec<u8>, PaperAgeError> {
+    debug!("Decrypting ciphertext");
+
+    let mut text = String::new();
+    reader
+        .read_to_string(&mut text)
+        .map_err(|e| PaperAgeError::InvalidCiphertext(e.to_string()))?;
+
+    let armored = armored_ciphertext(&text)?;
+
+    let decryptor = age::Decryptor::new(ArmoredReader::new(armored.as_bytes()))
+        .map_err(|e| PaperAgeError::InvalidCiphertext(e.to_string()))?;
+
+    if !decryptor.is_scrypt() {
+        return Err(PaperAgeError::Decryption(
+            "the ciphertext is encrypted to recipients, not with a passphrase".to_string(),
+        ));
+    }
+
+    let identity = age::scrypt::Identity::new(passphrase);
+    let mut reader = decryptor
+        .decrypt(iter::once(&identity as &dyn age::Identity))
+        .map_err(decryption_error)?;
+
+    let mut plaintext = vec![];
+    reader
+        .read_to_end(&mut plaintext)
+        .map_err(|e| PaperAgeError::InvalidCiphertext(e.to_string()))?;
+
+    Ok(plaintext)
+}
+
+/// Map age decryption errors to a wrong passphrase or a damaged ciphertext
+fn decryption_error(error: DecryptError) -> PaperAgeError {
+    match error {
+        DecryptError::DecryptionFailed | DecryptError::KeyDecryptionFailed => {
+            PaperAgeError::IncorrectPassphrase
+        }
+        DecryptError::ExcessiveWork { .. } => PaperAgeError::Decryption(error.to_string()),
+        _ => PaperAgeError::InvalidCiphertext(error.to_string()),
+    }
+}
+
+/// Extract the PEM block from the text and undo the whitespace changes that
+/// come from copying, pasting, or typing in the ciphertext by hand
+fn normalize_pem(text: &str) -> Option<String> {
+    let mut body: Option<String> = None;
+
+    for line in text.lines() {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        if words.join(" ") == PEM_BEGIN {
+            body = Some(String::new());
+        } else if words.join(" ") == PEM_END {
+            if let Some(body) = body {
+                let chars: Vec<char> = body.chars().collect();
+                let mut pem = vec![PEM_BEGIN.to_string()];
+                pem.extend(
+                    chars
+                        .chunks(PEM_LINE_LEN)
+                        .map(|chunk| chunk.iter().collect::<String>()),
+                );
+                pem.push(PEM_END.to_string());
+                return Some(pem.join("\n") + "\n");
+            }
+        } else if let Some(body) = body.as_mut() {
+            body.push_str(&words.concat());
+        }
+    }
+
+    None
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    const TEST_SSH_ED25519_PK: &str =
+        "ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIHsKLqeplhpW+uObz5dvMgjz1OxfM/XXUB+VHtZ6isGN";
+    const TEST_SSH_RSA_PK: &str = "ssh-rsa AAAAB3NzaC1yc2EAAAADAQABAAABAQDE7nIXTGNuaRBN9toI/wNALuQec8mvlt0iJ7o3OaD2UvoKHJ7S8rmIn4FiQDUed/Vac3OhUibei1k+TBmm16u2Rj3klgWZOIDgi8d4vXKI5N3YBhxr3jsQ+kz1c+iZ4z/tTtz306+4K46XViVMWwyyg9j82Jn41mOAy9vdeDIfQ5fLeaGqn5KwlT61GNkZ+ozWK/ZNlQIlNCcoXxhJULIs9XrtczWyVBAea1nlDo0WHODePxoJjmsNHrpQXn5mf9O83xs10qfTUjnRUt48jRmedFy4tcra3QGmSTQ3KZne+wXXSb0cIpXLGvZjQSPHgG1hc4r3uBpiSzvesGLv79XL";
+
     #[test]
     fn test_armored_output() {
         let mut input = b"some secrets" as &[u8];
@@ -54,4 +578,305 @@ mod tests {
         let last_line: &str = armored.lines().last().unwrap();
         assert_eq!(last_line, "-----END AGE ENCRYPTED FILE-----")
     }
+
+    #[test]
+    fn test_encrypt_to_recipients() {
+        let identity = age::x25519::Identity::generate();
+        let mode = EncryptionMode::Recipients(vec![identity.to_public().into()]);
+        let (_, armored) = encrypt(&mut &b"some secrets"[..], &mode).unwrap();
+
+        let decryptor = age::Decryptor::new(ArmoredReader::new(armored.as_bytes())).unwrap();
+        assert!(!decryptor.is_scrypt());
+
+        let mut reader = decryptor
+            .decrypt(iter::once(&identity as &dyn age::Identity))
+            .unwrap();
+        let mut plaintext = vec![];
+        reader.read_to_end(&mut plaintext).unwrap();
+
+        assert_eq!(plaintext, b"some secrets");
+    }
+
+    #[test]
+    fn test_encrypt_without_recipients() {
+        let mode = EncryptionMode::Recipients(vec![]);
+        let result = encrypt(&mut &b"some secrets"[..], &mode);
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_default_notes_label() {
+        let passphrase = EncryptionMode::from("snakeoil");
+        assert_eq!(passphrase.default_notes_label(), "Passphrase:");
+
+        let recipients =
+            EncryptionMode::from(vec![age::x25519::Identity::generate().to_public().into()]);
+        assert_eq!(recipients.default_notes_label(), "Encrypted to:");
+
+        let recipients = EncryptionMode::from(
+            (0..3)
+                .map(|_| age::x25519::Identity::generate().to_public().into())
+                .collect::<Vec<_>>(),
+        );
+        assert_eq!(recipients.default_notes_label(), "Encrypted to:");
+        assert_eq!(
+            recipients.default_notes_label_in(Language::En),
+            "Any one of these 3 keys can decrypt:"
+        );
+    }
+
+    #[test]
+    fn test_short_fingerprint() {
+        let recipient =
+            parse_recipient("age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p")
+                .unwrap();
+        assert_eq!(recipient.short_fingerprint(), "age1ql3z7h…mcac8p");
+
+        let recipient = parse_recipient(TEST_SSH_ED25519_PK).unwrap();
+        assert_eq!(recipient.short_fingerprint(), "SHA256:PYX4o9UfGwCwG76h…");
+    }
+
+    #[test]
+    fn test_parse_recipient() {
+        let recipient = age::x25519::Identity::generate().to_public().to_string();
+        assert!(parse_recipient(&recipient).is_ok());
+
+        let result = parse_recipient("age1notarecipient");
+        assert!(matches!(result, Err(PaperAgeError::InvalidRecipient(_))));
+    }
+
+    #[test]
+    fn test_parse_ssh_recipient() {
+        let recipient = parse_recipient(&format!("{TEST_SSH_ED25519_PK} alice@rust")).unwrap();
+
+        assert_eq!(recipient.key_type(), "ssh-ed25519");
+        assert_eq!(recipient.comment(), Some("alice@rust"));
+        assert_eq!(
+            recipient.to_string(),
+            format!("{TEST_SSH_ED25519_PK} alice@rust")
+        );
+    }
+
+    #[test]
+    fn test_parse_ssh_recipient_with_options() {
+        let line = format!("no-pty,no-port-forwarding {TEST_SSH_RSA_PK}");
+        let recipient = parse_ssh_recipient(&line).unwrap();
+
+        assert_eq!(recipient.key_type(), "ssh-rsa");
+        assert_eq!(recipient.comment(), None);
+    }
+
+    #[test]
+    fn test_parse_unsupported_ssh_recipient() {
+        let result = parse_recipient("ecdsa-sha2-nistp256 AAAAE2VjZHNh");
+        assert!(matches!(result, Err(PaperAgeError::InvalidRecipient(_))));
+
+        let result = parse_recipient("not a key");
+        assert!(matches!(result, Err(PaperAgeError::InvalidRecipient(_))));
+    }
+
+    #[test]
+    fn test_ssh_fingerprint() {
+        let recipient = parse_recipient(TEST_SSH_ED25519_PK).unwrap();
+
+        // Same as `ssh-keygen -l` for the key
+        assert_eq!(
+            recipient.fingerprint(),
+            "SHA256:PYX4o9UfGwCwG76hFcLAEkmMS0PtIBtV9MNmVgW16Oc"
+        );
+    }
+
+    #[test]
+    fn test_encrypt_to_ssh_recipient() {
+        let recipient = parse_recipient(TEST_SSH_ED25519_PK).unwrap();
+        let mode = EncryptionMode::Recipients(vec![recipient]);
+        let result = encrypt(&mut &b"some secrets"[..], &mode);
+
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_read_recipients() {
+        let alice = age::x25519::Identity::generate().to_public().to_string();
+        let bob = age::x25519::Identity::generate().to_public().to_string();
+        let file = format!("# Alice\n{alice}\n\n  # Bob\n  {bob}  \n");
+
+        let recipients = read_recipients(&mut file.as_bytes()).unwrap();
+        assert_eq!(recipients.len(), 2);
+        assert_eq!(recipients[0].to_string(), alice);
+        assert_eq!(recipients[1].to_string(), bob);
+    }
+
+    #[test]
+    fn test_decrypt_ciphertext() {
+        let mut input = b"some secrets" as &[u8];
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let (_, armored) = encrypt_plaintext(&mut input, passphrase).unwrap();
+
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let result = decrypt_ciphertext(&mut armored.as_bytes(), passphrase);
+
+        assert_eq!(result.unwrap(), b"some secrets");
+    }
+
+    #[test]
+    fn test_decrypt_split_ciphertext() {
+        let input = "x".repeat(3000);
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let (_, armored) = encrypt_plaintext(&mut input.as_bytes(), passphrase).unwrap();
+
+        // Scanned in reverse order
+        let scanned: String = crate::split::split(&armored, crate::split::LINES_PER_PART)
+            .iter()
+            .rev()
+            .map(|part| part.payload())
+            .collect();
+
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let result = decrypt_ciphertext(&mut scanned.as_bytes(), passphrase);
+
+        assert_eq!(result.unwrap(), input.as_bytes());
+    }
+
+    #[test]
+    fn test_decrypt_incorrect_passphrase() {
+        let mut input = b"some secrets" as &[u8];
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let (_, armored) = encrypt_plaintext(&mut input, passphrase).unwrap();
+
+        let passphrase = SecretString::from("hunter2".to_owned());
+        let result = decrypt_ciphertext(&mut armored.as_bytes(), passphrase);
+
+        assert!(matches!(result, Err(PaperAgeError::IncorrectPassphrase)));
+    }
+
+    #[test]
+    fn test_decrypt_corrupted_ciphertext() {
+        let mut input = b"some secrets" as &[u8];
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let (_, armored) = encrypt_plaintext(&mut input, passphrase).unwrap();
+
+        // Change the first character of the base64 encoded header
+        let mut lines: Vec<String> = armored.lines().map(String::from).collect();
+        lines[1].replace_range(0..1, "Z");
+        let corrupted = lines.join("\n");
+
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let result = decrypt_ciphertext(&mut corrupted.as_bytes(), passphrase);
+
+        assert!(matches!(result, Err(PaperAgeError::InvalidCiphertext(_))));
+    }
+
+    #[test]
+    fn test_decrypt_missing_pem_block() {
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let result = decrypt_ciphertext(&mut &b"not a ciphertext"[..], passphrase);
+
+        assert!(matches!(result, Err(PaperAgeError::InvalidCiphertext(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "insecure-deterministic")]
+    fn test_encrypt_with_seed() {
+        let mode = EncryptionMode::from("snakeoil");
+        let plaintext = "x".repeat(PAYLOAD_CHUNK_SIZE + 100);
+        let encrypt = |seed| encrypt_with_seed(&mut plaintext.as_bytes(), &mode, seed).unwrap();
+        let (len, first) = encrypt(1);
+        assert_eq!(len, plaintext.len());
+        assert_eq!(encrypt(1).1, first);
+        assert_ne!(encrypt(2).1, first);
+
+        // age decrypts it like any other ciphertext
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let decrypted = decrypt_ciphertext(&mut first.as_bytes(), passphrase).unwrap();
+        assert_eq!(decrypted, plaintext.as_bytes());
+
+        let passphrase = SecretString::from("snakeoil".to_owned());
+        let (_, empty) = encrypt_with_rng(&mut &b""[..], &mode, &mut rand::thread_rng()).unwrap();
+        assert!(decrypt_ciphertext(&mut empty.as_bytes(), passphrase)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    #[cfg(feature = "insecure-deterministic")]
+    fn test_encrypt_with_seed_chunk_boundaries() {
+        let mode = EncryptionMode::from("snakeoil");
+
+        for len in [
+            0,
+            1,
+            PAYLOAD_CHUNK_SIZE - 1,
+            PAYLOAD_CHUNK_SIZE,
+            PAYLOAD_CHUNK_SIZE + 1,
+            2 * PAYLOAD_CHUNK_SIZE,
+            2 * PAYLOAD_CHUNK_SIZE + 1,
+        ] {
+            let plaintext: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            let (_, encrypted) = encrypt_with_seed(&mut plaintext.as_slice(), &mode, 1).unwrap();
+
+            let decryptor = age::Decryptor::new(ArmoredReader::new(encrypted.as_bytes())).unwrap();
+            let identity = age::scrypt::Identity::new(SecretString::from("snakeoil".to_owned()));
+            let mut reader = decryptor
+                .decrypt(iter::once(&identity as &dyn age::Identity))
+                .unwrap();
+            let mut decrypted = vec![];
+            reader.read_to_end(&mut decrypted).unwrap();
+
+            assert_eq!(decrypted, plaintext, "{len} bytes");
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "insecure-deterministic")]
+    fn test_encrypt_with_rng_recipients() {
+        let recipient = age::x25519::Identity::generate().to_public();
+        let mode = EncryptionMode::from(vec![recipient.into()]);
+        let result = encrypt_with_rng(&mut &b"Hello"[..], &mode, &mut rand::thread_rng());
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn test_ciphertext_sha256() {
+        let mode = EncryptionMode::from("snakeoil");
+        let (_, pem) = encrypt(&mut &b"Hello"[..], &mode).unwrap();
+        let sha256 = ciphertext_sha256(&pem);
+        assert_eq!(sha256.len(), 64);
+        assert!(sha256.chars().all(|c| c.is_ascii_hexdigit()));
+
+        // Typed in with different line breaks and whitespace
+        let typed = pem.replace('\n', "\r\n  ");
+        assert_eq!(ciphertext_sha256(&typed), sha256);
+        assert_eq!(
+            ciphertext_sha256(&armored_ciphertext(&typed).unwrap()),
+            sha256
+        );
+
+        let (_, other) = encrypt(&mut &b"Hello"[..], &mode).unwrap();
+        assert_ne!(ciphertext_sha256(&other), sha256);
+    }
+
+    #[test]
+    fn test_normalize_pem() {
+        let typed = "Some notes\n  -----BEGIN AGE  ENCRYPTED FILE-----\r\nYWJj ZGVm\n\n Z2hp \n-----END AGE ENCRYPTED FILE-----\nMore notes";
+
+        assert_eq!(
+            normalize_pem(typed).unwrap(),
+            "-----BEGIN AGE ENCRYPTED FILE-----\nYWJjZGVmZ2hp\n-----END AGE ENCRYPTED FILE-----\n"
+        );
+    }
+
+    #[test]
+    fn test_normalize_pem_rewraps_lines() {
+        let body = "A".repeat(100);
+        let typed = format!("{PEM_BEGIN}\n{body}\n{PEM_END}");
+        let normalized = normalize_pem(&typed).unwrap();
+
+        let lines: Vec<&str> = normalized.lines().collect();
+        assert_eq!(lines.len(), 4);
+        assert_eq!(lines[1].len(), 64);
+        assert_eq!(lines[2].len(), 36);
+    }
 }
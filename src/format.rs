@@ -0,0 +1,45 @@
+//! Output formats
+use std::fmt;
+
+/// Output file format
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Format {
+    /// PDF document
+    #[default]
+    Pdf,
+    /// SVG image, with the pages stacked from top to bottom
+    Svg,
+    /// PNG image, with the pages stacked from top to bottom
+    Png,
+}
+
+impl Format {
+    /// File name extension for the format
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Pdf => "pdf",
+            Format::Svg => "svg",
+            Format::Png => "png",
+        }
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.extension())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extension() {
+        assert_eq!(Format::default(), Format::Pdf);
+        assert_eq!(Format::Pdf.extension(), "pdf");
+        assert_eq!(Format::Svg.to_string(), "svg");
+        assert_eq!(Format::Png.extension(), "png");
+    }
+}
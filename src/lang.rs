@@ -0,0 +1,385 @@
+//! Translations of the fixed text on the page
+//!
+//! Each [`Language`] has a [`Catalog`] with every string that PaperAge prints
+//! on the page and on the instructions page. Placeholders in braces, such as
+//! `{count}`, are filled in with [`fill`].
+
+use std::fmt;
+
+/// Language of the fixed text on the page
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Language {
+    /// English
+    #[default]
+    En,
+    /// Finnish
+    Fi,
+    /// German
+    De,
+    /// French
+    Fr,
+    /// Japanese, which needs a fallback font with Japanese glyphs
+    Ja,
+}
+
+impl Language {
+    /// The translations of the language
+    pub fn catalog(&self) -> &'static Catalog {
+        match self {
+            Language::En => &EN,
+            Language::Fi => &FI,
+            Language::De => &DE,
+            Language::Fr => &FR,
+            Language::Ja => &JA,
+        }
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Language::En => write!(f, "en"),
+            Language::Fi => write!(f, "fi"),
+            Language::De => write!(f, "de"),
+            Language::Fr => write!(f, "fr"),
+            Language::Ja => write!(f, "ja"),
+        }
+    }
+}
+
+/// The fixed text on the page in one language
+#[derive(Debug)]
+pub struct Catalog {
+    /// Footer at the bottom of the page
+    pub footer: &'static str,
+    /// Default notes label with a passphrase
+    pub passphrase_label: &'static str,
+    /// Default label of a single recipient
+    pub recipient_label: &'static str,
+    /// Default label of several recipients, with `{count}`
+    pub recipients_label: &'static str,
+    /// Page number, with `{page}` and `{total}`
+    pub page_number: &'static str,
+    /// Label of a split part, with `{index}`, `{total}`, and `{hash}`
+    pub part_label: &'static str,
+    /// Subtitle of a Shamir share, with `{index}`, `{count}`, `{threshold}`,
+    /// and `{set}`
+    pub share_label: &'static str,
+    /// Hint at the bottom of a wallet card
+    pub card_hint: &'static str,
+    /// The recipients that don't fit on a wallet card, with `{count}`
+    pub more_recipients: &'static str,
+    /// Heading of the instructions page
+    pub instructions_title: &'static str,
+    /// Introduction of the instructions, with `{key}`
+    pub intro: &'static str,
+    /// What decrypts a passphrase encrypted secret
+    pub key_passphrase: &'static str,
+    /// What decrypts a secret encrypted to recipients
+    pub key_recipients: &'static str,
+    /// Step: install age
+    pub step_install: &'static str,
+    /// Step: scan the QR code
+    pub step_scan: &'static str,
+    /// Step: scan the QR codes of the split parts
+    pub step_scan_split: &'static str,
+    /// Step: type in the PEM text
+    pub step_type: &'static str,
+    /// Addition to the typing step with line checksums
+    pub step_checksums: &'static str,
+    /// Step: decrypt with a passphrase
+    pub step_decrypt_passphrase: &'static str,
+    /// Step: decrypt with a private key
+    pub step_decrypt_recipients: &'static str,
+    /// Step: keep the decrypted secret safe
+    pub step_keep: &'static str,
+    /// Version and date of the backup, with `{version}` and `{date}`
+    pub created: &'static str,
+    /// Sheet identifier on the metadata line, with `{id}`
+    pub sheet_id: &'static str,
+}
+
+impl Catalog {
+    /// All the strings of the catalog
+    pub fn strings(&self) -> [&'static str; 23] {
+        [
+            self.footer,
+            self.passphrase_label,
+            self.recipient_label,
+            self.recipients_label,
+            self.page_number,
+            self.part_label,
+            self.share_label,
+            self.card_hint,
+            self.more_recipients,
+            self.instructions_title,
+            self.intro,
+            self.key_passphrase,
+            self.key_recipients,
+            self.step_install,
+            self.step_scan,
+            self.step_scan_split,
+            self.step_type,
+            self.step_checksums,
+            self.step_decrypt_passphrase,
+            self.step_decrypt_recipients,
+            self.step_keep,
+            self.created,
+            self.sheet_id,
+        ]
+    }
+}
+
+/// Replace the `{name}` placeholders in the template with the values
+pub fn fill(template: &str, values: &[(&str, &dyn fmt::Display)]) -> String {
+    values
+        .iter()
+        .fold(template.to_string(), |text, (name, value)| {
+            text.replace(&format!("{{{name}}}"), &value.to_string())
+        })
+}
+
+static EN: Catalog = Catalog {
+    footer: "Scan QR code and decrypt using Age <https://age-encryption.org>",
+    passphrase_label: "Passphrase:",
+    recipient_label: "Encrypted to:",
+    recipients_label: "Any one of these {count} keys can decrypt:",
+    page_number: "Page {page} of {total}",
+    part_label: "Part {index} of {total} · {hash}",
+    share_label: "Share {index} of {count} · {threshold} needed · Set {set}",
+    card_hint: "Decrypt with age",
+    more_recipients: "+{count} more",
+    instructions_title: "How to restore this backup",
+    intro: "This sheet holds a secret encrypted with age, a simple and secure file encryption \
+        tool. Decrypting the secret requires {key}.",
+    key_passphrase: "the passphrase",
+    key_recipients: "the private key of one of the recipients listed on the sheet",
+    step_install: "Install age from https://age-encryption.org on a computer that you trust. \
+        PaperAge from https://github.com/matiaskorhonen/paper-age can restore the backup too, \
+        with paper-age restore.",
+    step_scan: "Scan the QR code with a phone or a QR code scanner. It contains the block of \
+        text printed on the sheet, from -----BEGIN AGE ENCRYPTED FILE----- to -----END AGE \
+        ENCRYPTED FILE-----. Save the text in a file, e.g. backup.age.",
+    step_scan_split: "The secret is split into several QR codes, each labelled with its part \
+        number. Scan all the parts, save them in one file, and join them with paper-age \
+        restore, in any order.",
+    step_type: "If the QR code doesn't scan, type in the block of text instead, line by line, \
+        including the BEGIN and END lines. It only has the letters A-Z and a-z, the digits 0-9, \
+        and the characters + / and =, so take care with look-alikes such as 0 and O, or 1, l, \
+        and I.",
+    step_checksums: "Leave out the line numbers and the checksums at the start and the end of \
+        each line, or let paper-age restore check and remove them.",
+    step_decrypt_passphrase: "Decrypt the file with the command below and type in the \
+        passphrase when asked:",
+    step_decrypt_recipients: "Decrypt the file with the command below, where key.txt is the \
+        private key of one of the recipients:",
+    step_keep: "Keep the decrypted secret safe, and delete the files that you no longer need.",
+    created: "Created with PaperAge v{version} on {date}",
+    sheet_id: "Sheet {id}",
+};
+
+static FI: Catalog = Catalog {
+    footer: "Skannaa QR-koodi ja pura salaus Agella <https://age-encryption.org>",
+    passphrase_label: "Salalause:",
+    recipient_label: "Salattu avaimelle:",
+    recipients_label: "Mikä tahansa näistä {count} avaimesta purkaa salauksen:",
+    page_number: "Sivu {page}/{total}",
+    part_label: "Osa {index}/{total} · {hash}",
+    share_label: "Osuus {index}/{count} · {threshold} tarvitaan · Sarja {set}",
+    card_hint: "Pura salaus agella",
+    more_recipients: "+{count} muuta",
+    instructions_title: "Varmuuskopion palauttaminen",
+    intro: "Tällä arkilla on salaisuus, joka on salattu agella, yksinkertaisella ja \
+        turvallisella tiedostojen salausohjelmalla. Salauksen purkamiseen tarvitaan {key}.",
+    key_passphrase: "salalause",
+    key_recipients: "jonkin arkille listatun vastaanottajan yksityinen avain",
+    step_install: "Asenna age osoitteesta https://age-encryption.org tietokoneelle, johon \
+        luotat. Myös PaperAge osoitteesta https://github.com/matiaskorhonen/paper-age voi \
+        palauttaa varmuuskopion komennolla paper-age restore.",
+    step_scan: "Skannaa QR-koodi puhelimella tai QR-koodinlukijalla. Se sisältää arkille \
+        tulostetun tekstilohkon rivistä -----BEGIN AGE ENCRYPTED FILE----- riviin -----END AGE \
+        ENCRYPTED FILE-----. Tallenna teksti tiedostoon, esim. backup.age.",
+    step_scan_split: "Salaisuus on jaettu useaan QR-koodiin, joihin kuhunkin on merkitty osan \
+        numero. Skannaa kaikki osat, tallenna ne samaan tiedostoon ja yhdistä ne komennolla \
+        paper-age restore missä tahansa järjestyksessä.",
+    step_type: "Jos QR-koodi ei skannaudu, kirjoita tekstilohko rivi riviltä, myös BEGIN- ja \
+        END-rivit. Siinä on vain kirjaimet A–Z ja a–z, numerot 0–9 sekä merkit + / ja =, joten \
+        varo samannäköisiä merkkejä, kuten 0 ja O tai 1, l ja I.",
+    step_checksums: "Jätä pois rivinumerot ja tarkistussummat kunkin rivin alusta ja lopusta, \
+        tai anna paper-age restore -komennon tarkistaa ja poistaa ne.",
+    step_decrypt_passphrase: "Pura tiedoston salaus alla olevalla komennolla ja kirjoita \
+        salalause pyydettäessä:",
+    step_decrypt_recipients: "Pura tiedoston salaus alla olevalla komennolla, jossa key.txt \
+        on jonkin vastaanottajan yksityinen avain:",
+    step_keep: "Säilytä purettu salaisuus turvassa ja poista tiedostot, joita et enää tarvitse.",
+    created: "Luotu PaperAgen versiolla v{version} {date}",
+    sheet_id: "Arkki {id}",
+};
+
+static DE: Catalog = Catalog {
+    footer: "QR-Code scannen und mit Age entschlüsseln <https://age-encryption.org>",
+    passphrase_label: "Passphrase:",
+    recipient_label: "Verschlüsselt für:",
+    recipients_label: "Jeder dieser {count} Schlüssel kann entschlüsseln:",
+    page_number: "Seite {page} von {total}",
+    part_label: "Teil {index} von {total} · {hash}",
+    share_label: "Anteil {index} von {count} · {threshold} benötigt · Satz {set}",
+    card_hint: "Mit age entschlüsseln",
+    more_recipients: "+{count} weitere",
+    instructions_title: "So stellen Sie diese Sicherung wieder her",
+    intro: "Dieses Blatt enthält ein Geheimnis, das mit age verschlüsselt ist, einem einfachen \
+        und sicheren Werkzeug zur Dateiverschlüsselung. Zum Entschlüsseln wird {key} benötigt.",
+    key_passphrase: "die Passphrase",
+    key_recipients: "der private Schlüssel eines der auf dem Blatt aufgeführten Empfänger",
+    step_install: "Installieren Sie age von https://age-encryption.org auf einem Computer, dem \
+        Sie vertrauen. Auch PaperAge von https://github.com/matiaskorhonen/paper-age kann die \
+        Sicherung mit paper-age restore wiederherstellen.",
+    step_scan: "Scannen Sie den QR-Code mit einem Smartphone oder einem QR-Code-Scanner. Er \
+        enthält den auf dem Blatt gedruckten Textblock von -----BEGIN AGE ENCRYPTED FILE----- \
+        bis -----END AGE ENCRYPTED FILE-----. Speichern Sie den Text in einer Datei, z. B. \
+        backup.age.",
+    step_scan_split: "Das Geheimnis ist auf mehrere QR-Codes aufgeteilt, die jeweils mit ihrer \
+        Teilnummer beschriftet sind. Scannen Sie alle Teile, speichern Sie sie in einer Datei \
+        und fügen Sie sie mit paper-age restore in beliebiger Reihenfolge zusammen.",
+    step_type: "Falls sich der QR-Code nicht scannen lässt, tippen Sie den Textblock Zeile für \
+        Zeile ab, einschließlich der BEGIN- und END-Zeilen. Er enthält nur die Buchstaben A–Z \
+        und a–z, die Ziffern 0–9 und die Zeichen + / und =. Achten Sie auf ähnlich aussehende \
+        Zeichen wie 0 und O oder 1, l und I.",
+    step_checksums: "Lassen Sie die Zeilennummern und Prüfsummen am Anfang und Ende jeder Zeile \
+        weg, oder lassen Sie paper-age restore sie prüfen und entfernen.",
+    step_decrypt_passphrase: "Entschlüsseln Sie die Datei mit dem folgenden Befehl und geben \
+        Sie die Passphrase ein, wenn Sie dazu aufgefordert werden:",
+    step_decrypt_recipients: "Entschlüsseln Sie die Datei mit dem folgenden Befehl, wobei \
+        key.txt der private Schlüssel eines der Empfänger ist:",
+    step_keep: "Bewahren Sie das entschlüsselte Geheimnis sicher auf und löschen Sie Dateien, \
+        die Sie nicht mehr benötigen.",
+    created: "Erstellt mit PaperAge v{version} am {date}",
+    sheet_id: "Blatt {id}",
+};
+
+static FR: Catalog = Catalog {
+    footer: "Scannez le code QR et déchiffrez avec Age <https://age-encryption.org>",
+    passphrase_label: "Phrase secrète :",
+    recipient_label: "Chiffré pour :",
+    recipients_label: "Chacune de ces {count} clés peut déchiffrer :",
+    page_number: "Page {page} sur {total}",
+    part_label: "Partie {index} sur {total} · {hash}",
+    share_label: "Part {index} sur {count} · {threshold} requises · Lot {set}",
+    card_hint: "Déchiffrer avec age",
+    more_recipients: "+{count} autres",
+    instructions_title: "Comment restaurer cette sauvegarde",
+    intro: "Cette feuille contient un secret chiffré avec age, un outil de chiffrement de \
+        fichiers simple et sûr. Pour déchiffrer le secret, il faut {key}.",
+    key_passphrase: "la phrase secrète",
+    key_recipients: "la clé privée de l'un des destinataires indiqués sur la feuille",
+    step_install: "Installez age depuis https://age-encryption.org sur un ordinateur de \
+        confiance. PaperAge, disponible sur https://github.com/matiaskorhonen/paper-age, peut \
+        aussi restaurer la sauvegarde avec paper-age restore.",
+    step_scan: "Scannez le code QR avec un téléphone ou un lecteur de codes QR. Il contient le \
+        bloc de texte imprimé sur la feuille, de -----BEGIN AGE ENCRYPTED FILE----- à -----END \
+        AGE ENCRYPTED FILE-----. Enregistrez le texte dans un fichier, par exemple backup.age.",
+    step_scan_split: "Le secret est réparti sur plusieurs codes QR, chacun portant son numéro \
+        de partie. Scannez toutes les parties, enregistrez-les dans un même fichier et \
+        assemblez-les avec paper-age restore, dans n'importe quel ordre.",
+    step_type: "Si le code QR ne se scanne pas, saisissez le bloc de texte ligne par ligne, y \
+        compris les lignes BEGIN et END. Il ne contient que les lettres A–Z et a–z, les chiffres \
+        0–9 et les caractères + / et =. Attention aux caractères qui se ressemblent, comme 0 et \
+        O, ou 1, l et I.",
+    step_checksums: "Omettez les numéros de ligne et les sommes de contrôle au début et à la fin \
+        de chaque ligne, ou laissez paper-age restore les vérifier et les retirer.",
+    step_decrypt_passphrase: "Déchiffrez le fichier avec la commande ci-dessous et saisissez la \
+        phrase secrète lorsqu'elle est demandée :",
+    step_decrypt_recipients: "Déchiffrez le fichier avec la commande ci-dessous, où key.txt est \
+        la clé privée de l'un des destinataires :",
+    step_keep: "Conservez le secret déchiffré en lieu sûr et supprimez les fichiers dont vous \
+        n'avez plus besoin.",
+    created: "Créé avec PaperAge v{version} le {date}",
+    sheet_id: "Feuille {id}",
+};
+
+static JA: Catalog = Catalog {
+    footer: "QRコードをスキャンし、Ageで復号してください <https://age-encryption.org>",
+    passphrase_label: "パスフレーズ:",
+    recipient_label: "暗号化の宛先:",
+    recipients_label: "次の{count}個の鍵のいずれでも復号できます:",
+    page_number: "{page} / {total} ページ",
+    part_label: "パート {index} / {total} · {hash}",
+    share_label: "シェア {index} / {count} · 必要数 {threshold} · セット {set}",
+    card_hint: "ageで復号",
+    more_recipients: "他 {count} 件",
+    instructions_title: "このバックアップの復元方法",
+    intro: "この用紙には、シンプルで安全なファイル暗号化ツール age で暗号化された秘密情報が\
+        記載されています。復号には{key}が必要です。",
+    key_passphrase: "パスフレーズ",
+    key_recipients: "用紙に記載された受信者のいずれかの秘密鍵",
+    step_install: "信頼できるコンピューターに https://age-encryption.org から age を\
+        インストールしてください。https://github.com/matiaskorhonen/paper-age の PaperAge でも \
+        paper-age restore でバックアップを復元できます。",
+    step_scan: "スマートフォンまたはQRコードリーダーでQRコードをスキャンしてください。\
+        QRコードには、用紙に印刷された -----BEGIN AGE ENCRYPTED FILE----- から -----END AGE \
+        ENCRYPTED FILE----- までのテキストが含まれています。テキストを backup.age などの\
+        ファイルに保存してください。",
+    step_scan_split: "秘密情報は複数のQRコードに分割されており、それぞれにパート番号が\
+        記載されています。すべてのパートをスキャンして1つのファイルに保存し、paper-age restore \
+        で結合してください。順序は問いません。",
+    step_type: "QRコードを読み取れない場合は、BEGIN 行と END 行を含めてテキストを1行ずつ\
+        入力してください。使われている文字は A–Z、a–z、0–9 と + / = のみです。0 と O、\
+        1 と l と I など、似た文字に注意してください。",
+    step_checksums: "各行の先頭の行番号と末尾のチェックサムは入力しないか、paper-age restore \
+        で確認して取り除いてください。",
+    step_decrypt_passphrase: "次のコマンドでファイルを復号し、求められたらパスフレーズを\
+        入力してください:",
+    step_decrypt_recipients: "次のコマンドでファイルを復号してください。key.txt は受信者の\
+        いずれかの秘密鍵です:",
+    step_keep: "復号した秘密情報は安全に保管し、不要になったファイルは削除してください。",
+    created: "PaperAge v{version} で {date} に作成",
+    sheet_id: "シート {id}",
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fill() {
+        assert_eq!(
+            fill(EN.page_number, &[("page", &1), ("total", &3)]),
+            "Page 1 of 3"
+        );
+        assert_eq!(
+            fill(FI.page_number, &[("page", &1), ("total", &3)]),
+            "Sivu 1/3"
+        );
+        assert_eq!(fill("{missing}", &[]), "{missing}");
+    }
+
+    #[test]
+    fn test_catalogs_have_the_same_placeholders() {
+        let placeholders = |text: &str| {
+            let mut names: Vec<String> = text
+                .split('{')
+                .skip(1)
+                .filter_map(|rest| rest.split_once('}').map(|(name, _)| name.to_string()))
+                .collect();
+            names.sort();
+            names
+        };
+
+        for language in [Language::Fi, Language::De, Language::Fr, Language::Ja] {
+            for (english, translated) in EN.strings().iter().zip(language.catalog().strings()) {
+                assert_eq!(
+                    placeholders(english),
+                    placeholders(translated),
+                    "{language}: {translated}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(Language::default().to_string(), "en");
+        assert_eq!(Language::Ja.to_string(), "ja");
+    }
+}
@@ -1,6 +1,18 @@
+#[cfg(feature = "cli")]
+pub mod batch;
 pub mod builder;
+pub mod checksums;
 #[cfg(feature = "cli")]
 pub mod cli;
+#[cfg(feature = "cli")]
+pub mod config;
 pub mod convenience;
 pub mod encryption;
+pub mod format;
+pub mod lang;
+pub mod metadata;
 pub mod page;
+pub mod scan;
+pub mod shamir;
+pub mod split;
+pub mod verify;
@@ -5,38 +5,238 @@
 #![doc(html_favicon_url = "https://shots.matiaskorhonen.fi/paper-age-favicon.ico")]
 
 use std::{
+    collections::HashSet,
     env,
     fs::File,
-    io::{self, stdin, BufReader, BufWriter, Read, Write},
-    path::PathBuf,
+    io::{self, stdin, BufRead, BufReader, BufWriter, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
 };
 
 use age::secrecy::{ExposeSecret, SecretString};
 use clap::Parser;
-use paper_age::{builder, cli, encryption};
+use paper_age::{
+    batch,
+    builder::{
+        self,
+        layout::Font,
+        metrics::{Fonts, UserFont},
+    },
+    cli, config,
+    convenience::PaperAgeError,
+    encryption::{self, EncryptionMode, Recipient},
+    format::Format,
+    metadata::Metadata,
+    scan, shamir, split, verify,
+};
 use qrcode::types::QrError;
+use rand::{rngs::StdRng, SeedableRng};
 use rpassword::prompt_password;
+use unicode_segmentation::UnicodeSegmentation;
 
 #[macro_use]
 extern crate log;
 
-/// Maximum length of the document title
+/// Maximum length of the document title in characters
 const TITLE_MAX_LEN: usize = 64;
 
+/// Maximum length of the notes label in characters
+const NOTES_LABEL_MAX_LEN: usize = 32;
+
+/// Characters of the PEM encoded ciphertext
+const PEM_CHARACTERS: &str = "-ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/= ";
+
 fn main() -> Result<(), Box<dyn std::error::Error>> {
-    let args = cli::Args::parse();
+    let args = match config::with_config(env::args_os().collect()) {
+        Ok(args) => cli::Args::parse_from(args),
+        Err(error) => {
+            eprintln!("Error: {error}");
+            std::process::exit(exitcode::CONFIG);
+        }
+    };
 
     env_logger::Builder::new()
         .filter_level(args.verbose.log_level_filter())
         .init();
 
     if args.fonts_license {
-        let license = include_bytes!("assets/fonts/IBMPlexMono-LICENSE.txt");
-        io::stdout().write_all(license)?;
+        let fonts = load_fonts(&args);
+        check_glyphs(&args, &fonts);
+        io::stdout().write_all(fonts_license(&fonts).as_bytes())?;
         return Ok(());
     }
 
-    if args.title.len() > TITLE_MAX_LEN {
+    match args.command {
+        Some(cli::Command::Restore(restore_args)) => return restore(restore_args),
+        Some(cli::Command::Batch(batch_args)) => return batch(batch_args),
+        None => {}
+    }
+
+    check_args(&args);
+
+    let fonts = load_fonts(&args);
+    check_glyphs(&args, &fonts);
+
+    let mut rng = random_number_generator(&args);
+
+    let outputs = output_paths(&args);
+    for output in &outputs {
+        check_output(output, args.force);
+    }
+
+    let path = match args.input.clone() {
+        Some(p) => p,
+        None => PathBuf::from("-"),
+    };
+    let mut reader = open_input(&path, args.split)?;
+
+    let mode = if args.recipient.is_empty()
+        && args.ssh_recipient.is_empty()
+        && args.recipients_file.is_empty()
+    {
+        EncryptionMode::Passphrase(get_passphrase()?)
+    } else {
+        recipients_mode(&args)
+    };
+
+    let mut plaintext = vec![];
+    reader.read_to_end(&mut plaintext)?;
+
+    let documents = create_documents(&args, &fonts, &plaintext, &mode, &mut rng)?;
+    for (document, output) in documents.iter().zip(outputs) {
+        write_output(&output, &document.save_to_bytes()?)?;
+    }
+
+    Ok(())
+}
+
+/// Create the sheets listed in the manifest, prompting for the shared
+/// passphrase at most once
+fn batch(args: cli::BatchArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let sheets = match batch::read_manifest(&args.manifest) {
+        Ok(sheets) => sheets,
+        Err(error @ batch::BatchError::Read(..)) => {
+            error!("{error}");
+            std::process::exit(exitcode::NOINPUT);
+        }
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(exitcode::DATAERR);
+        }
+    };
+    info!("Creating {} sheet(s)", sheets.len());
+
+    // The fonts are the same for every sheet
+    let fonts = load_fonts(&sheets[0].args);
+    for sheet in &sheets {
+        check_args(&sheet.args);
+        check_glyphs(&sheet.args, &fonts);
+    }
+
+    let mut outputs = vec![];
+    match &args.combined {
+        Some(combined) => {
+            if sheets.iter().any(|sheet| sheet.args.format != Format::Pdf) {
+                error!("--combined only writes PDF, please remove the format option of the sheets");
+                std::process::exit(exitcode::USAGE);
+            }
+            if sheets.iter().any(|sheet| sheet.args.shares.is_some()) {
+                error!("Shamir shares can't be combined into one PDF, please write them to their own files");
+                std::process::exit(exitcode::USAGE);
+            }
+            check_output(combined, args.force);
+        }
+        None => {
+            for (index, sheet) in sheets.iter().enumerate() {
+                if sheet.args.output.is_none() {
+                    error!(
+                        "Sheet {}: The sheet has no output file, please give one or use --combined",
+                        index + 1
+                    );
+                    std::process::exit(exitcode::USAGE);
+                }
+                for output in output_paths(&sheet.args) {
+                    check_output(&output, args.force || sheet.args.force);
+                    outputs.push(output);
+                }
+            }
+            let mut unique = HashSet::new();
+            if let Some(output) = outputs.iter().find(|output| !unique.insert(*output)) {
+                error!("Several sheets are written to {}", output.display());
+                std::process::exit(exitcode::USAGE);
+            }
+        }
+    }
+
+    let mut rng = StdRng::from_entropy();
+    let mut shared_passphrase = None;
+    let mut documents = vec![];
+    for (index, sheet) in sheets.iter().enumerate() {
+        info!("Sheet {}: {}", index + 1, sheet.args.title);
+
+        let path = sheet.args.input.clone().unwrap_or_default();
+        if !path.is_file() {
+            error!("Sheet {}: File not found: {}", index + 1, path.display());
+            std::process::exit(exitcode::NOINPUT);
+        }
+        let mut plaintext = vec![];
+        open_input(&path, sheet.args.split)?.read_to_end(&mut plaintext)?;
+
+        let has_recipients = !sheet.args.recipient.is_empty()
+            || !sheet.args.ssh_recipient.is_empty()
+            || !sheet.args.recipients_file.is_empty();
+        let mode = if has_recipients {
+            recipients_mode(&sheet.args)
+        } else {
+            let passphrase = match &sheet.passphrase {
+                batch::PassphraseSource::Shared => match &shared_passphrase {
+                    Some(passphrase) => passphrase,
+                    None => shared_passphrase.insert(get_passphrase()?),
+                }
+                .clone(),
+                source => match read_passphrase(source) {
+                    Ok(passphrase) => passphrase,
+                    Err(error) => {
+                        error!("Sheet {}: {error}", index + 1);
+                        std::process::exit(exitcode::NOINPUT);
+                    }
+                },
+            };
+            EncryptionMode::Passphrase(passphrase)
+        };
+
+        documents.extend(create_documents(
+            &sheet.args,
+            &fonts,
+            &plaintext,
+            &mode,
+            &mut rng,
+        )?);
+    }
+
+    // Nothing is written until every sheet has been created
+    match &args.combined {
+        Some(combined) => {
+            let title = args
+                .manifest
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_else(|| String::from("PaperAge"));
+            write_output(combined, &builder::combined_pdf(&title, &documents)?)?;
+        }
+        None => {
+            for (document, output) in documents.iter().zip(outputs) {
+                write_output(&output, &document.save_to_bytes()?)?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Exit if the title or the notes label is too long, or if the options don't
+/// work together
+fn check_args(args: &cli::Args) {
+    if args.title.graphemes(true).count() > TITLE_MAX_LEN {
         error!(
             "The title cannot be longer than {} characters",
             TITLE_MAX_LEN
@@ -44,70 +244,528 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         std::process::exit(exitcode::DATAERR);
     }
 
-    let output = args.output;
+    if let Some(label) = &args.notes_label {
+        if label.graphemes(true).count() > NOTES_LABEL_MAX_LEN {
+            error!(
+                "The notes label cannot be longer than {} characters",
+                NOTES_LABEL_MAX_LEN
+            );
+            std::process::exit(exitcode::DATAERR);
+        }
+    }
+
+    if args.verify && args.format == Format::Svg {
+        error!("--verify is not supported with SVG output");
+        std::process::exit(exitcode::USAGE);
+    }
+}
+
+/// The output files, one for each Shamir share with --shares, exiting if the
+/// shares would be written to STDOUT
+fn output_paths(args: &cli::Args) -> Vec<PathBuf> {
+    let output_path = output_path(args);
+
+    match args.shares {
+        Some(count) if output_path.to_string_lossy() == "-" => {
+            error!("Can't write {count} shares to STDOUT, please give an output file name");
+            std::process::exit(exitcode::USAGE);
+        }
+        Some(count) => (1..=count)
+            .map(|index| share_output_path(&output_path, index))
+            .collect(),
+        None => vec![output_path],
+    }
+}
+
+/// Exit if the output file exists, unless it should be overwritten
+fn check_output(output: &Path, force: bool) {
     if output.exists() {
-        if args.force {
+        if force {
             warn!("Overwriting existing output file: {}", output.display());
         } else {
             error!("Output file already exists: {}", output.display());
             std::process::exit(exitcode::CANTCREAT);
         }
     }
+}
 
-    let path = match args.input {
-        Some(p) => p,
-        None => PathBuf::from("-"),
-    };
-    let mut reader: BufReader<Box<dyn Read>> = {
-        if path.to_string_lossy() == "-" {
-            BufReader::new(Box::new(stdin().lock()))
-        } else if path.is_file() {
-            let size = path.metadata()?.len();
-            if size >= 2048 {
-                warn!("File too large ({size:?} bytes). The maximum file size is about 1.9 KiB.");
-            }
-            BufReader::new(Box::new(File::open(&path).unwrap()))
-        } else {
-            error!("File not found: {}", path.display());
-            std::process::exit(exitcode::NOINPUT);
+/// Open the input file or STDIN, exiting if the file doesn't exist
+fn open_input(path: &Path, split: bool) -> Result<BufReader<Box<dyn Read>>, io::Error> {
+    if path.to_string_lossy() == "-" {
+        Ok(BufReader::new(Box::new(stdin().lock())))
+    } else if path.is_file() {
+        let size = path.metadata()?.len();
+        if size >= 2048 && !split {
+            warn!("File too large ({size:?} bytes). The maximum file size is about 1.9 KiB.");
         }
-    };
+        Ok(BufReader::new(Box::new(File::open(path)?)))
+    } else {
+        error!("File not found: {}", path.display());
+        std::process::exit(exitcode::NOINPUT);
+    }
+}
 
-    let passphrase = get_passphrase()?;
+/// Encrypt to the recipients in the arguments, exiting if they're invalid
+fn recipients_mode(args: &cli::Args) -> EncryptionMode {
+    match get_recipients(&args.recipient, &args.ssh_recipient, &args.recipients_file) {
+        Ok(recipients) => EncryptionMode::Recipients(recipients),
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(exitcode::DATAERR);
+        }
+    }
+}
+
+/// Encrypt the plaintext, or each of its Shamir shares with --shares, and lay
+/// out the pages, verifying them with --verify
+fn create_documents(
+    args: &cli::Args,
+    fonts: &Fonts,
+    plaintext: &[u8],
+    mode: &EncryptionMode,
+    rng: &mut StdRng,
+) -> Result<Vec<builder::Document>, Box<dyn std::error::Error>> {
+    if let Some((count, threshold)) = args.shares.zip(args.threshold) {
+        let shares = match shamir::split(plaintext, threshold, count, rng) {
+            Ok(s) => s,
+            Err(error) => {
+                error!("{error}");
+                std::process::exit(exitcode::USAGE);
+            }
+        };
+        info!(
+            "Split the plaintext into {count} shares (threshold {threshold}), set {}",
+            shares[0].set_id
+        );
+
+        let mut documents = vec![];
+        for share in &shares {
+            // Encrypt each share separately...
+            let share_bytes = share.to_bytes();
+            let (_, encrypted) = encrypt(args, &mut share_bytes.as_slice(), mode, rng)?;
+            info!(
+                "Share {} encrypted length: {:?} bytes",
+                share.index,
+                encrypted.len()
+            );
+
+            let document = build_document(
+                args,
+                fonts,
+                encrypted.clone(),
+                mode,
+                Some(share.label_in(args.lang)),
+                rng,
+            )?;
+            if args.verify {
+                let bytes = document.save_to_bytes()?;
+                verify_output(&bytes, args.format, &encrypted, mode, &share_bytes);
+            }
+            documents.push(document);
+        }
 
-    // Encrypt the plaintext to a ciphertext using the passphrase...
-    let (plaintext_len, encrypted) = encryption::encrypt_plaintext(&mut reader, passphrase)?;
+        return Ok(documents);
+    }
+
+    // Encrypt the plaintext to a ciphertext using the passphrase or recipients...
+    let (plaintext_len, encrypted) = encrypt(args, &mut &plaintext[..], mode, rng)?;
 
     info!("Plaintext length: {plaintext_len:?} bytes");
     info!("Encrypted length: {:?} bytes", encrypted.len());
 
-    let pdf = builder::Document::new(args.title, args.page_size)?;
+    let document = build_document(args, fonts, encrypted.clone(), mode, None, rng)?;
+    if args.verify {
+        let bytes = document.save_to_bytes()?;
+        verify_output(&bytes, args.format, &encrypted, mode, plaintext);
+    }
+
+    Ok(vec![document])
+}
+
+/// Load the fonts given on the command line, exiting if a font can't be read
+fn load_fonts(args: &cli::Args) -> Fonts {
+    let load = |path: &Option<PathBuf>, name: &str| {
+        let path = path.as_ref()?;
+        let bytes = match std::fs::read(path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                error!("Failed to read the {name} font {}: {e}", path.display());
+                std::process::exit(exitcode::NOINPUT);
+            }
+        };
+        match UserFont::from_bytes(bytes) {
+            Ok(font) => Some(font),
+            Err(e) => {
+                error!("Failed to parse the {name} font {}: {e}", path.display());
+                std::process::exit(exitcode::DATAERR);
+            }
+        }
+    };
+
+    Fonts {
+        title: load(&args.title_font, "title"),
+        code: load(&args.code_font, "code"),
+        fallback: load(&args.fallback_font, "fallback"),
+    }
+}
+
+/// Exit if the title, the notes label, the PEM text, or the page text for
+/// `--lang` has characters that none of the fonts cover
+fn check_glyphs(args: &cli::Args, fonts: &Fonts) {
+    let page_text = args.lang.catalog().strings().concat();
+    let page_text_name = format!("the page text for --lang {}", args.lang);
+    let texts = [
+        ("the title", Some(args.title.as_str()), Font::Title),
+        ("the notes label", args.notes_label.as_deref(), Font::Title),
+        ("the ciphertext", Some(PEM_CHARACTERS), Font::Code),
+        (
+            page_text_name.as_str(),
+            Some(page_text.as_str()),
+            Font::Title,
+        ),
+        (
+            page_text_name.as_str(),
+            Some(page_text.as_str()),
+            Font::Code,
+        ),
+    ];
+    for (name, text, font) in texts {
+        let missing = fonts.missing_glyphs(text.unwrap_or_default(), font);
+        if !missing.is_empty() {
+            let missing: String = missing.into_iter().collect();
+            error!("The fonts have no glyphs for \"{missing}\" in {name}, please give a font that does with --fallback-font");
+            std::process::exit(exitcode::DATAERR);
+        }
+    }
+}
+
+/// The licenses of the fonts that are embedded in the output
+fn fonts_license(fonts: &Fonts) -> String {
+    let mut sections = vec![];
+    if fonts.title.is_none() || fonts.code.is_none() {
+        sections.push(include_str!("assets/fonts/IBMPlexMono-LICENSE.txt").to_string());
+    }
+    for (font, name) in [
+        (&fonts.title, "title"),
+        (&fonts.code, "code"),
+        (&fonts.fallback, "fallback"),
+    ] {
+        if let Some(font) = font {
+            let license = font
+                .license()
+                .unwrap_or_else(|| String::from("The font has no license information"));
+            sections.push(format!("The {name} font:\n{license}\n"));
+        }
+    }
+
+    sections.join("\n")
+}
+
+/// The random number generator for the sheet ID and the Shamir shares, seeded
+/// with --insecure-deterministic
+#[cfg(feature = "insecure-deterministic")]
+fn random_number_generator(args: &cli::Args) -> StdRng {
+    match args.insecure_deterministic {
+        Some(seed) => {
+            warn!("Encrypting deterministically with seed {seed}, never use the output for real secrets");
+            StdRng::seed_from_u64(seed)
+        }
+        None => StdRng::from_entropy(),
+    }
+}
+
+/// The random number generator for the sheet ID and the Shamir shares
+#[cfg(not(feature = "insecure-deterministic"))]
+fn random_number_generator(_args: &cli::Args) -> StdRng {
+    StdRng::from_entropy()
+}
+
+/// Encrypt the plaintext, with a seed from the random number generator when
+/// the output should be deterministic
+#[cfg(feature = "insecure-deterministic")]
+fn encrypt(
+    args: &cli::Args,
+    reader: &mut dyn BufRead,
+    mode: &EncryptionMode,
+    rng: &mut StdRng,
+) -> Result<(usize, String), Box<dyn std::error::Error>> {
+    if args.insecure_deterministic.is_some() {
+        encryption::encrypt_with_seed(reader, mode, rand::RngCore::next_u64(rng))
+    } else {
+        encryption::encrypt(reader, mode)
+    }
+}
+
+/// Encrypt the plaintext
+#[cfg(not(feature = "insecure-deterministic"))]
+fn encrypt(
+    _args: &cli::Args,
+    reader: &mut dyn BufRead,
+    mode: &EncryptionMode,
+    _rng: &mut StdRng,
+) -> Result<(usize, String), Box<dyn std::error::Error>> {
+    encryption::encrypt(reader, mode)
+}
+
+/// Lay out the pages for the ciphertext, exiting if the ciphertext doesn't fit
+fn build_document(
+    args: &cli::Args,
+    fonts: &Fonts,
+    encrypted: String,
+    mode: &EncryptionMode,
+    subtitle: Option<String>,
+    rng: &mut StdRng,
+) -> Result<builder::Document, Box<dyn std::error::Error>> {
+    let mut pdf =
+        builder::Document::with_fonts(args.title.clone(), args.page_size.clone(), fonts.clone())?;
+    pdf.orientation = args.orientation;
+    pdf.split = args.split;
+    pdf.subtitle = subtitle;
+    pdf.line_checksums = args.line_checksums;
+    pdf.copies = args.copies.into();
+    pdf.wallet_card = args.wallet_card;
+    pdf.card_count = args.cards.map(usize::from);
+    pdf.instructions = args.instructions;
+    pdf.language = args.lang;
+    pdf.metadata = Metadata::from_fields(&args.metadata);
+    pdf.sheet_id = builder::random_sheet_id(rng);
+    pdf.format = args.format;
+    pdf.dpi = args.dpi;
+
+    if pdf.metadata.sheet_id {
+        info!("Sheet ID: {}", pdf.sheet_id);
+    }
 
-    let bytes = match pdf.create_pdf(args.grid, args.notes_label, args.skip_notes_line, encrypted) {
-        Ok(b) => b,
+    match pdf.layout(
+        args.grid,
+        args.notes_label.clone(),
+        args.skip_notes_line,
+        encrypted,
+        mode,
+    ) {
+        Ok(()) => Ok(pdf),
         Err(error) => {
-            if error.is::<QrError>() {
+            if error.is::<QrError>() && args.wallet_card {
+                error!(
+                    "Too much data after encryption for a wallet card, please try a smaller secret"
+                );
+                std::process::exit(exitcode::DATAERR);
+            } else if error.is::<QrError>() && args.split {
                 error!("Too much data after encryption, please try a smaller file");
                 std::process::exit(exitcode::DATAERR);
+            } else if error.is::<QrError>() {
+                error!("Too much data after encryption, please try a smaller file or use --split");
+                std::process::exit(exitcode::DATAERR);
             } else {
                 error!("The QR code generation failed for an unknown reason");
                 std::process::exit(exitcode::SOFTWARE);
             }
         }
+    }
+}
+
+/// Verify that the PDF or image restores to the ciphertext and plaintext,
+/// exiting if it doesn't
+fn verify_output(
+    bytes: &[u8],
+    format: Format,
+    encrypted: &str,
+    mode: &EncryptionMode,
+    plaintext: &[u8],
+) {
+    let (kind, result) = match format {
+        Format::Pdf => ("PDF", verify::verify(bytes, encrypted, mode, plaintext)),
+        Format::Png => (
+            "image",
+            verify::verify_image(bytes, encrypted, mode, plaintext),
+        ),
+        Format::Svg => unreachable!("SVG output can't be verified"),
     };
+    debug!("Verifying the {kind}");
+
+    if let Err(error) = result {
+        error!("{error}");
+        error!("The {kind} was not written");
+        std::process::exit(exitcode::SOFTWARE);
+    }
+
+    info!("Verified that the {kind} can be restored");
+}
 
+/// Write the bytes to the output file or to STDOUT
+fn write_output(output: &PathBuf, bytes: &[u8]) -> Result<(), io::Error> {
     if output.to_string_lossy() == "-" {
         debug!("Writing to STDOUT");
-        io::stdout().write_all(&bytes)?;
+        io::stdout().write_all(bytes)?;
     } else {
         debug!("Writing to file: {}", output.to_string_lossy());
         let file = File::create(output)?;
-        BufWriter::new(file).write_all(&bytes)?;
+        BufWriter::new(file).write_all(bytes)?;
     }
 
     Ok(())
 }
 
+/// The output file name, defaulting to out.pdf or the extension of the output
+/// format
+fn output_path(args: &cli::Args) -> PathBuf {
+    args.output
+        .clone()
+        .unwrap_or_else(|| PathBuf::from(format!("out.{}", args.format.extension())))
+}
+
+/// Output file name for the given share, e.g. out-share-1.pdf for out.pdf
+fn share_output_path(output: &Path, index: u8) -> PathBuf {
+    let stem = output.file_stem().unwrap_or_default().to_string_lossy();
+    let mut path = output.with_file_name(format!("{stem}-share-{index}"));
+    if let Some(extension) = output.extension() {
+        path.set_extension(extension);
+    }
+    path
+}
+
+/// Decrypt a PEM encoded ciphertext and write out the plaintext
+fn restore(args: cli::RestoreArgs) -> Result<(), Box<dyn std::error::Error>> {
+    let output = args.output;
+    if output.to_string_lossy() != "-" && output.exists() {
+        if args.force {
+            warn!("Overwriting existing output file: {}", output.display());
+        } else {
+            error!("Output file already exists: {}", output.display());
+            std::process::exit(exitcode::CANTCREAT);
+        }
+    }
+
+    let paths = if args.input.is_empty() {
+        vec![PathBuf::from("-")]
+    } else {
+        args.input
+    };
+    for path in &paths {
+        if path.to_string_lossy() != "-" && !path.is_file() {
+            error!("File not found: {}", path.display());
+            std::process::exit(exitcode::NOINPUT);
+        }
+    }
+
+    let mut ciphertexts = vec![];
+    for path in &paths {
+        let mut reader: BufReader<Box<dyn Read>> = {
+            if path.to_string_lossy() == "-" {
+                if stdin().is_terminal() {
+                    eprintln!("Paste or type the PEM encoded ciphertext, then press Ctrl-D:");
+                }
+                BufReader::new(Box::new(stdin().lock()))
+            } else {
+                BufReader::new(Box::new(File::open(path)?))
+            }
+        };
+
+        let mut bytes = vec![];
+        reader.read_to_end(&mut bytes)?;
+
+        if scan::is_image(&bytes) {
+            debug!("Decoding the QR codes in {}", path.display());
+            match scan::decode_image(&bytes) {
+                Ok(texts) => ciphertexts.push(texts.join("\n")),
+                Err(error) => {
+                    error!("{}: {error}", path.display());
+                    std::process::exit(exitcode::DATAERR);
+                }
+            }
+        } else {
+            ciphertexts.push(String::from_utf8_lossy(&bytes).into_owned());
+        }
+    }
+
+    let passphrase = get_passphrase()?;
+
+    // The pages of a split ciphertext may have been scanned separately
+    let mut plaintexts = vec![];
+    for ciphertext in split::group_parts(ciphertexts) {
+        // The SHA-256 hash matches the fingerprint on the metadata line
+        let decrypted = encryption::armored_ciphertext(&ciphertext).and_then(|armored| {
+            info!(
+                "SHA-256 of the ciphertext: {}",
+                encryption::ciphertext_sha256(&armored)
+            );
+            encryption::decrypt_ciphertext(&mut armored.as_bytes(), passphrase.clone())
+        });
+        let plaintext = match decrypted {
+            Ok(p) => p,
+            Err(PaperAgeError::IncorrectPassphrase) => {
+                error!("Incorrect passphrase, the ciphertext could not be decrypted");
+                std::process::exit(exitcode::NOPERM);
+            }
+            Err(PaperAgeError::InvalidCiphertext(msg)) => {
+                error!("The ciphertext is corrupted or incomplete: {msg}");
+                std::process::exit(exitcode::DATAERR);
+            }
+            Err(error) => {
+                error!("{error}");
+                std::process::exit(exitcode::SOFTWARE);
+            }
+        };
+        plaintexts.push(plaintext);
+    }
+
+    let plaintext = match combine_shares(plaintexts) {
+        Ok(p) => p,
+        Err(error) => {
+            error!("{error}");
+            std::process::exit(exitcode::DATAERR);
+        }
+    };
+
+    info!("Plaintext length: {:?} bytes", plaintext.len());
+
+    if output.to_string_lossy() == "-" {
+        debug!("Writing to STDOUT");
+        io::stdout().write_all(&plaintext)?;
+    } else {
+        debug!("Writing to file: {}", output.to_string_lossy());
+        let file = File::create(output)?;
+        BufWriter::new(file).write_all(&plaintext)?;
+    }
+
+    Ok(())
+}
+
+/// Combine the decrypted shares back into the plaintext. A single plaintext
+/// that isn't a complete set of shares is returned as is, even if it starts
+/// like a share.
+fn combine_shares(mut plaintexts: Vec<Vec<u8>>) -> Result<Vec<u8>, PaperAgeError> {
+    if plaintexts.len() == 1 {
+        let combined = shamir::Share::from_bytes(&plaintexts[0])
+            .map(|share| share.and_then(|share| shamir::combine(&[share])));
+        return match combined {
+            Some(Ok(plaintext)) => Ok(plaintext),
+            Some(Err(error)) => {
+                warn!("The plaintext starts like a share but isn't a complete set of shares ({error}), restoring it as is");
+                Ok(plaintexts.remove(0))
+            }
+            None => Ok(plaintexts.remove(0)),
+        };
+    }
+
+    let shares = plaintexts
+        .iter()
+        .filter_map(|p| shamir::Share::from_bytes(p))
+        .collect::<Result<Vec<_>, _>>()?;
+    if shares.len() != plaintexts.len() {
+        return Err(PaperAgeError::InvalidShares(
+            "several ciphertexts were given but not all of them are shares".to_string(),
+        ));
+    }
+
+    info!(
+        "Combining {} share(s) of set {}",
+        shares.len(),
+        shares[0].set_id
+    );
+    shamir::combine(&shares)
+}
+
 /// Read a secret from the user
 pub fn read_secret(prompt: &str) -> Result<SecretString, io::Error> {
     let passphrase = prompt_password(format!("{}: ", prompt)).map(SecretString::from)?;
@@ -137,6 +795,84 @@ fn get_passphrase() -> Result<SecretString, io::Error> {
     }
 }
 
+/// Read the passphrase of a batch sheet from an environment variable or the
+/// first line of a file
+fn read_passphrase(source: &batch::PassphraseSource) -> Result<SecretString, io::Error> {
+    let passphrase = match source {
+        batch::PassphraseSource::Shared => return get_passphrase(),
+        batch::PassphraseSource::Env(name) => env::var(name)
+            .map_err(|e| io::Error::new(io::ErrorKind::NotFound, format!("{name}: {e}")))?,
+        batch::PassphraseSource::File(path) => {
+            let text = std::fs::read_to_string(path)
+                .map_err(|e| io::Error::new(e.kind(), format!("{}: {e}", path.display())))?;
+            text.lines().next().unwrap_or_default().to_string()
+        }
+    };
+
+    if passphrase.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Passphrase can't be empty",
+        ));
+    }
+
+    Ok(SecretString::from(passphrase))
+}
+
+/// Collect the recipients from the command line arguments and recipients files
+fn get_recipients(
+    recipients: &[String],
+    ssh_recipients: &[String],
+    files: &[PathBuf],
+) -> Result<Vec<Recipient>, PaperAgeError> {
+    let mut parsed = recipients
+        .iter()
+        .map(|r| encryption::parse_recipient(r))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    for ssh_recipient in ssh_recipients {
+        let path = PathBuf::from(ssh_recipient);
+        if path.is_file() {
+            let keys = read_recipients_file(&path)?;
+            if let Some(key) = keys.iter().find(|k| matches!(k, Recipient::X25519(_))) {
+                return Err(PaperAgeError::InvalidRecipient(format!(
+                    "{key}: not an SSH public key"
+                )));
+            }
+            parsed.extend(keys);
+        } else {
+            parsed.push(encryption::parse_ssh_recipient(ssh_recipient)?);
+        }
+    }
+
+    for path in files {
+        parsed.extend(read_recipients_file(path)?);
+    }
+
+    // The same key could be listed in several places
+    let mut fingerprints = HashSet::new();
+    parsed.retain(|recipient| fingerprints.insert(recipient.fingerprint()));
+
+    info!("Encrypting to {} recipient(s)", parsed.len());
+    for recipient in &parsed {
+        info!(
+            "Recipient: {} {}",
+            recipient.key_type(),
+            recipient.fingerprint()
+        );
+    }
+
+    Ok(parsed)
+}
+
+/// Read the recipients from a recipients or authorized_keys file
+fn read_recipients_file(path: &PathBuf) -> Result<Vec<Recipient>, PaperAgeError> {
+    let file = File::open(path)
+        .map_err(|e| PaperAgeError::InvalidRecipient(format!("{}: {e}", path.display())))?;
+
+    encryption::read_recipients(&mut BufReader::new(file))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -156,4 +892,50 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_output_path() {
+        let args = cli::Args::parse_from(["paper-age", "--format", "svg"]);
+        assert_eq!(output_path(&args), PathBuf::from("out.svg"));
+
+        let args = cli::Args::parse_from(["paper-age", "--format", "svg", "-o", "sheet.svg"]);
+        assert_eq!(output_path(&args), PathBuf::from("sheet.svg"));
+
+        let args = cli::Args::parse_from(["paper-age"]);
+        assert_eq!(output_path(&args), PathBuf::from("out.pdf"));
+    }
+
+    #[test]
+    fn test_share_output_path() {
+        assert_eq!(
+            share_output_path(Path::new("backups/out.pdf"), 2),
+            PathBuf::from("backups/out-share-2.pdf")
+        );
+        assert_eq!(
+            share_output_path(Path::new("out"), 1),
+            PathBuf::from("out-share-1")
+        );
+    }
+
+    #[test]
+    fn test_combine_shares() {
+        let shares = shamir::split(b"secret", 2, 3, &mut rand::thread_rng()).unwrap();
+        let plaintexts = vec![shares[0].to_bytes(), shares[2].to_bytes()];
+        assert_eq!(combine_shares(plaintexts).unwrap(), b"secret");
+
+        assert_eq!(combine_shares(vec![b"plain".to_vec()]).unwrap(), b"plain");
+        assert!(combine_shares(vec![b"plain".to_vec(), shares[1].to_bytes()]).is_err());
+    }
+
+    #[test]
+    fn test_combine_shares_plaintext_like_a_share() {
+        // An ordinary backup that happens to start with the share prefix
+        let plaintext = b"PAPERAGE SHARE notes for the shares of the vault\n".to_vec();
+        assert_eq!(combine_shares(vec![plaintext.clone()]).unwrap(), plaintext);
+
+        // A single share of a 2-of-3 set isn't enough to combine
+        let shares = shamir::split(b"secret", 2, 3, &mut rand::thread_rng()).unwrap();
+        let share = shares[0].to_bytes();
+        assert_eq!(combine_shares(vec![share.clone()]).unwrap(), share);
+    }
 }
@@ -0,0 +1,112 @@
+//! Metadata line below the title
+use std::fmt;
+
+/// A field of the metadata line
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum MetadataField {
+    /// Creation date and time in UTC
+    Date,
+    /// PaperAge version
+    Version,
+    /// SHA-256 fingerprint of the PEM encoded ciphertext
+    Sha256,
+    /// Random identifier of the sheet
+    SheetId,
+}
+
+impl MetadataField {
+    /// All the fields, in the order they are printed
+    pub const ALL: [MetadataField; 4] = [
+        MetadataField::Date,
+        MetadataField::Version,
+        MetadataField::Sha256,
+        MetadataField::SheetId,
+    ];
+}
+
+impl fmt::Display for MetadataField {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            MetadataField::Date => write!(f, "date"),
+            MetadataField::Version => write!(f, "version"),
+            MetadataField::Sha256 => write!(f, "sha256"),
+            MetadataField::SheetId => write!(f, "sheet-id"),
+        }
+    }
+}
+
+/// The fields to print on the metadata line. Nothing is printed by default.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Metadata {
+    /// Creation date and time in UTC
+    pub date: bool,
+
+    /// PaperAge version
+    pub version: bool,
+
+    /// SHA-256 fingerprint of the PEM encoded ciphertext
+    pub sha256: bool,
+
+    /// Random identifier of the sheet
+    pub sheet_id: bool,
+}
+
+impl Metadata {
+    /// Every field
+    pub fn all() -> Metadata {
+        Metadata::from_fields(&MetadataField::ALL)
+    }
+
+    /// The given fields
+    pub fn from_fields(fields: &[MetadataField]) -> Metadata {
+        Metadata {
+            date: fields.contains(&MetadataField::Date),
+            version: fields.contains(&MetadataField::Version),
+            sha256: fields.contains(&MetadataField::Sha256),
+            sheet_id: fields.contains(&MetadataField::SheetId),
+        }
+    }
+
+    /// Whether the field is printed
+    pub fn contains(&self, field: MetadataField) -> bool {
+        match field {
+            MetadataField::Date => self.date,
+            MetadataField::Version => self.version,
+            MetadataField::Sha256 => self.sha256,
+            MetadataField::SheetId => self.sheet_id,
+        }
+    }
+
+    /// Whether no field is printed
+    pub fn is_empty(&self) -> bool {
+        *self == Metadata::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_fields() {
+        assert!(Metadata::default().is_empty());
+        assert!(Metadata::from_fields(&[]).is_empty());
+
+        let metadata = Metadata::from_fields(&[MetadataField::Sha256, MetadataField::SheetId]);
+        assert!(!metadata.is_empty());
+        assert!(!metadata.date);
+        assert!(!metadata.version);
+        assert!(metadata.contains(MetadataField::Sha256));
+        assert!(metadata.contains(MetadataField::SheetId));
+
+        let all = Metadata::all();
+        assert!(MetadataField::ALL.iter().all(|field| all.contains(*field)));
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(MetadataField::Date.to_string(), "date");
+        assert_eq!(MetadataField::SheetId.to_string(), "sheet-id");
+    }
+}
@@ -1,7 +1,8 @@
 //! Page size and dimensions
 use std::fmt;
+use std::str::FromStr;
 
-use printpdf::{Mm, Point};
+use printpdf::{Mm, Point, Pt};
 
 /// PDF dimensions
 #[derive(Clone, Copy, Debug)]
@@ -52,6 +53,62 @@ impl PageDimensions {
     pub fn bottom_right(&self) -> Point {
         Point::new(self.width - self.margin, self.margin)
     }
+
+    /// Width of the page between the margins
+    pub fn inner_width(&self) -> Mm {
+        self.width - self.margin * 2.0
+    }
+
+    /// Baseline of a line of text of the given size at the top of the page
+    pub fn title_baseline(&self, font_size: Pt) -> Mm {
+        self.height - self.margin - Mm::from(font_size)
+    }
+
+    /// Factor for scaling the font sizes down on pages that are narrower than
+    /// A4 or shorter than letter. Larger pages use the full font sizes.
+    pub fn font_scale(&self) -> f32 {
+        let width = self.inner_width().0 / FULL_SCALE_INNER_WIDTH.0;
+        let height = self.height.0 / FULL_SCALE_HEIGHT.0;
+
+        width.min(height).min(1.0)
+    }
+}
+
+/// Width between the margins of the narrowest page with full size fonts (A4)
+const FULL_SCALE_INNER_WIDTH: Mm = Mm(190.0);
+
+/// Height of the shortest page with full size fonts (letter)
+const FULL_SCALE_HEIGHT: Mm = Mm(279.4);
+
+/// Margin as a fraction of the shorter side of the page
+const MARGIN_RATIO: f32 = 0.05;
+
+/// Bounds for the page margin
+const MIN_MARGIN: Mm = Mm(5.0);
+const MAX_MARGIN: Mm = Mm(10.0);
+
+/// Space between the QR code and the divider for the notes field
+pub const NOTES_FIELD_HEIGHT: Mm = Mm(18.5);
+
+/// Space for the title, the part labels, and the notes field around the QR
+/// codes of a split ciphertext
+pub const PART_LABELS_HEIGHT: Mm = Mm(30.0);
+
+/// Bounds for the sides of custom page sizes
+const MIN_CUSTOM_SIDE: Mm = Mm(100.0);
+const MAX_CUSTOM_SIDE: Mm = Mm(1000.0);
+
+/// Page dimensions with a margin derived from the size of the page
+fn dimensions_for(width: Mm, height: Mm) -> PageDimensions {
+    let margin = Mm(width.0.min(height.0) * MARGIN_RATIO)
+        .max(MIN_MARGIN)
+        .min(MAX_MARGIN);
+
+    PageDimensions {
+        width,
+        height,
+        margin,
+    }
 }
 
 /// A4 dimensions with a 10mm margin
@@ -75,40 +132,180 @@ impl Default for PageDimensions {
     }
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+/// Page orientation
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "cli", derive(clap::ValueEnum))]
+pub enum Orientation {
+    /// QR code in the top half and the PEM text in the bottom half
+    #[default]
+    Portrait,
+    /// QR code in the left half and the PEM text in the right half
+    Landscape,
+}
+
+impl fmt::Display for Orientation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Orientation::Portrait => write!(f, "portrait"),
+            Orientation::Landscape => write!(f, "landscape"),
+        }
+    }
+}
+
+/// Paper size
+#[derive(Clone, Debug, PartialEq)]
 pub enum PageSize {
+    /// ISO A3, 297 × 420 mm
+    A3,
+    /// ISO A4, 210 × 297 mm
     A4,
+    /// ISO A5, 148 × 210 mm
+    A5,
+    /// ISO B5, 176 × 250 mm
+    B5,
+    /// US letter, 8.5 × 11 in
     Letter,
+    /// US legal, 8.5 × 14 in
+    Legal,
+    /// US executive, 7.25 × 10.5 in
+    Executive,
+    /// 5 × 8 in index card
+    IndexCard,
+    /// Custom width and height
+    Custom { width: Mm, height: Mm },
 }
 
 impl PageSize {
+    /// Names of the built-in page sizes
+    pub const NAMES: [&'static str; 8] = [
+        "a3",
+        "a4",
+        "a5",
+        "b5",
+        "letter",
+        "legal",
+        "executive",
+        "index-card",
+    ];
+
     /// Page dimensions
     pub fn dimensions(&self) -> PageDimensions {
         match self {
             PageSize::A4 => A4_PAGE,
             PageSize::Letter => LETTER_PAGE,
+            PageSize::A3 => dimensions_for(Mm(297.0), Mm(420.0)),
+            PageSize::A5 => dimensions_for(Mm(148.0), Mm(210.0)),
+            PageSize::B5 => dimensions_for(Mm(176.0), Mm(250.0)),
+            PageSize::Legal => dimensions_for(Mm(215.9), Mm(355.6)),
+            PageSize::Executive => dimensions_for(Mm(184.15), Mm(266.7)),
+            PageSize::IndexCard => dimensions_for(Mm(127.0), Mm(203.2)),
+            PageSize::Custom { width, height } => dimensions_for(*width, *height),
         }
     }
 
-    /// QR code size for the page size
-    pub fn qrcode_size(&self) -> Mm {
-        match self {
-            PageSize::A4 => Mm(110.0),
-            PageSize::Letter => Mm(102.0),
+    /// Page dimensions in the given orientation
+    pub fn dimensions_in(&self, orientation: Orientation) -> PageDimensions {
+        let dimensions = self.dimensions();
+        match orientation {
+            Orientation::Portrait => dimensions,
+            Orientation::Landscape => PageDimensions {
+                width: dimensions.height,
+                height: dimensions.width,
+                margin: dimensions.margin,
+            },
         }
     }
 
+    /// QR code size for the page size: as large as fits between the margins
+    /// and between the title and the notes field in the top half of the page
+    pub fn qrcode_size(&self) -> Mm {
+        let dimensions = self.dimensions();
+        let height = dimensions.height / 2.0 - dimensions.margin * 2.0 - NOTES_FIELD_HEIGHT;
+
+        dimensions.inner_width().min(height)
+    }
+
     /// The left edge of the QR code on the page
     pub fn qrcode_left_edge(&self) -> Mm {
         (self.dimensions().width - self.qrcode_size()) / 2.0
     }
+
+    /// The bottom edge of the QR code on the page
+    pub fn qrcode_bottom_edge(&self) -> Mm {
+        self.dimensions().height - self.qrcode_size() - self.dimensions().margin * 2.0
+    }
 }
 
 impl fmt::Display for PageSize {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        write!(f, "{}", format!("{self:?}").to_lowercase())
+        match self {
+            PageSize::A3 => write!(f, "a3"),
+            PageSize::A4 => write!(f, "a4"),
+            PageSize::A5 => write!(f, "a5"),
+            PageSize::B5 => write!(f, "b5"),
+            PageSize::Letter => write!(f, "letter"),
+            PageSize::Legal => write!(f, "legal"),
+            PageSize::Executive => write!(f, "executive"),
+            PageSize::IndexCard => write!(f, "index-card"),
+            PageSize::Custom { width, height } => write!(f, "custom:{}x{}mm", width.0, height.0),
+        }
+    }
+}
+
+/// Parse one of the built-in page sizes, e.g. `a5`, or a custom size in
+/// millimetres, e.g. `custom:120x180mm`
+impl FromStr for PageSize {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+
+        if let Some(size) = s.strip_prefix("custom:") {
+            return parse_custom(size);
+        }
+
+        match s.as_str() {
+            "a3" => Ok(PageSize::A3),
+            "a4" => Ok(PageSize::A4),
+            "a5" => Ok(PageSize::A5),
+            "b5" => Ok(PageSize::B5),
+            "letter" => Ok(PageSize::Letter),
+            "legal" => Ok(PageSize::Legal),
+            "executive" => Ok(PageSize::Executive),
+            "index-card" => Ok(PageSize::IndexCard),
+            _ => Err(format!(
+                "unknown page size '{s}', expected one of {} or custom:WIDTHxHEIGHTmm",
+                PageSize::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Parse the `WIDTHxHEIGHTmm` part of a custom page size
+fn parse_custom(size: &str) -> Result<PageSize, String> {
+    let invalid = || format!("invalid custom page size '{size}', expected e.g. custom:120x180mm");
+
+    let (width, height) = size
+        .strip_suffix("mm")
+        .unwrap_or(size)
+        .split_once('x')
+        .ok_or_else(invalid)?;
+    let width: f32 = width.trim().parse().map_err(|_| invalid())?;
+    let height: f32 = height.trim().parse().map_err(|_| invalid())?;
+
+    for side in [width, height] {
+        if !(MIN_CUSTOM_SIDE.0..=MAX_CUSTOM_SIDE.0).contains(&side) {
+            return Err(format!(
+                "custom page sides must be between {} and {} mm",
+                MIN_CUSTOM_SIDE.0, MAX_CUSTOM_SIDE.0
+            ));
+        }
     }
+
+    Ok(PageSize::Custom {
+        width: Mm(width),
+        height: Mm(height),
+    })
 }
 
 #[cfg(test)]
@@ -160,6 +357,76 @@ mod tests {
         );
     }
 
+    #[test]
+    fn page_dimensions_text_baselines() {
+        assert_eq!(TEST_DIMENSIONS.inner_width(), Mm(80.0));
+        assert_eq!(TEST_DIMENSIONS.title_baseline(Pt(0.0)), Mm(190.0));
+    }
+
+    #[test]
+    fn page_size_qrcode_edges() {
+        assert_eq!(PageSize::A4.qrcode_left_edge(), Mm(50.0));
+        assert_eq!(PageSize::A4.qrcode_bottom_edge(), Mm(167.0));
+    }
+
+    #[test]
+    fn page_dimensions_font_scale() {
+        assert_eq!(A4_PAGE.font_scale(), 1.0);
+        assert_eq!(LETTER_PAGE.font_scale(), 1.0);
+        assert_eq!(PageSize::A3.dimensions().font_scale(), 1.0);
+        assert!((PageSize::A5.dimensions().font_scale() - 0.7).abs() < 0.01);
+    }
+
+    #[test]
+    fn page_size_derived_dimensions() {
+        assert_eq!(PageSize::A4.qrcode_size(), Mm(110.0));
+        assert_eq!(PageSize::A5.dimensions().margin, Mm(7.4));
+        assert_eq!(PageSize::A3.dimensions().margin, Mm(10.0));
+
+        for size in PageSize::NAMES {
+            let size: PageSize = size.parse().unwrap();
+            let dimensions = size.dimensions();
+            assert!(size.qrcode_size() > Mm(0.0), "{size}");
+            assert!(size.qrcode_size() <= dimensions.inner_width(), "{size}");
+            assert!(
+                size.qrcode_bottom_edge() > dimensions.height / 2.0,
+                "{size}"
+            );
+        }
+    }
+
+    #[test]
+    fn page_size_dimensions_in() {
+        assert_eq!(PageSize::A4.dimensions_in(Orientation::Portrait), A4_PAGE);
+
+        let landscape = PageSize::A4.dimensions_in(Orientation::Landscape);
+        assert_eq!(landscape.width, Mm(297.0));
+        assert_eq!(landscape.height, Mm(210.0));
+        assert_eq!(landscape.margin, Mm(10.0));
+    }
+
+    #[test]
+    fn page_size_from_str() {
+        for size in PageSize::NAMES {
+            assert_eq!(size.parse::<PageSize>().unwrap().to_string(), size);
+        }
+        assert_eq!("A5".parse::<PageSize>(), Ok(PageSize::A5));
+        assert_eq!(
+            "custom:120x180mm".parse::<PageSize>(),
+            Ok(PageSize::Custom {
+                width: Mm(120.0),
+                height: Mm(180.0)
+            })
+        );
+        assert_eq!(
+            "custom:120.5x180".parse::<PageSize>().unwrap().to_string(),
+            "custom:120.5x180mm"
+        );
+        assert!("a6".parse::<PageSize>().is_err());
+        assert!("custom:120mm".parse::<PageSize>().is_err());
+        assert!("custom:50x180mm".parse::<PageSize>().is_err());
+    }
+
     #[test]
     fn page_dimensions_bottom_right() {
         assert_eq!(
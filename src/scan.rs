@@ -0,0 +1,161 @@
+//! Decoding the QR codes in scans and photos of PaperAge sheets
+//!
+//! PNG, JPEG, and PPM/PGM images are supported. Everything happens locally,
+//! so a backup can be restored on an offline machine.
+use image::{imageops::FilterType, GenericImageView, ImageFormat};
+use log::{debug, info, warn};
+
+use crate::convenience::PaperAgeError;
+
+/// Images larger than this (in pixels, on the longest side) are scaled down
+/// before looking for QR codes. Even a full page QR code has a few pixels per
+/// module at this size.
+const MAX_IMAGE_SIZE: u32 = 3000;
+
+/// Whether the bytes look like a supported image rather than text
+pub fn is_image(bytes: &[u8]) -> bool {
+    matches!(
+        image::guess_format(bytes),
+        Ok(ImageFormat::Png | ImageFormat::Jpeg | ImageFormat::Pnm)
+    )
+}
+
+/// Find and decode the QR codes in the image, from the top left
+pub fn decode_image(bytes: &[u8]) -> Result<Vec<String>, PaperAgeError> {
+    let image = image::load_from_memory(bytes)
+        .map_err(|e| PaperAgeError::InvalidCiphertext(format!("the image can't be read: {e}")))?;
+
+    let (width, height) = image.dimensions();
+    debug!("Image size: {width}×{height}");
+
+    let image = if width.max(height) > MAX_IMAGE_SIZE {
+        debug!("Scaling the image down to {MAX_IMAGE_SIZE} pixels");
+        image.resize(MAX_IMAGE_SIZE, MAX_IMAGE_SIZE, FilterType::Triangle)
+    } else {
+        image
+    };
+    let luma = image.to_luma8();
+
+    let mut prepared = rqrr::PreparedImage::prepare_from_greyscale(
+        luma.width() as usize,
+        luma.height() as usize,
+        |x, y| luma.get_pixel(x as u32, y as u32).0[0],
+    );
+
+    let mut grids = prepared.detect_grids();
+    info!("Found {} QR code(s) in the image", grids.len());
+
+    // Top to bottom, then left to right
+    grids.sort_by_key(|grid| (grid.bounds[0].y / 100, grid.bounds[0].x));
+
+    let mut decoded = vec![];
+    let mut last_error = None;
+    for grid in grids {
+        match grid.decode() {
+            Ok((meta, text)) => {
+                debug!("Decoded a version {} QR code", meta.version.0);
+                decoded.push(text);
+            }
+            Err(error) => {
+                warn!("A QR code in the image could not be decoded: {error}");
+                last_error = Some(error);
+            }
+        }
+    }
+
+    if decoded.is_empty() {
+        let msg = match last_error {
+            Some(error) => format!("the QR code in the image could not be decoded: {error}"),
+            None => "no QR codes found in the image".to_string(),
+        };
+        return Err(PaperAgeError::InvalidCiphertext(msg));
+    }
+
+    Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{GrayImage, Luma};
+    use qrcode::{types::Color, QrCode};
+    use std::io::Cursor;
+
+    /// Render the text as a QR code with a quiet zone, `scale` pixels per module
+    fn qr_image(text: &str, scale: u32) -> GrayImage {
+        let code = QrCode::new(text).unwrap();
+        let modules = code.width() as u32;
+        let colors = code.to_colors();
+        let size = (modules + 8) * scale;
+
+        GrayImage::from_fn(size, size, |x, y| {
+            let (col, row) = (x / scale, y / scale);
+            let dark = (4..modules + 4).contains(&col)
+                && (4..modules + 4).contains(&row)
+                && colors[((row - 4) * modules + col - 4) as usize] == Color::Dark;
+            Luma([if dark { 0 } else { 255 }])
+        })
+    }
+
+    fn encode(image: &GrayImage, format: ImageFormat) -> Vec<u8> {
+        let mut bytes = vec![];
+        image
+            .write_to(&mut Cursor::new(&mut bytes), format)
+            .unwrap();
+        bytes
+    }
+
+    #[test]
+    fn test_decode_png() {
+        let bytes = encode(&qr_image("Hello, PaperAge!", 4), ImageFormat::Png);
+
+        assert!(is_image(&bytes));
+        assert_eq!(decode_image(&bytes).unwrap(), vec!["Hello, PaperAge!"]);
+    }
+
+    #[test]
+    fn test_decode_jpeg() {
+        let bytes = encode(&qr_image("Hello, PaperAge!", 6), ImageFormat::Jpeg);
+
+        assert!(is_image(&bytes));
+        assert_eq!(decode_image(&bytes).unwrap(), vec!["Hello, PaperAge!"]);
+    }
+
+    #[test]
+    fn test_decode_ppm() {
+        let image = image::DynamicImage::ImageLuma8(qr_image("Hello, PaperAge!", 4)).to_rgb8();
+        let mut bytes = vec![];
+        image
+            .write_to(&mut Cursor::new(&mut bytes), ImageFormat::Pnm)
+            .unwrap();
+
+        assert!(is_image(&bytes));
+        assert_eq!(decode_image(&bytes).unwrap(), vec!["Hello, PaperAge!"]);
+    }
+
+    #[test]
+    fn test_decode_ciphertext() {
+        let (_, encrypted) =
+            crate::encryption::encrypt(&mut &b"Hello"[..], &"snakeoil".into()).unwrap();
+        let bytes = encode(&qr_image(&encrypted, 4), ImageFormat::Png);
+
+        assert_eq!(decode_image(&bytes).unwrap(), vec![encrypted]);
+    }
+
+    #[test]
+    fn test_no_qr_code() {
+        let bytes = encode(&GrayImage::new(100, 100), ImageFormat::Png);
+
+        let error = decode_image(&bytes).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid ciphertext: no QR codes found in the image"
+        );
+    }
+
+    #[test]
+    fn test_is_image() {
+        assert!(!is_image(b"-----BEGIN AGE ENCRYPTED FILE-----"));
+        assert!(!is_image(b""));
+    }
+}
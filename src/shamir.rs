@@ -0,0 +1,319 @@
+//! Shamir's secret sharing over GF(256)
+//!
+//! The plaintext is split into N shares so that any K of them can be combined
+//! to recover it, while fewer than K shares reveal nothing about it. Each share
+//! is encrypted and printed on its own sheet.
+//!
+//! A share starts with a header line such as
+//! `PAPERAGE SHARE 2/5 3 1a2b3c4d`, i.e. the share index, the number of
+//! shares, the threshold, and an identifier common to all shares of the set,
+//! followed by the share bytes.
+use rand::{CryptoRng, Rng, RngCore};
+
+use crate::convenience::PaperAgeError;
+use crate::lang::{self, Language};
+
+/// Prefix of the header line of each share
+pub const SHARE_PREFIX: &str = "PAPERAGE SHARE";
+
+/// A single share of a secret
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Share {
+    /// Index of the share, starting from 1. Also the x coordinate of the share.
+    pub index: u8,
+    /// Total number of shares in the set
+    pub count: u8,
+    /// Number of shares needed to recover the secret
+    pub threshold: u8,
+    /// Identifier common to all shares of the set
+    pub set_id: String,
+    /// The share bytes, one for each byte of the secret
+    pub data: Vec<u8>,
+}
+
+impl Share {
+    /// Serialize the share with its header line
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = format!(
+            "{SHARE_PREFIX} {}/{} {} {}\n",
+            self.index, self.count, self.threshold, self.set_id
+        )
+        .into_bytes();
+        bytes.extend_from_slice(&self.data);
+        bytes
+    }
+
+    /// Parse a serialized share, or return `None` if the bytes aren't a share
+    pub fn from_bytes(bytes: &[u8]) -> Option<Result<Share, PaperAgeError>> {
+        if !bytes.starts_with(SHARE_PREFIX.as_bytes()) {
+            return None;
+        }
+
+        Some(Share::parse(bytes))
+    }
+
+    /// Human readable label for the sheet of this share
+    pub fn label(&self) -> String {
+        self.label_in(Language::En)
+    }
+
+    /// Human readable label for this share in the language
+    pub fn label_in(&self, language: Language) -> String {
+        lang::fill(
+            language.catalog().share_label,
+            &[
+                ("index", &self.index),
+                ("count", &self.count),
+                ("threshold", &self.threshold),
+                ("set", &self.set_id),
+            ],
+        )
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Share, PaperAgeError> {
+        let newline = bytes.iter().position(|&b| b == b'\n').ok_or_else(|| {
+            PaperAgeError::InvalidShares("the share header is incomplete".to_string())
+        })?;
+        let header = String::from_utf8_lossy(&bytes[SHARE_PREFIX.len()..newline]);
+        let invalid = || PaperAgeError::InvalidShares(format!("invalid share header:{header}"));
+
+        let words: Vec<&str> = header.split_whitespace().collect();
+        let [position, threshold, set_id] = words[..] else {
+            return Err(invalid());
+        };
+        let (index, count) = position.split_once('/').ok_or_else(invalid)?;
+        let index: u8 = index.parse().map_err(|_| invalid())?;
+        let count: u8 = count.parse().map_err(|_| invalid())?;
+        let threshold: u8 = threshold.parse().map_err(|_| invalid())?;
+
+        if index == 0 || index > count || threshold == 0 || threshold > count {
+            return Err(invalid());
+        }
+
+        Ok(Share {
+            index,
+            count,
+            threshold,
+            set_id: set_id.to_string(),
+            data: bytes[newline + 1..].to_vec(),
+        })
+    }
+}
+
+/// Split the secret into `count` shares, any `threshold` of which can recover it
+pub fn split<R: RngCore + CryptoRng>(
+    secret: &[u8],
+    threshold: u8,
+    count: u8,
+    rng: &mut R,
+) -> Result<Vec<Share>, PaperAgeError> {
+    if threshold < 2 {
+        return Err(PaperAgeError::InvalidShares(
+            "the threshold must be at least 2".to_string(),
+        ));
+    }
+    if threshold > count {
+        return Err(PaperAgeError::InvalidShares(format!(
+            "the threshold ({threshold}) can't be larger than the number of shares ({count})"
+        )));
+    }
+
+    let set_id: String = (0..4).map(|_| format!("{:02x}", rng.gen::<u8>())).collect();
+
+    let mut shares: Vec<Share> = (1..=count)
+        .map(|index| Share {
+            index,
+            count,
+            threshold,
+            set_id: set_id.clone(),
+            data: Vec::with_capacity(secret.len()),
+        })
+        .collect();
+
+    // A random polynomial of degree threshold - 1 for each byte of the secret,
+    // with the secret byte as the constant term
+    let mut coefficients = vec![0u8; threshold as usize];
+    for &byte in secret {
+        coefficients[0] = byte;
+        rng.fill(&mut coefficients[1..]);
+
+        for share in shares.iter_mut() {
+            share.data.push(evaluate(&coefficients, share.index));
+        }
+    }
+
+    coefficients.fill(0);
+
+    Ok(shares)
+}
+
+/// Combine the shares back into the secret
+pub fn combine(shares: &[Share]) -> Result<Vec<u8>, PaperAgeError> {
+    let first = shares
+        .first()
+        .ok_or_else(|| PaperAgeError::InvalidShares("no shares given".to_string()))?;
+
+    let mut unique: Vec<&Share> = vec![];
+    for share in shares {
+        if share.set_id != first.set_id || share.threshold != first.threshold {
+            return Err(PaperAgeError::InvalidShares(format!(
+                "share {} is from set {}, not from set {}",
+                share.index, share.set_id, first.set_id
+            )));
+        }
+        if share.data.len() != first.data.len() {
+            return Err(PaperAgeError::InvalidShares(format!(
+                "share {} has a different length than the other shares",
+                share.index
+            )));
+        }
+        // The same share may have been given twice
+        if !unique.iter().any(|s| s.index == share.index) {
+            unique.push(share);
+        }
+    }
+
+    if unique.len() < first.threshold as usize {
+        return Err(PaperAgeError::InvalidShares(format!(
+            "{} share(s) given but {} are needed",
+            unique.len(),
+            first.threshold
+        )));
+    }
+
+    let xs: Vec<u8> = unique.iter().map(|s| s.index).collect();
+    let weights: Vec<u8> = xs
+        .iter()
+        .enumerate()
+        .map(|(i, &xi)| lagrange_weight(&xs, i, xi))
+        .collect();
+
+    let secret = (0..first.data.len())
+        .map(|n| {
+            unique
+                .iter()
+                .zip(&weights)
+                .fold(0, |acc, (share, &weight)| acc ^ mul(share.data[n], weight))
+        })
+        .collect();
+
+    Ok(secret)
+}
+
+/// Lagrange basis polynomial of the i-th share evaluated at x = 0
+fn lagrange_weight(xs: &[u8], i: usize, xi: u8) -> u8 {
+    xs.iter()
+        .enumerate()
+        .filter(|&(j, _)| j != i)
+        // In GF(256) subtraction is XOR, so (0 - xj) / (xi - xj) = xj / (xi ^ xj)
+        .fold(1, |acc, (_, &xj)| mul(acc, div(xj, xi ^ xj)))
+}
+
+/// Evaluate the polynomial at x with Horner's method
+fn evaluate(coefficients: &[u8], x: u8) -> u8 {
+    coefficients
+        .iter()
+        .rev()
+        .fold(0, |acc, &coefficient| mul(acc, x) ^ coefficient)
+}
+
+/// Multiplication in GF(256) with the AES polynomial x^8 + x^4 + x^3 + x + 1,
+/// without data dependent branches
+fn mul(mut a: u8, mut b: u8) -> u8 {
+    let mut product = 0;
+    for _ in 0..8 {
+        product ^= a & (b & 1).wrapping_neg();
+        let carry = (a >> 7).wrapping_neg();
+        a = (a << 1) ^ (carry & 0x1b);
+        b >>= 1;
+    }
+    product
+}
+
+/// Division in GF(256), where the inverse is a^254
+fn div(a: u8, b: u8) -> u8 {
+    let mut inverse = 1;
+    let mut power = b;
+    for _ in 0..7 {
+        power = mul(power, power);
+        inverse = mul(inverse, power);
+    }
+    mul(a, inverse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gf256_arithmetic() {
+        assert_eq!(mul(0x53, 0xca), 0x01);
+        assert_eq!(mul(0x57, 0x83), 0xc1);
+        for a in 1..=255 {
+            assert_eq!(mul(div(1, a), a), 1);
+        }
+    }
+
+    #[test]
+    fn test_split_and_combine() {
+        let secret = b"correct horse battery staple";
+        let shares = split(secret, 3, 5, &mut rand::thread_rng()).unwrap();
+
+        assert_eq!(shares.len(), 5);
+        assert!(shares.iter().all(|s| s.set_id == shares[0].set_id));
+        assert!(shares.iter().all(|s| s.data.len() == secret.len()));
+
+        for subset in [[0, 1, 2], [4, 2, 0], [1, 3, 4]] {
+            let subset: Vec<Share> = subset.iter().map(|&i| shares[i].clone()).collect();
+            assert_eq!(combine(&subset).unwrap(), secret);
+        }
+
+        assert_eq!(combine(&shares).unwrap(), secret);
+    }
+
+    #[test]
+    fn test_combine_too_few_shares() {
+        let shares = split(b"secret", 3, 5, &mut rand::thread_rng()).unwrap();
+        let error = combine(&[shares[0].clone(), shares[1].clone(), shares[1].clone()]);
+
+        assert_eq!(
+            error.unwrap_err().to_string(),
+            "Invalid shares: 2 share(s) given but 3 are needed"
+        );
+    }
+
+    #[test]
+    fn test_combine_mixed_sets() {
+        let first = split(b"secret", 2, 3, &mut rand::thread_rng()).unwrap();
+        let mut second = split(b"secret", 2, 3, &mut rand::thread_rng()).unwrap();
+        second[1].set_id = String::from("00000000");
+
+        assert!(combine(&[first[0].clone(), second[1].clone()]).is_err());
+    }
+
+    #[test]
+    fn test_split_invalid_threshold() {
+        assert!(split(b"secret", 1, 3, &mut rand::thread_rng()).is_err());
+        assert!(split(b"secret", 4, 3, &mut rand::thread_rng()).is_err());
+    }
+
+    #[test]
+    fn test_share_bytes() {
+        let share = Share {
+            index: 2,
+            count: 5,
+            threshold: 3,
+            set_id: String::from("1a2b3c4d"),
+            data: vec![0, 10, 255],
+        };
+        let bytes = share.to_bytes();
+
+        assert!(bytes.starts_with(b"PAPERAGE SHARE 2/5 3 1a2b3c4d\n"));
+        assert_eq!(Share::from_bytes(&bytes).unwrap().unwrap(), share);
+        assert!(Share::from_bytes(b"plain text").is_none());
+        assert!(Share::from_bytes(b"PAPERAGE SHARE 6/5 3 x\n")
+            .unwrap()
+            .is_err());
+        assert_eq!(share.label(), "Share 2 of 5 · 3 needed · Set 1a2b3c4d");
+    }
+}
@@ -0,0 +1,310 @@
+//! Splitting large ciphertexts across several QR codes
+//!
+//! Each part is prefixed with a header line such as
+//! `PAPERAGE PART 1/3 1a2b3c4d`, where the last field is the start of the
+//! SHA-256 hash of the whole PEM encoded ciphertext. The parts can be scanned
+//! in any order and joined back together with [`join`].
+use std::collections::BTreeMap;
+
+use sha2::{Digest, Sha256};
+
+use crate::convenience::PaperAgeError;
+use crate::lang::{self, Language};
+
+/// Prefix of the header line of each part
+pub const PART_PREFIX: &str = "PAPERAGE PART";
+
+/// Number of PEM lines in each part
+pub const LINES_PER_PART: usize = 14;
+
+/// A part of a PEM encoded ciphertext
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Part {
+    /// Index of the part, starting from 1
+    pub index: usize,
+    /// Total number of parts
+    pub total: usize,
+    /// Content hash of the whole ciphertext
+    pub hash: String,
+    /// The PEM lines in this part
+    pub text: String,
+}
+
+impl Part {
+    /// The text to encode in the QR code of this part
+    pub fn payload(&self) -> String {
+        format!(
+            "{PART_PREFIX} {}/{} {}\n{}",
+            self.index, self.total, self.hash, self.text
+        )
+    }
+
+    /// Human readable label for this part
+    pub fn label(&self) -> String {
+        self.label_in(Language::En)
+    }
+
+    /// Human readable label for this part in the language
+    pub fn label_in(&self, language: Language) -> String {
+        lang::fill(
+            language.catalog().part_label,
+            &[
+                ("index", &self.index),
+                ("total", &self.total),
+                ("hash", &self.hash),
+            ],
+        )
+    }
+}
+
+/// Short content hash of the PEM encoded ciphertext
+pub fn content_hash(pem: &str) -> String {
+    let digest = Sha256::digest(pem.as_bytes());
+    digest[..4].iter().map(|b| format!("{b:02x}")).collect()
+}
+
+/// Split the PEM encoded ciphertext into parts of `lines_per_part` lines
+pub fn split(pem: &str, lines_per_part: usize) -> Vec<Part> {
+    let hash = content_hash(pem);
+    let lines: Vec<&str> = pem.lines().collect();
+    let chunks: Vec<&[&str]> = lines.chunks(lines_per_part.max(1)).collect();
+    let total = chunks.len();
+
+    chunks
+        .iter()
+        .enumerate()
+        .map(|(i, chunk)| Part {
+            index: i + 1,
+            total,
+            hash: hash.clone(),
+            text: chunk.iter().map(|line| format!("{line}\n")).collect(),
+        })
+        .collect()
+}
+
+/// Whether the text contains parts of a split ciphertext
+pub fn is_split(text: &str) -> bool {
+    text.lines()
+        .any(|line| line.trim_start().starts_with(PART_PREFIX))
+}
+
+/// Merge the texts that contain parts of the same split ciphertext, e.g. the
+/// scans of each page, so that they can be joined. Other texts are kept as is.
+pub fn group_parts(texts: Vec<String>) -> Vec<String> {
+    let mut groups: Vec<(Option<String>, String)> = vec![];
+
+    for text in texts {
+        let hash = text.lines().find_map(|line| {
+            let fields = line.trim().strip_prefix(PART_PREFIX)?;
+            parse_header(fields).ok().map(|(_, _, hash)| hash)
+        });
+
+        match groups
+            .iter_mut()
+            .find(|(h, _)| hash.is_some() && *h == hash)
+        {
+            Some((_, group)) => {
+                group.push('\n');
+                group.push_str(&text);
+            }
+            None => groups.push((hash, text)),
+        }
+    }
+
+    groups.into_iter().map(|(_, text)| text).collect()
+}
+
+/// Join the scanned parts of a split ciphertext back together. The parts can
+/// be in any order and duplicates are ignored, but every part must be present.
+pub fn join(text: &str) -> Result<String, PaperAgeError> {
+    let mut parts: BTreeMap<usize, String> = BTreeMap::new();
+    let mut header: Option<(usize, String)> = None;
+    let mut current: Option<usize> = None;
+
+    for line in text.lines() {
+        let line = line.trim();
+
+        if let Some(fields) = line.strip_prefix(PART_PREFIX) {
+            let (index, total, hash) = parse_header(fields)?;
+
+            match &header {
+                Some((t, h)) if *t != total || *h != hash => {
+                    return Err(PaperAgeError::InvalidCiphertext(format!(
+                        "part {index}/{total} ({hash}) is from a different backup"
+                    )));
+                }
+                _ => header = Some((total, hash)),
+            }
+
+            // A part scanned twice replaces the earlier scan
+            parts.insert(index, String::new());
+            current = Some(index);
+        } else if let Some(index) = current {
+            if !line.is_empty() {
+                let part = parts.entry(index).or_default();
+                part.push_str(line);
+                part.push('\n');
+            }
+        }
+    }
+
+    let (total, hash) = header
+        .ok_or_else(|| PaperAgeError::InvalidCiphertext("no PaperAge parts found".to_string()))?;
+
+    let missing: Vec<String> = (1..=total)
+        .filter(|i| !parts.contains_key(i))
+        .map(|i| i.to_string())
+        .collect();
+    if !missing.is_empty() {
+        return Err(PaperAgeError::InvalidCiphertext(format!(
+            "missing part(s) {} of {total}",
+            missing.join(", ")
+        )));
+    }
+
+    let pem: String = parts.into_values().collect();
+
+    if content_hash(&pem) != hash {
+        return Err(PaperAgeError::InvalidCiphertext(format!(
+            "the joined parts don't match the content hash {hash}"
+        )));
+    }
+
+    Ok(pem)
+}
+
+/// Parse the `1/3 1a2b3c4d` fields of a part header
+fn parse_header(fields: &str) -> Result<(usize, usize, String), PaperAgeError> {
+    let invalid = || PaperAgeError::InvalidCiphertext(format!("invalid part header: {fields}"));
+
+    let mut words = fields.split_whitespace();
+    let (index, total) = words
+        .next()
+        .and_then(|w| w.split_once('/'))
+        .ok_or_else(invalid)?;
+    let index: usize = index.parse().map_err(|_| invalid())?;
+    let total: usize = total.parse().map_err(|_| invalid())?;
+    let hash = words.next().ok_or_else(invalid)?.to_lowercase();
+
+    if index == 0 || index > total {
+        return Err(invalid());
+    }
+
+    Ok((index, total, hash))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pem(lines: usize) -> String {
+        let mut pem = String::from("-----BEGIN AGE ENCRYPTED FILE-----\n");
+        for i in 0..lines {
+            pem.push_str(&format!("{:064}\n", i));
+        }
+        pem.push_str("-----END AGE ENCRYPTED FILE-----\n");
+        pem
+    }
+
+    #[test]
+    fn test_split() {
+        let parts = split(&pem(30), 10);
+
+        assert_eq!(parts.len(), 4);
+        assert!(parts.iter().all(|p| p.total == 4));
+        assert_eq!(parts[0].text.lines().count(), 10);
+        assert_eq!(parts[3].text.lines().count(), 2);
+        assert_eq!(parts[0].label(), format!("Part 1 of 4 · {}", parts[0].hash));
+    }
+
+    #[test]
+    fn test_payload() {
+        let parts = split(&pem(1), 10);
+        let payload = parts[0].payload();
+
+        assert!(payload.starts_with(&format!("PAPERAGE PART 1/1 {}\n", parts[0].hash)));
+        assert!(payload.ends_with("-----END AGE ENCRYPTED FILE-----\n"));
+    }
+
+    #[test]
+    fn test_join_in_any_order() {
+        let pem = pem(30);
+        let parts = split(&pem, 10);
+        let scanned = [&parts[2], &parts[0], &parts[3], &parts[1], &parts[0]]
+            .iter()
+            .map(|p| p.payload())
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        assert!(is_split(&scanned));
+        assert_eq!(join(&scanned).unwrap(), pem);
+    }
+
+    #[test]
+    fn test_join_missing_part() {
+        let parts = split(&pem(30), 10);
+        let scanned = format!("{}{}", parts[0].payload(), parts[2].payload());
+
+        let error = join(&scanned).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Invalid ciphertext: missing part(s) 2, 4 of 4"
+        );
+    }
+
+    #[test]
+    fn test_join_mixed_backups() {
+        let first = split(&pem(30), 10);
+        let second = split(&pem(31), 10);
+        let scanned = format!("{}{}", first[0].payload(), second[1].payload());
+
+        assert!(matches!(
+            join(&scanned),
+            Err(PaperAgeError::InvalidCiphertext(_))
+        ));
+    }
+
+    #[test]
+    fn test_join_damaged_part() {
+        let parts = split(&pem(30), 10);
+        let scanned = parts
+            .iter()
+            .map(|p| p.payload())
+            .collect::<String>()
+            .replace(
+                "0000000000000000000000000000000000000000000000000000000000000012",
+                "x",
+            );
+
+        let error = join(&scanned).unwrap_err();
+        assert!(error.to_string().contains("content hash"));
+    }
+
+    #[test]
+    fn test_group_parts() {
+        let first = split(&pem(30), 10);
+        let second = split(&pem(31), 10);
+        let texts = vec![
+            first[0].payload(),
+            second[0].payload(),
+            pem(3),
+            format!("{}{}", first[1].payload(), first[3].payload()),
+            second[1].payload(),
+            first[2].payload(),
+            second[2].payload(),
+            second[3].payload(),
+        ];
+
+        let groups = group_parts(texts);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(join(&groups[0]).unwrap(), pem(30));
+        assert_eq!(join(&groups[1]).unwrap(), pem(31));
+        assert_eq!(groups[2], pem(3));
+    }
+
+    #[test]
+    fn test_not_split() {
+        assert!(!is_split(&pem(3)));
+        assert!(join(&pem(3)).is_err());
+    }
+}
@@ -0,0 +1,423 @@
+//! Verify that a generated PDF or image can be restored
+//!
+//! The PDF is parsed back, the QR code module matrices are reconstructed from
+//! the filled polygons on each page, and the decoded QR codes are compared to
+//! the PEM encoded ciphertext. With a passphrase, the decoded ciphertext is
+//! also decrypted and compared to the plaintext. Images are scanned for QR
+//! codes like in `restore`.
+use log::{debug, info};
+use printpdf::{Color, Op, PaintMode, PdfDocument, PdfParseOptions, Polygon};
+use rqrr::BitGrid;
+
+use crate::convenience::PaperAgeError;
+use crate::encryption::{self, EncryptionMode};
+use crate::{scan, split};
+
+/// Maximum distance in points between the polygons of the same QR code. The
+/// QR codes of split ciphertexts are 10 mm (~28 pt) apart.
+const CLUSTER_TOLERANCE: f32 = 8.0;
+
+/// Decode the QR codes in the PDF, page by page, from the top left
+pub fn decode_qr_codes(pdf: &[u8]) -> Result<Vec<String>, PaperAgeError> {
+    let mut warnings = vec![];
+    let doc = PdfDocument::parse(pdf, &PdfParseOptions::default(), &mut warnings)
+        .map_err(|e| PaperAgeError::Verification(format!("the PDF could not be parsed: {e}")))?;
+
+    let mut decoded = vec![];
+    for (page_index, page) in doc.pages.iter().enumerate() {
+        let mut codes = cluster(filled_polygons(&page.ops));
+        // Top to bottom, then left to right
+        codes.sort_by(|a, b| {
+            (-a.bounds.max_y.round(), a.bounds.min_x)
+                .partial_cmp(&(-b.bounds.max_y.round(), b.bounds.min_x))
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+
+        for (code_index, code) in codes.iter().enumerate() {
+            let matrix = code.module_matrix().ok_or_else(|| {
+                PaperAgeError::Verification(format!(
+                    "QR code {} on page {} has an invalid size",
+                    code_index + 1,
+                    page_index + 1
+                ))
+            })?;
+            debug!(
+                "Reconstructed a {0}×{0} QR code on page {1}",
+                matrix.size,
+                page_index + 1
+            );
+
+            let (_, text) = rqrr::Grid::new(matrix).decode().map_err(|e| {
+                PaperAgeError::Verification(format!(
+                    "QR code {} on page {} could not be decoded: {e}",
+                    code_index + 1,
+                    page_index + 1
+                ))
+            })?;
+            decoded.push(text);
+        }
+    }
+
+    Ok(decoded)
+}
+
+/// Verify that the QR codes in the PDF decode to the PEM encoded ciphertext,
+/// and that the ciphertext decrypts to the plaintext when encrypted with a
+/// passphrase
+pub fn verify(
+    pdf: &[u8],
+    encrypted: &str,
+    mode: &EncryptionMode,
+    plaintext: &[u8],
+) -> Result<(), PaperAgeError> {
+    let decoded = decode_qr_codes(pdf)?;
+    info!("Decoded {} QR code(s) from the PDF", decoded.len());
+
+    if decoded.is_empty() {
+        return Err(PaperAgeError::Verification(
+            "no QR codes found in the PDF".to_string(),
+        ));
+    }
+
+    check_decoded(decoded, encrypted, mode, plaintext)
+}
+
+/// Verify that the QR codes in the image decode to the PEM encoded
+/// ciphertext, and that the ciphertext decrypts to the plaintext when
+/// encrypted with a passphrase
+pub fn verify_image(
+    image: &[u8],
+    encrypted: &str,
+    mode: &EncryptionMode,
+    plaintext: &[u8],
+) -> Result<(), PaperAgeError> {
+    let decoded = scan::decode_image(image).map_err(|e| match e {
+        PaperAgeError::InvalidCiphertext(msg) => PaperAgeError::Verification(msg),
+        e => e,
+    })?;
+    info!("Decoded {} QR code(s) from the image", decoded.len());
+
+    check_decoded(decoded, encrypted, mode, plaintext)
+}
+
+/// Compare the decoded QR codes to the ciphertext and the plaintext
+fn check_decoded(
+    decoded: Vec<String>,
+    encrypted: &str,
+    mode: &EncryptionMode,
+    plaintext: &[u8],
+) -> Result<(), PaperAgeError> {
+    // Copies of the same QR code, e.g. on wallet cards, only count once
+    let mut unique: Vec<String> = vec![];
+    for text in decoded {
+        if !unique.contains(&text) {
+            unique.push(text);
+        }
+    }
+    let decoded = unique;
+
+    let text = match decoded.len() {
+        1 if !split::is_split(&decoded[0]) => decoded[0].clone(),
+        _ => split::join(&decoded.concat()).map_err(|e| {
+            PaperAgeError::Verification(format!("the QR codes could not be joined: {e}"))
+        })?,
+    };
+
+    if text != encrypted {
+        return Err(PaperAgeError::Verification(
+            "the decoded QR code doesn't match the ciphertext".to_string(),
+        ));
+    }
+    info!("The decoded QR code matches the ciphertext");
+
+    match mode {
+        EncryptionMode::Passphrase(passphrase) => {
+            let decrypted =
+                encryption::decrypt_ciphertext(&mut text.as_bytes(), passphrase.clone()).map_err(
+                    |e| {
+                        PaperAgeError::Verification(format!(
+                            "the decoded ciphertext could not be decrypted: {e}"
+                        ))
+                    },
+                )?;
+
+            if decrypted != plaintext {
+                return Err(PaperAgeError::Verification(
+                    "the decrypted ciphertext doesn't match the plaintext".to_string(),
+                ));
+            }
+            info!("The decoded ciphertext decrypts to the plaintext");
+        }
+        EncryptionMode::Recipients(_) => {
+            info!("Skipping decryption, the ciphertext is encrypted to recipients");
+        }
+    }
+
+    Ok(())
+}
+
+/// Bounding box in points
+#[derive(Clone, Copy, Debug)]
+struct Bounds {
+    min_x: f32,
+    min_y: f32,
+    max_x: f32,
+    max_y: f32,
+}
+
+impl Bounds {
+    fn of(rings: &[Vec<(f32, f32)>]) -> Bounds {
+        let points = rings.iter().flatten();
+        Bounds {
+            min_x: points.clone().map(|p| p.0).fold(f32::INFINITY, f32::min),
+            min_y: points.clone().map(|p| p.1).fold(f32::INFINITY, f32::min),
+            max_x: points
+                .clone()
+                .map(|p| p.0)
+                .fold(f32::NEG_INFINITY, f32::max),
+            max_y: points.map(|p| p.1).fold(f32::NEG_INFINITY, f32::max),
+        }
+    }
+
+    fn union(&self, other: &Bounds) -> Bounds {
+        Bounds {
+            min_x: self.min_x.min(other.min_x),
+            min_y: self.min_y.min(other.min_y),
+            max_x: self.max_x.max(other.max_x),
+            max_y: self.max_y.max(other.max_y),
+        }
+    }
+
+    fn near(&self, other: &Bounds, tolerance: f32) -> bool {
+        self.min_x - tolerance <= other.max_x
+            && other.min_x - tolerance <= self.max_x
+            && self.min_y - tolerance <= other.max_y
+            && other.min_y - tolerance <= self.max_y
+    }
+}
+
+/// The polygons of a single QR code
+struct PolygonCluster {
+    rings: Vec<Vec<(f32, f32)>>,
+    bounds: Bounds,
+}
+
+impl PolygonCluster {
+    /// Rebuild the module matrix by sampling the center of each module
+    fn module_matrix(&self) -> Option<ModuleMatrix> {
+        let edges: Vec<((f32, f32), (f32, f32))> = self
+            .rings
+            .iter()
+            .flat_map(|ring| ring.iter().zip(ring.iter().cycle().skip(1)))
+            .map(|(a, b)| (*a, *b))
+            .collect();
+
+        // The timing patterns guarantee that the shortest edge is a single module
+        let module = edges
+            .iter()
+            .map(|(a, b)| (a.0 - b.0).abs().max((a.1 - b.1).abs()))
+            .filter(|length| *length > 0.01)
+            .fold(f32::INFINITY, f32::min);
+
+        let width = self.bounds.max_x - self.bounds.min_x;
+        let size = (width / module).round() as usize;
+        if size < 21 || (size - 17) % 4 != 0 {
+            return None;
+        }
+        let module = width / size as f32;
+
+        let mut modules = vec![false; size * size];
+        for row in 0..size {
+            let y = self.bounds.max_y - (row as f32 + 0.5) * module;
+
+            // The polygons only have horizontal and vertical edges, so a
+            // module is dark if an odd number of vertical edges cross the row
+            // to its left (even-odd fill rule)
+            let mut crossings: Vec<f32> = edges
+                .iter()
+                .filter(|(a, b)| (a.1 > y) != (b.1 > y))
+                .map(|(a, _)| a.0)
+                .collect();
+            crossings.sort_by(|a, b| a.total_cmp(b));
+
+            for col in 0..size {
+                let x = self.bounds.min_x + (col as f32 + 0.5) * module;
+                let count = crossings.iter().take_while(|&&cx| cx < x).count();
+                modules[row * size + col] = count % 2 == 1;
+            }
+        }
+
+        Some(ModuleMatrix { size, modules })
+    }
+}
+
+/// The dark and light modules of a QR code
+struct ModuleMatrix {
+    size: usize,
+    modules: Vec<bool>,
+}
+
+impl BitGrid for ModuleMatrix {
+    fn size(&self) -> usize {
+        self.size
+    }
+
+    fn bit(&self, y: usize, x: usize) -> bool {
+        self.modules[y * self.size + x]
+    }
+}
+
+/// The rings of the polygons filled with a dark color
+fn filled_polygons(ops: &[Op]) -> Vec<Vec<Vec<(f32, f32)>>> {
+    let mut dark = false;
+    let mut polygons = vec![];
+
+    for op in ops {
+        match op {
+            Op::SetFillColor { col } => dark = is_dark(col),
+            Op::DrawPolygon {
+                polygon: Polygon { rings, mode, .. },
+            } if dark && matches!(mode, PaintMode::Fill | PaintMode::FillStroke) => {
+                polygons.push(
+                    rings
+                        .iter()
+                        .map(|ring| ring.points.iter().map(|lp| (lp.p.x.0, lp.p.y.0)).collect())
+                        .collect(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    polygons
+}
+
+/// Group nearby polygons into QR codes
+fn cluster(polygons: Vec<Vec<Vec<(f32, f32)>>>) -> Vec<PolygonCluster> {
+    let mut clusters: Vec<PolygonCluster> = vec![];
+
+    for rings in polygons {
+        let mut cluster = PolygonCluster {
+            bounds: Bounds::of(&rings),
+            rings,
+        };
+
+        // Merge every cluster that is near the new one, which may in turn
+        // bring other clusters within reach
+        loop {
+            let (near, far): (Vec<_>, Vec<_>) = clusters
+                .into_iter()
+                .partition(|c| c.bounds.near(&cluster.bounds, CLUSTER_TOLERANCE));
+            clusters = far;
+            if near.is_empty() {
+                break;
+            }
+            for other in near {
+                cluster.bounds = cluster.bounds.union(&other.bounds);
+                cluster.rings.extend(other.rings);
+            }
+        }
+
+        clusters.push(cluster);
+    }
+
+    clusters
+}
+
+fn is_dark(color: &Color) -> bool {
+    match color {
+        Color::Rgb(rgb) => rgb.r + rgb.g + rgb.b < 1.5,
+        Color::Greyscale(grey) => grey.percent < 0.5,
+        Color::Cmyk(cmyk) => cmyk.k > 0.5,
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::Document;
+    use crate::page::PageSize;
+
+    fn create_pdf(plaintext: &[u8], mode: &EncryptionMode, split: bool) -> (Vec<u8>, String) {
+        let (_, encrypted) = encryption::encrypt(&mut &plaintext[..], mode).unwrap();
+        let mut document = Document::new(String::from("Verify"), PageSize::A4).unwrap();
+        document.split = split;
+        let pdf = document
+            .render(false, None, false, encrypted.clone(), mode)
+            .unwrap();
+        (pdf, encrypted)
+    }
+
+    #[test]
+    fn test_decode_qr_codes() {
+        let mode = EncryptionMode::from("snakeoil");
+        let (pdf, encrypted) = create_pdf(b"Hello", &mode, false);
+
+        assert_eq!(decode_qr_codes(&pdf).unwrap(), vec![encrypted]);
+    }
+
+    #[test]
+    fn test_verify() {
+        let mode = EncryptionMode::from("snakeoil");
+        let (pdf, encrypted) = create_pdf(b"Hello", &mode, false);
+
+        assert!(verify(&pdf, &encrypted, &mode, b"Hello").is_ok());
+    }
+
+    #[test]
+    fn test_verify_split() {
+        let mode = EncryptionMode::from("snakeoil");
+        let plaintext = "x".repeat(4000);
+        let (pdf, encrypted) = create_pdf(plaintext.as_bytes(), &mode, true);
+
+        assert!(decode_qr_codes(&pdf).unwrap().len() > 2);
+        assert!(verify(&pdf, &encrypted, &mode, plaintext.as_bytes()).is_ok());
+    }
+
+    #[test]
+    fn test_verify_mismatch() {
+        let mode = EncryptionMode::from("snakeoil");
+        let (pdf, _) = create_pdf(b"Hello", &mode, false);
+        let (_, other) = create_pdf(b"Hello", &mode, false);
+
+        let error = verify(&pdf, &other, &mode, b"Hello").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "Verification failed: the decoded QR code doesn't match the ciphertext"
+        );
+    }
+
+    #[test]
+    fn test_verify_wrong_plaintext() {
+        let mode = EncryptionMode::from("snakeoil");
+        let (pdf, encrypted) = create_pdf(b"Hello", &mode, false);
+
+        let error = verify(&pdf, &encrypted, &mode, b"Goodbye").unwrap_err();
+        assert!(error.to_string().contains("doesn't match the plaintext"));
+    }
+
+    #[test]
+    fn test_verify_image() {
+        let mode = EncryptionMode::from("snakeoil");
+        let (_, encrypted) = encryption::encrypt(&mut &b"Hello"[..], &mode).unwrap();
+        let mut document = Document::new(String::from("Verify"), PageSize::A4).unwrap();
+        document.format = crate::format::Format::Png;
+        document.dpi = 150;
+        let png = document
+            .render(false, None, false, encrypted.clone(), &mode)
+            .unwrap();
+
+        assert!(verify_image(&png, &encrypted, &mode, b"Hello").is_ok());
+        assert!(verify_image(&png, &encrypted, &mode, b"Goodbye").is_err());
+    }
+
+    #[test]
+    fn test_verify_invalid_pdf() {
+        let mode = EncryptionMode::from("snakeoil");
+        assert!(matches!(
+            verify(b"not a PDF", "", &mode, b""),
+            Err(PaperAgeError::Verification(_))
+        ));
+    }
+}
@@ -45,6 +45,133 @@ fn test_letter_support() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_page_sizes() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+
+    for size in ["a5", "index-card", "custom:120x180mm"] {
+        let output = temp.child("sized.pdf");
+        let mut cmd = cargo_bin_cmd!("paper-age");
+
+        cmd.arg("--force")
+            .arg("--output")
+            .arg(output.path())
+            .arg("--page-size")
+            .arg(size)
+            .arg(input.path())
+            .env("PAPERAGE_PASSPHRASE", "secret");
+        cmd.assert().success();
+
+        output.assert(predicate::path::is_file());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_landscape() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("landscape.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--output")
+        .arg(output.path())
+        .arg("--orientation")
+        .arg("landscape")
+        .arg("--verify")
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn test_copies() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("copies.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--output")
+        .arg(output.path())
+        .arg("--copies")
+        .arg("2")
+        .arg("--verify")
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn test_wallet_card() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("JBSWY3DPEHPK3PXP")?;
+    let output = temp.child("cards.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--output")
+        .arg(output.path())
+        .arg("--wallet-card")
+        .arg("--verify")
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn test_wallet_card_too_large() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str(&"a".repeat(1024))?;
+    let output = temp.child("cards.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--output")
+        .arg(output.path())
+        .arg("--wallet-card")
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("for a wallet card"));
+
+    output.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_page_size() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--page-size")
+        .arg("custom:10x10mm")
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().failure().stderr(predicate::str::contains(
+        "custom page sides must be between",
+    ));
+
+    Ok(())
+}
+
 #[test]
 fn test_stdout() -> Result<(), Box<dyn std::error::Error>> {
     let temp = assert_fs::TempDir::new().unwrap();
@@ -104,6 +231,146 @@ fn test_too_much_data() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_split() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("x".repeat(5000).as_str())?;
+    let output = temp.child("output.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--split")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_line_checksums() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let output = temp.child("output.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--line-checksums")
+        .arg("--output")
+        .arg(output.path())
+        .write_stdin("Hello")
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_verify() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("output.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--verify")
+        .arg("-vv")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success().stderr(predicate::str::contains(
+        "Verified that the PDF can be restored",
+    ));
+
+    output.assert(predicate::path::exists());
+
+    Ok(())
+}
+
+#[test]
+fn test_svg() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("output.svg");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--format")
+        .arg("svg")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::str::starts_with("<?xml"));
+    output.assert(predicate::str::contains(
+        "-----BEGIN AGE ENCRYPTED FILE-----",
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_svg_verify() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("output.svg");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--format")
+        .arg("svg")
+        .arg("--verify")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().failure().code(64);
+
+    output.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn test_png() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("output.png");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--format")
+        .arg("png")
+        .arg("--dpi")
+        .arg("150")
+        .arg("--verify")
+        .arg("-vv")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success().stderr(predicate::str::contains(
+        "Verified that the image can be restored",
+    ));
+
+    output.assert(predicate::path::is_file());
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("restore")
+        .arg(output.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success().stdout("Hello");
+
+    Ok(())
+}
+
 #[test]
 fn test_fonts_license() -> Result<(), Box<dyn std::error::Error>> {
     let mut cmd = cargo_bin_cmd!("paper-age");
@@ -116,6 +383,68 @@ fn test_fonts_license() -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+#[test]
+fn test_instructions() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("instructions.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--instructions")
+        .arg("--verify")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    let pdf = std::fs::read(output.path())?;
+    assert_eq!(paper_age::verify::decode_qr_codes(&pdf)?.len(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_user_fonts() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("fonts.pdf");
+    let font = "src/assets/fonts/IBMPlexMono-Medium.ttf";
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--title-font")
+        .arg(font)
+        .arg("--code-font")
+        .arg(font)
+        .arg("--verify")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn test_user_fonts_license() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--fonts-license")
+        .arg("--code-font")
+        .arg("src/assets/fonts/IBMPlexMono-Medium.ttf");
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("SIL OPEN FONT LICENSE"))
+        .stdout(predicate::str::contains("The code font:\nIBM Plex Mono"));
+
+    Ok(())
+}
+
 #[test]
 fn test_title_too_long() -> Result<(), Box<dyn std::error::Error>> {
     let input = assert_fs::NamedTempFile::new("sample.txt")?;
@@ -128,3 +457,795 @@ fn test_title_too_long() -> Result<(), Box<dyn std::error::Error>> {
 
     Ok(())
 }
+
+#[test]
+fn test_unicode_title() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("unicode.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    // 64 characters, but 128 bytes
+    cmd.arg("--title")
+        .arg("ä".repeat(64))
+        .arg("--notes-label")
+        .arg("Pääsyavain – Tuotanto:")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn test_notes_label_too_long() -> Result<(), Box<dyn std::error::Error>> {
+    let input = assert_fs::NamedTempFile::new("sample.txt")?;
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--notes-label")
+        .arg("x".repeat(33))
+        .arg(input.path());
+    cmd.assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "The notes label cannot be longer than",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_title_missing_glyphs() -> Result<(), Box<dyn std::error::Error>> {
+    let input = assert_fs::NamedTempFile::new("sample.txt")?;
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--title").arg("秘密の鍵").arg(input.path());
+    cmd.assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("--fallback-font"));
+
+    Ok(())
+}
+
+#[test]
+fn test_lang() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("lang.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--lang")
+        .arg("de")
+        .arg("--instructions")
+        .arg("--verify")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    Ok(())
+}
+
+#[test]
+fn test_lang_missing_glyphs() -> Result<(), Box<dyn std::error::Error>> {
+    let input = assert_fs::NamedTempFile::new("sample.txt")?;
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--lang").arg("ja").arg(input.path());
+    cmd.assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("--lang ja"))
+        .stderr(predicate::str::contains("--fallback-font"));
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_fallback_font() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let font = temp.child("font.ttf");
+    font.write_str("not a font")?;
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--fallback-font")
+        .arg(font.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains(
+            "Failed to parse the fallback font",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_restore() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+    let (_, encrypted) = paper_age::encryption::encrypt_plaintext(&mut &b"Hello"[..], passphrase)?;
+    let input = temp.child("ciphertext.txt");
+    input.write_str(&encrypted)?;
+    let output = temp.child("plaintext.txt");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("restore")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert("Hello");
+
+    Ok(())
+}
+
+#[test]
+fn test_metadata() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("metadata.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--metadata=date,sheet-id")
+        .arg("-vv")
+        .arg("--verify")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Sheet ID: "));
+
+    Ok(())
+}
+
+#[test]
+#[cfg(feature = "insecure-deterministic")]
+fn test_insecure_deterministic() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+
+    let mut outputs = vec![];
+    for name in ["first.pdf", "second.pdf"] {
+        let output = temp.child(name);
+        let mut cmd = cargo_bin_cmd!("paper-age");
+
+        cmd.arg("--insecure-deterministic")
+            .arg("42")
+            .arg("--metadata")
+            .arg("--verify")
+            .arg("--output")
+            .arg(output.path())
+            .arg(input.path())
+            .env("PAPERAGE_PASSPHRASE", "secret")
+            .env("SOURCE_DATE_EPOCH", "1700000000");
+        cmd.assert().success();
+
+        outputs.push(std::fs::read(output.path())?);
+    }
+
+    assert_eq!(outputs[0], outputs[1]);
+    let pdf = String::from_utf8_lossy(&outputs[0]);
+    assert!(pdf.contains("/CreationDate(D:20231114221320"));
+
+    Ok(())
+}
+
+#[test]
+fn test_default_config() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("config.pdf");
+    temp.child("paper-age/config.toml")
+        .write_str(&format!("title = \"{}\"\n", "x".repeat(65)))?;
+
+    // The title from the config file is too long...
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("The title cannot be longer"));
+
+    // ...but the command line overrides it
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("--title")
+        .arg("Mine")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("XDG_CONFIG_HOME", temp.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    Ok(())
+}
+
+#[test]
+fn test_config_profile() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("profile.pdf");
+    let config = temp.child("team.toml");
+    config.write_str(
+        "page-size = \"letter\"\n\n[profile.prod-root]\ntitle = \"Production root key\"\nrecipient = [\"age1ql3z7hjy54pw3hyww5ayyfg7zqgvc7w3j2elw8zmrj2kg5sfn9aqmcac8p\"]\n",
+    )?;
+
+    // Encrypts to the recipient of the profile without asking for a passphrase
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("--config")
+        .arg(config.path())
+        .arg("--profile")
+        .arg("prod-root")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path());
+    cmd.assert().success();
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("--config")
+        .arg(config.path())
+        .arg("--profile")
+        .arg("staging")
+        .arg(input.path());
+    cmd.assert()
+        .failure()
+        .code(78)
+        .stderr(predicate::str::contains("There is no profile staging"));
+
+    Ok(())
+}
+
+#[test]
+fn test_missing_config() -> Result<(), Box<dyn std::error::Error>> {
+    let input = assert_fs::NamedTempFile::new("sample.txt")?;
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--config")
+        .arg("does-not-exist.toml")
+        .arg(input.path());
+    cmd.assert()
+        .failure()
+        .code(78)
+        .stderr(predicate::str::contains(
+            "Failed to read the config file does-not-exist.toml",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_batch() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("db.txt").write_str("database")?;
+    temp.child("api.txt").write_str("api key")?;
+    let db = temp.child("db.pdf");
+    let api = temp.child("api.pdf");
+    let manifest = temp.child("sheets.toml");
+    manifest.write_str(&format!(
+        "page-size = \"letter\"\n\n\
+         [[sheet]]\ninput = {:?}\noutput = {:?}\ntitle = \"Database\"\n\n\
+         [[sheet]]\ninput = {:?}\noutput = {:?}\ntitle = \"API key\"\npassphrase-env = \"API_PASSPHRASE\"\n",
+        temp.child("db.txt").path(),
+        db.path(),
+        temp.child("api.txt").path(),
+        api.path(),
+    ))?;
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("batch")
+        .arg(manifest.path())
+        .env("PAPERAGE_PASSPHRASE", "secret")
+        .env("API_PASSPHRASE", "other");
+    cmd.assert().success();
+
+    db.assert(predicate::path::is_file());
+    api.assert(predicate::path::is_file());
+
+    // The outputs exist now
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("batch")
+        .arg(manifest.path())
+        .env("PAPERAGE_PASSPHRASE", "secret")
+        .env("API_PASSPHRASE", "other");
+    cmd.assert().failure().code(73);
+
+    Ok(())
+}
+
+#[test]
+fn test_batch_relative_paths() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("ops/db.txt").write_str("database")?;
+    let output = temp.child("ops/sheets/db.pdf");
+    temp.child("ops/sheets").create_dir_all()?;
+    let manifest = temp.child("ops/q3.toml");
+    manifest.write_str("[[sheet]]\ninput = \"db.txt\"\noutput = \"sheets/db.pdf\"\n")?;
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.current_dir(temp.path())
+        .arg("batch")
+        .arg("ops/q3.toml")
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn test_batch_combined() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    temp.child("db.txt").write_str("database")?;
+    temp.child("api.txt").write_str("api key")?;
+    let output = temp.child("all.pdf");
+    let manifest = temp.child("sheets.toml");
+    manifest.write_str(&format!(
+        "[[sheet]]\ninput = {:?}\ntitle = \"Database\"\n\n\
+         [[sheet]]\ninput = {:?}\ntitle = \"API key\"\n",
+        temp.child("db.txt").path(),
+        temp.child("api.txt").path(),
+    ))?;
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("batch")
+        .arg("--combined")
+        .arg(output.path())
+        .arg(manifest.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    let pdf = std::fs::read(output.path())?;
+    let texts = paper_age::verify::decode_qr_codes(&pdf)?;
+    assert_eq!(texts.len(), 2);
+    let mut plaintexts = vec![];
+    for text in texts {
+        let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+        let plaintext =
+            paper_age::encryption::decrypt_ciphertext(&mut text.as_bytes(), passphrase)?;
+        plaintexts.push(String::from_utf8(plaintext)?);
+    }
+    plaintexts.sort();
+    assert_eq!(plaintexts, vec!["api key", "database"]);
+
+    // Without --combined, every sheet needs an output file
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("batch")
+        .arg(manifest.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .failure()
+        .code(64)
+        .stderr(predicate::str::contains(
+            "Sheet 1: The sheet has no output file",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_batch_invalid_manifest() -> Result<(), Box<dyn std::error::Error>> {
+    let manifest = assert_fs::NamedTempFile::new("sheets.toml")?;
+    manifest.write_str("[[sheet]]\ninput = \"a.txt\"\ncolour = \"blue\"\n")?;
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("batch").arg(manifest.path());
+    cmd.assert()
+        .failure()
+        .code(65)
+        .stderr(predicate::str::contains("Sheet 1: Unknown option: colour"));
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("batch").arg("does-not-exist.toml");
+    cmd.assert().failure().code(66);
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_sha256() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+    let (_, encrypted) = paper_age::encryption::encrypt_plaintext(&mut &b"Hello"[..], passphrase)?;
+    let input = temp.child("ciphertext.txt");
+    input.write_str(&encrypted)?;
+    let output = temp.child("plaintext.txt");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("restore")
+        .arg("-vv")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success().stderr(predicate::str::contains(
+        paper_age::encryption::ciphertext_sha256(&encrypted),
+    ));
+
+    Ok(())
+}
+
+#[test]
+fn test_shares() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("output.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--shares")
+        .arg("3")
+        .arg("--threshold")
+        .arg("2")
+        .arg("--output")
+        .arg(output.path())
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success();
+
+    output.assert(predicate::path::missing());
+    for index in 1..=3 {
+        temp.child(format!("output-share-{index}.pdf"))
+            .assert(predicate::path::exists());
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_shares_to_stdout() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.args(["--shares", "3", "--threshold", "2", "--output", "-"])
+        .write_stdin("Hello")
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Can't write 3 shares to STDOUT"));
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_shares() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let shares = paper_age::shamir::split(b"Hello", 2, 3, &mut rand::thread_rng())?;
+    for share in &shares {
+        let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+        let (_, encrypted) =
+            paper_age::encryption::encrypt_plaintext(&mut share.to_bytes().as_slice(), passphrase)?;
+        temp.child(format!("share-{}.txt", share.index))
+            .write_str(&encrypted)?;
+    }
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("restore")
+        .arg(temp.child("share-3.txt").path())
+        .arg(temp.child("share-1.txt").path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success().stdout("Hello");
+
+    // A single share is restored as is, with a warning
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("restore")
+        .arg("-v")
+        .arg(temp.child("share-2.txt").path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .success()
+        .stdout(predicate::function(|out: &[u8]| {
+            out.starts_with(b"PAPERAGE SHARE 2/3")
+        }))
+        .stderr(predicate::str::contains(
+            "1 share(s) given but 2 are needed",
+        ));
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_plaintext_like_a_share() -> Result<(), Box<dyn std::error::Error>> {
+    let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+    let plaintext = "PAPERAGE SHARE of the vault: hunter2\n";
+    let (_, encrypted) =
+        paper_age::encryption::encrypt_plaintext(&mut plaintext.as_bytes(), passphrase)?;
+    let input = assert_fs::NamedTempFile::new("ciphertext.txt")?;
+    input.write_str(&encrypted)?;
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("restore")
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success().stdout(plaintext);
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_line_checksums() -> Result<(), Box<dyn std::error::Error>> {
+    let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+    let (_, encrypted) = paper_age::encryption::encrypt_plaintext(&mut &b"Hello"[..], passphrase)?;
+    let annotated = paper_age::checksums::annotate(&encrypted);
+
+    // The first line always starts with "YWdlLW", so type the l as a 1
+    let typed = annotated.replacen("YWdlLW", "YWd1LW", 1);
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.args(["restore", "-v"])
+        .write_stdin(typed)
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .success()
+        .stdout("Hello")
+        .stderr(predicate::str::contains(
+            "Line 1, column 4: corrected 1 to l",
+        ));
+
+    // Drop the second line
+    let mut lines: Vec<&str> = annotated.lines().collect();
+    lines.remove(2);
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("restore")
+        .write_stdin(lines.join("\n"))
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("line(s) 2 are missing"));
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_stdin() -> Result<(), Box<dyn std::error::Error>> {
+    let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+    let (_, encrypted) = paper_age::encryption::encrypt_plaintext(&mut &b"Hello"[..], passphrase)?;
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("restore")
+        .write_stdin(encrypted)
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success().stdout("Hello");
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_incorrect_passphrase() -> Result<(), Box<dyn std::error::Error>> {
+    let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+    let (_, encrypted) = paper_age::encryption::encrypt_plaintext(&mut &b"Hello"[..], passphrase)?;
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("restore")
+        .write_stdin(encrypted)
+        .env("PAPERAGE_PASSPHRASE", "wrong");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Incorrect passphrase"));
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_invalid_ciphertext() -> Result<(), Box<dyn std::error::Error>> {
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("restore")
+        .write_stdin("Hello")
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("corrupted or incomplete"));
+
+    Ok(())
+}
+
+#[test]
+fn test_recipients() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let alice = age::x25519::Identity::generate().to_public().to_string();
+    let bob = age::x25519::Identity::generate().to_public().to_string();
+    let recipients_file = temp.child("recipients.txt");
+    recipients_file.write_str(&format!("# Bob\n{bob}\n"))?;
+    let output = temp.child("output.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--output")
+        .arg(output.path())
+        .arg("--recipient")
+        .arg(alice)
+        .arg("--recipients-file")
+        .arg(recipients_file.path())
+        .arg(input.path());
+    cmd.assert().success();
+
+    output.assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_recipient() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let output = temp.child("output.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--output")
+        .arg(output.path())
+        .arg("--recipient")
+        .arg("age1invalid")
+        .arg(input.path());
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("Invalid recipient: age1invalid"));
+
+    output.assert(predicate::path::missing());
+
+    Ok(())
+}
+
+#[test]
+fn test_ssh_recipients() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let authorized_keys = temp.child("authorized_keys");
+    authorized_keys.write_str(
+        "# Alice\nno-pty ssh-ed25519 AAAAC3NzaC1lZDI1NTE5AAAAIHsKLqeplhpW+uObz5dvMgjz1OxfM/XXUB+VHtZ6isGN alice@rust\n",
+    )?;
+    let output = temp.child("output.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--output")
+        .arg(output.path())
+        .arg("--ssh-recipient")
+        .arg(authorized_keys.path())
+        .arg("-vv")
+        .arg(input.path());
+    cmd.assert().success().stderr(predicate::str::contains(
+        "ssh-ed25519 SHA256:PYX4o9UfGwCwG76hFcLAEkmMS0PtIBtV9MNmVgW16Oc",
+    ));
+
+    output.assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+#[test]
+fn test_multiple_recipients() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("sample.txt");
+    input.write_str("Hello")?;
+    let recipients: Vec<String> = (0..5)
+        .map(|_| age::x25519::Identity::generate().to_public().to_string())
+        .collect();
+    let output = temp.child("output.pdf");
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("--output").arg(output.path()).arg("-vv");
+    for recipient in &recipients {
+        cmd.arg("--recipient").arg(recipient);
+    }
+    // Duplicates are only listed once
+    cmd.arg("--recipient").arg(&recipients[0]);
+    cmd.arg(input.path());
+    cmd.assert()
+        .success()
+        .stderr(predicate::str::contains("Encrypting to 5 recipient(s)"));
+
+    output.assert(predicate::path::is_file());
+
+    Ok(())
+}
+
+/// Render the text as a PNG image of a QR code with a quiet zone
+fn qr_png(text: &str) -> Vec<u8> {
+    let code = qrcode::QrCode::new(text).unwrap();
+    let modules = code.width() as u32;
+    let colors = code.to_colors();
+    let scale = 4;
+
+    let image = image::GrayImage::from_fn((modules + 8) * scale, (modules + 8) * scale, |x, y| {
+        let (col, row) = (x / scale, y / scale);
+        let dark = (4..modules + 4).contains(&col)
+            && (4..modules + 4).contains(&row)
+            && colors[((row - 4) * modules + col - 4) as usize] == qrcode::Color::Dark;
+        image::Luma([if dark { 0 } else { 255 }])
+    });
+
+    let mut bytes = vec![];
+    image
+        .write_to(
+            &mut std::io::Cursor::new(&mut bytes),
+            image::ImageFormat::Png,
+        )
+        .unwrap();
+    bytes
+}
+
+#[test]
+fn test_restore_image() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+    let (_, encrypted) = paper_age::encryption::encrypt_plaintext(&mut &b"Hello"[..], passphrase)?;
+    let input = temp.child("scan.png");
+    input.write_binary(&qr_png(&encrypted))?;
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("restore")
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert().success().stdout("Hello");
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_split_images() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let plaintext = "x".repeat(3000);
+    let passphrase = age::secrecy::SecretString::from("secret".to_owned());
+    let (_, encrypted) =
+        paper_age::encryption::encrypt_plaintext(&mut plaintext.as_bytes(), passphrase)?;
+
+    let mut cmd = cargo_bin_cmd!("paper-age");
+    cmd.arg("restore").env("PAPERAGE_PASSPHRASE", "secret");
+    for part in paper_age::split::split(&encrypted, paper_age::split::LINES_PER_PART) {
+        let input = temp.child(format!("part-{}.png", part.index));
+        input.write_binary(&qr_png(&part.payload()))?;
+        cmd.arg(input.path());
+    }
+    cmd.assert().success().stdout(plaintext);
+
+    Ok(())
+}
+
+#[test]
+fn test_restore_image_without_qr_code() -> Result<(), Box<dyn std::error::Error>> {
+    let temp = assert_fs::TempDir::new().unwrap();
+    let input = temp.child("blank.png");
+    let mut bytes = vec![];
+    image::GrayImage::new(100, 100).write_to(
+        &mut std::io::Cursor::new(&mut bytes),
+        image::ImageFormat::Png,
+    )?;
+    input.write_binary(&bytes)?;
+    let mut cmd = cargo_bin_cmd!("paper-age");
+
+    cmd.arg("restore")
+        .arg(input.path())
+        .env("PAPERAGE_PASSPHRASE", "secret");
+    cmd.assert()
+        .failure()
+        .stderr(predicate::str::contains("no QR codes found in the image"));
+
+    Ok(())
+}